@@ -0,0 +1,65 @@
+// Plain interpreter against the block cache, run with `cargo bench --bench interpreter`
+use std::time::{Duration, Instant};
+
+use chips_8::core::machine::Machine;
+use chips_8::core::ops::MyChips8;
+use chips_8::core::quirks::Quirks;
+use chips_8::core::random::XorShift;
+
+// Draws once and then sits in a one instruction JP loop, the worst case for the cache
+const IBM_LOGO: &[u8] = include_bytes!("../src/IBM_Logo.ch8");
+
+// Arithmetic, memory and a subroutine in a loop that never waits on the display or keypad
+const BUSY_LOOP: &[u8] = &[
+    0x60, 0x00, // 200: LD V0, 00
+    0xA3, 0x00, // 202: LD I, 300
+    0x70, 0x01, // 204: ADD V0, 01
+    0x81, 0x04, // 206: ADD V1, V0
+    0x82, 0x13, // 208: XOR V2, V1
+    0x83, 0x26, // 20A: SHR V3, V2
+    0xC4, 0xFF, // 20C: RND V4, FF
+    0xF3, 0x33, // 20E: LD B, V3
+    0xF2, 0x65, // 210: LD V2, [I]
+    0x22, 0x1A, // 212: CALL 21A
+    0x30, 0x00, // 214: SE V0, 00
+    0x12, 0x04, // 216: JP 204
+    0x12, 0x02, // 218: JP 202
+    0x85, 0x44, // 21A: ADD V5, V4
+    0x86, 0x57, // 21C: SUBN V6, V5
+    0x00, 0xEE, // 21E: RET
+];
+
+const FRAMES: u64 = 600;
+const SPEED: u64 = 1_000_000;
+
+fn run(rom: &[u8], cached: bool) -> (Duration, u64) {
+    let mut cpu = MyChips8::new_with_quirks(Quirks::chip48());
+    cpu.set_random_source(Box::new(XorShift::new(1)));
+    cpu.load_rom(rom).unwrap();
+    let mut machine = Machine::new_with_speed(cpu, SPEED);
+    machine.set_block_cache(cached);
+
+    let start = Instant::now();
+    let mut instructions = 0;
+    for _ in 0..FRAMES {
+        let outcome = machine.run_frame().unwrap();
+        instructions += outcome.instructions;
+        if outcome.exited {
+            break;
+        }
+    }
+    (start.elapsed(), instructions)
+}
+
+fn main() {
+    for (name, rom) in [("busy loop", BUSY_LOOP), ("IBM logo", IBM_LOGO)] {
+        let (plain, instructions) = run(rom, false);
+        let (cached, cached_instructions) = run(rom, true);
+        assert_eq!(instructions, cached_instructions, "{}: engines ran a different number of instructions", name);
+
+        let rate = |time: Duration| instructions as f64 / time.as_secs_f64() / 1_000_000.0;
+        println!("{}: {} instructions", name, instructions);
+        println!("    interpreter  {:>10.2?}  {:>8.2} M/s", plain, rate(plain));
+        println!("    block cache  {:>10.2?}  {:>8.2} M/s  ({:.2}x)", cached, rate(cached), plain.as_secs_f64() / cached.as_secs_f64());
+    }
+}
@@ -0,0 +1,49 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use chips_8::core::assembler::assemble_file;
+
+const USAGE: &str = "Usage: chip8-asm <source> [-o OUT]
+
+Assembles source in the disassembler's syntax into a ROM, OUT defaults to the source with a .ch8 extension";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut source = None;
+    let mut out = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--out" => match args.next() {
+                Some(path) => out = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{} needs a file", arg);
+                    process::exit(2);
+                }
+            },
+            _ if source.is_none() && !arg.starts_with('-') => source = Some(PathBuf::from(arg)),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+    let source = source.unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+    let out = out.unwrap_or_else(|| source.with_extension("ch8"));
+
+    let rom = match assemble_file(&source) {
+        Ok(rom) => rom,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+    if let Err(err) = fs::write(&out, &rom) {
+        eprintln!("Could not write {}: {}", out.display(), err);
+        process::exit(1);
+    }
+    println!("{} bytes written to {}", rom.len(), out.display());
+}
@@ -0,0 +1,251 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::process;
+
+use chips_8::core::debugger::{Breakpoint, Comparison, Debugger, Register, Stop};
+use chips_8::core::disassemble::DisassembledChip8;
+use chips_8::core::machine::{Machine, DEFAULT_INSTRUCTIONS_PER_SECOND};
+use chips_8::core::ops::MyChips8;
+use chips_8::core::quirks::Quirks;
+
+const USAGE: &str = "Usage: chip8-debug <rom> [--quirks NAME] [--speed N]";
+
+const HELP: &str = "Numbers are decimal unless written as 0x...
+  s [N]                 Step N instructions (default 1)
+  c                     Continue until a breakpoint
+  u ADDR                Continue until PC reaches ADDR
+  b [ADDR]              Break at ADDR, lists breakpoints without one
+  w r|w|rw ADDR [LEN]   Watch LEN bytes (default 1) from ADDR for reads and/or writes
+  cond REG OP VALUE     Break when e.g. `V3 == 0x10` or `I >= 0x300` becomes true
+  d ID                  Delete a breakpoint
+  regs                  Registers and timers
+  stack                 Call stack
+  mem ADDR [LEN]        Hex dump LEN bytes (default 16)
+  dis [ADDR] [COUNT]    Disassemble COUNT instructions (default 10) from ADDR (default PC)
+  key down|up K         Press or release hex key K
+  q                     Quit";
+
+// Plenty for any loop to reach its breakpoint, without hanging forever on one that never comes
+const CONTINUE_LIMIT: u64 = 100_000_000;
+
+fn parse_number(text: &str) -> Result<u64, String> {
+    let parsed = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| format!("Bad number `{}`", text))
+}
+
+fn parse_register(text: &str) -> Result<Register, String> {
+    match text.to_ascii_uppercase().as_str() {
+        "I" => Ok(Register::I),
+        "PC" => Ok(Register::Pc),
+        name if name.len() == 2 && name.starts_with('V') => u8::from_str_radix(&name[1..], 16)
+            .map(Register::V)
+            .map_err(|_| format!("Bad register `{}`", text)),
+        _ => Err(format!("Bad register `{}`", text)),
+    }
+}
+
+fn parse_comparison(text: &str) -> Result<Comparison, String> {
+    match text {
+        "==" => Ok(Comparison::Eq),
+        "!=" => Ok(Comparison::Ne),
+        "<" => Ok(Comparison::Lt),
+        "<=" => Ok(Comparison::Le),
+        ">" => Ok(Comparison::Gt),
+        ">=" => Ok(Comparison::Ge),
+        _ => Err(format!("Bad comparison `{}`", text)),
+    }
+}
+
+fn disassemble(debugger: &Debugger, address: usize, count: usize) -> String {
+    let memory = debugger.machine.cpu.memory();
+    let start = address.min(memory.len());
+    let len = (start.saturating_add(count.saturating_mul(2)).min(memory.len()) - start) & !0x1;
+    DisassembledChip8::new_at(&memory[start..start + len], start).to_string()
+}
+
+fn print_location(debugger: &Debugger) {
+    print!("{}", disassemble(debugger, debugger.machine.cpu.pc as usize, 1));
+}
+
+fn print_stop(debugger: &Debugger, stop: Stop) {
+    match stop {
+        Stop::Breakpoint(breakpoint) => println!("Stopped: {}", breakpoint),
+        Stop::WaitingForKey => println!("Stopped: waiting for a key, use `key down K` and `key up K`"),
+        Stop::Exited => println!("Stopped: program exited"),
+        Stop::StepLimit => println!("Stopped: gave up after {} instructions", CONTINUE_LIMIT),
+    }
+    print_location(debugger);
+}
+
+fn print_registers(debugger: &Debugger) {
+    let cpu = &debugger.machine.cpu;
+    let registers: Vec<String> = cpu.registers().iter().enumerate().map(|(x, value)| format!("V{:X}={:02X}", x, value)).collect();
+    println!("{}", registers[0..8].join(" "));
+    println!("{}", registers[8..16].join(" "));
+    println!(
+        "PC={:03X} I={:03X} SP={:X} DT={:02X} ST={:02X} frame={}",
+        cpu.pc,
+        cpu.i(),
+        cpu.call_stack().len(),
+        cpu.delay_timer(),
+        cpu.sound_timer(),
+        debugger.machine.frame()
+    );
+}
+
+fn print_memory(debugger: &Debugger, address: usize, len: usize) {
+    let memory = debugger.machine.cpu.memory();
+    let end = address.saturating_add(len).min(memory.len());
+    for row_start in (address.min(end)..end).step_by(16) {
+        let row = &memory[row_start..(row_start + 16).min(end)];
+        let bytes: Vec<String> = row.iter().map(|byte| format!("{:02X}", byte)).collect();
+        println!("{:04X}  {}", row_start, bytes.join(" "));
+    }
+}
+
+fn execute(debugger: &mut Debugger, words: &[&str]) -> Result<bool, String> {
+    match words {
+        ["s"] | ["step"] => return execute(debugger, &["s", "1"]),
+        ["s", count] | ["step", count] => {
+            for _ in 0..parse_number(count)? {
+                debugger.step().map_err(|err| err.to_string())?;
+            }
+            print_location(debugger);
+        }
+        ["c"] | ["continue"] => {
+            let stop = debugger.run(CONTINUE_LIMIT).map_err(|err| err.to_string())?;
+            print_stop(debugger, stop);
+        }
+        ["u", address] => {
+            let until = Breakpoint::Pc(parse_number(address)? as u16);
+            let stop = debugger.continue_until(until, CONTINUE_LIMIT).map_err(|err| err.to_string())?;
+            print_stop(debugger, stop);
+        }
+        ["b"] => debugger.breakpoints().iter().for_each(|(id, breakpoint)| println!("{}: {}", id, breakpoint)),
+        ["b", address] => {
+            let id = debugger.add_breakpoint(Breakpoint::Pc(parse_number(address)? as u16));
+            println!("Breakpoint {}", id);
+        }
+        ["w", access, address] => return execute(debugger, &["w", access, address, "1"]),
+        ["w", access, address, len] => {
+            let (read, write) = match *access {
+                "r" => (true, false),
+                "w" => (false, true),
+                "rw" => (true, true),
+                _ => return Err(format!("Bad access `{}`, expected r, w or rw", access)),
+            };
+            let start = parse_number(address)? as usize;
+            let end = start
+                .checked_add(parse_number(len)?.max(1) as usize - 1)
+                .ok_or_else(|| format!("Watch at {} of {} bytes runs past the end of the address space", address, len))?;
+            let id = debugger.add_breakpoint(Breakpoint::Watch { start, end, read, write });
+            println!("Watchpoint {}", id);
+        }
+        ["cond", register, comparison, value] => {
+            let id = debugger.add_breakpoint(Breakpoint::Register {
+                register: parse_register(register)?,
+                comparison: parse_comparison(comparison)?,
+                value: parse_number(value)? as u16,
+            });
+            println!("Condition {}", id);
+        }
+        ["d", id] => {
+            if !debugger.remove_breakpoint(parse_number(id)? as usize) {
+                return Err(format!("No breakpoint {}", id));
+            }
+        }
+        ["regs"] => print_registers(debugger),
+        ["stack"] => {
+            let call_stack = debugger.machine.cpu.call_stack();
+            if call_stack.is_empty() {
+                println!("Empty");
+            }
+            call_stack.iter().rev().enumerate().for_each(|(depth, address)| println!("#{} returns to {:03X}", depth, address));
+        }
+        ["mem", address] => print_memory(debugger, parse_number(address)? as usize, 16),
+        ["mem", address, len] => print_memory(debugger, parse_number(address)? as usize, parse_number(len)? as usize),
+        ["dis"] => print!("{}", disassemble(debugger, debugger.machine.cpu.pc as usize, 10)),
+        ["dis", address] => print!("{}", disassemble(debugger, parse_number(address)? as usize, 10)),
+        ["dis", address, count] => {
+            print!("{}", disassemble(debugger, parse_number(address)? as usize, parse_number(count)? as usize))
+        }
+        ["key", state, key] => {
+            let key = parse_number(key)?.min(0xFF) as u8;
+            match *state {
+                "down" => debugger.machine.cpu.press_key(key),
+                "up" => debugger.machine.cpu.release_key(key),
+                _ => return Err(format!("Bad key state `{}`, expected down or up", state)),
+            }
+        }
+        ["h"] | ["help"] => println!("{}", HELP),
+        ["q"] | ["quit"] => return Ok(false),
+        [] => {}
+        _ => return Err(format!("Unknown command `{}`, try help", words.join(" "))),
+    }
+    Ok(true)
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut rom = None;
+    let mut quirks = Quirks::default();
+    let mut speed = DEFAULT_INSTRUCTIONS_PER_SECOND;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--quirks" => match args.next().as_deref().and_then(Quirks::from_name) {
+                Some(profile) => quirks = profile,
+                None => {
+                    eprintln!("--quirks expects vip, chip48, schip, xo-chip or original");
+                    process::exit(2);
+                }
+            },
+            "--speed" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => speed = value,
+                None => {
+                    eprintln!("--speed expects a number");
+                    process::exit(2);
+                }
+            },
+            _ if rom.is_none() && !arg.starts_with("--") => rom = Some(PathBuf::from(arg)),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+    let rom = rom.unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+
+    let mut cpu = MyChips8::new_with_quirks(quirks);
+    let loaded = fs::read(&rom).map_err(|err| err.to_string()).and_then(|bytes| cpu.load_rom(&bytes).map_err(|err| err.to_string()));
+    if let Err(err) = loaded {
+        eprintln!("{}: {}", rom.display(), err);
+        process::exit(1);
+    }
+    let mut debugger = Debugger::new(Machine::new_with_speed(cpu, speed));
+
+    println!("{} loaded, type help for commands", rom.display());
+    print_location(&debugger);
+    let stdin = io::stdin();
+    loop {
+        print!("(chip8) ");
+        io::stdout().flush().ok();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match execute(&mut debugger, &words) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(message) => println!("{}", message),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use chips_8::core::cfg::ControlFlowGraph;
+use chips_8::core::disassemble::DisassembledChip8;
+
+const USAGE: &str = "Usage: chip8-disasm <rom> [--format text|asm|json|csv|dot] [--linear]
+
+Disassembles a ROM by following its control flow, --linear decodes every byte pair instead
+asm output can be fed back into chip8-asm, dot is the control-flow graph for Graphviz";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Asm,
+    Json,
+    Csv,
+    Dot,
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut rom = None;
+    let mut format = Format::Text;
+    let mut linear = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("text") => Format::Text,
+                    Some("asm") => Format::Asm,
+                    Some("json") => Format::Json,
+                    Some("csv") => Format::Csv,
+                    Some("dot") => Format::Dot,
+                    _ => {
+                        eprintln!("--format expects text, asm, json, csv or dot");
+                        process::exit(2);
+                    }
+                }
+            }
+            "--linear" => linear = true,
+            _ if rom.is_none() && !arg.starts_with("--") => rom = Some(PathBuf::from(arg)),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+    let rom = rom.unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+
+    let bytes = match fs::read(&rom) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("{}: {}", rom.display(), err);
+            process::exit(1);
+        }
+    };
+    let listing = if linear { DisassembledChip8::new(&bytes) } else { DisassembledChip8::recursive(&bytes) };
+    match format {
+        Format::Text => print!("{}", listing),
+        Format::Asm => print!("{}", listing.to_source()),
+        Format::Json => print!("{}", listing.to_json()),
+        Format::Csv => print!("{}", listing.to_csv()),
+        Format::Dot => print!("{}", ControlFlowGraph::from_disassembly(&listing).to_dot()),
+    }
+}
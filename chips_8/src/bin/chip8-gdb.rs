@@ -0,0 +1,81 @@
+use std::fs;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process;
+
+use chips_8::core::debugger::Debugger;
+use chips_8::core::machine::{Machine, DEFAULT_INSTRUCTIONS_PER_SECOND};
+use chips_8::core::ops::MyChips8;
+use chips_8::core::quirks::Quirks;
+use chips_8::gdb::GdbStub;
+
+const USAGE: &str = "Usage: chip8-gdb <rom> [--port N] [--quirks NAME]
+
+Waits for gdb on 127.0.0.1, attach with `target remote :1234`";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut rom = None;
+    let mut port = 1234_u16;
+    let mut quirks = Quirks::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => port = value,
+                None => {
+                    eprintln!("--port expects a number");
+                    process::exit(2);
+                }
+            },
+            "--quirks" => match args.next().as_deref().and_then(Quirks::from_name) {
+                Some(profile) => quirks = profile,
+                None => {
+                    eprintln!("--quirks expects vip, chip48, schip, xo-chip or original");
+                    process::exit(2);
+                }
+            },
+            _ if rom.is_none() && !arg.starts_with("--") => rom = Some(PathBuf::from(arg)),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+    let rom = rom.unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+
+    let mut cpu = MyChips8::new_with_quirks(quirks);
+    let loaded = fs::read(&rom).map_err(|err| err.to_string()).and_then(|bytes| cpu.load_rom(&bytes).map_err(|err| err.to_string()));
+    if let Err(err) = loaded {
+        eprintln!("{}: {}", rom.display(), err);
+        process::exit(1);
+    }
+    let mut stub = GdbStub::new(Debugger::new(Machine::new_with_speed(cpu, DEFAULT_INSTRUCTIONS_PER_SECOND)));
+
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("Could not listen on port {}: {}", port, err);
+            process::exit(1);
+        }
+    };
+    println!("Waiting for gdb on 127.0.0.1:{}", port);
+
+    // One client at a time, the machine carries on where the last one detached
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Connection failed: {}", err);
+                continue;
+            }
+        };
+        println!("gdb attached");
+        if let Err(err) = stub.serve(&mut stream) {
+            eprintln!("Connection lost: {}", err);
+        }
+        println!("gdb detached");
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use chips_8::core::audio::{Audio, AudioSettings, WavSink, DEFAULT_SAMPLE_RATE};
+use chips_8::core::bus::OutOfRange;
+use chips_8::core::dump::{to_ascii, to_pbm};
+use chips_8::core::machine::{Machine, DEFAULT_INSTRUCTIONS_PER_SECOND, FRAMES_PER_SECOND};
+use chips_8::core::movie::Movie;
+use chips_8::core::ops::MyChips8;
+use chips_8::core::quirks::Quirks;
+use chips_8::core::random::XorShift;
+use chips_8::core::script::KeyScript;
+use chips_8::core::trace::TraceWriter;
+
+const USAGE: &str = "Usage: chip8-headless <rom> [options]
+
+Runs a ROM without a window and dumps the display, the last frame is dumped when no --dump is given
+
+Options:
+  --frames N         Run N frames of the 60Hz clock (default 60)
+  --cycles N         Run exactly N instructions instead of frames
+  --quirks NAME      vip, chip48, schip, xo-chip or original (default original)
+  --speed N          Instructions per second (default 700)
+  --seed N           Seed for RND, runs are reproducible (default 1)
+  --keys FILE        Scripted key input, `<frame> down|up <key>` per line
+  --movie FILE       Replay a recorded movie, it sets the seed, quirks, speed, keys and length (unless given)
+  --dump F1,F2,...   Frames to dump the display at, counted after that many frames have run
+  --format FORMAT    ascii or pbm (default ascii)
+  --out DIR          Write frame_<n>.txt / frame_<n>.pbm files instead of printing to stdout
+  --trace FILE       Log every executed instruction, one line each with PC, opcode, I, V0-VF and changes
+  --cached           Run from a cache of decoded blocks, faster with the same results
+  --out-of-range P   Accesses past the end of memory wrap, fault or read zero (default fault)
+  --wav FILE         Record the sound output as a 16 bit mono WAV file
+  --volume N         Tone volume in percent (default 25)
+  --tone HZ          Square wave frequency in Hz (default 440)
+  --mute             Keep time in the WAV file but write silence";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    Pbm,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Length {
+    Frames(u64),
+    Cycles(u64),
+}
+
+struct Options {
+    rom: PathBuf,
+    length: Option<Length>, // Frames(60) unless a movie says otherwise
+    quirks: Quirks,
+    speed: u64,
+    seed: u64,
+    keys: Option<PathBuf>,
+    movie: Option<PathBuf>,
+    dumps: BTreeSet<u64>,
+    format: Format,
+    out: Option<PathBuf>,
+    trace: Option<PathBuf>,
+    cached: bool,
+    out_of_range: OutOfRange,
+    wav: Option<PathBuf>,
+    audio: AudioSettings,
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<u64, String> {
+    let value = value.ok_or(format!("{} needs a value", flag))?;
+    value.parse().map_err(|_| format!("{} expects a number, found `{}`", flag, value))
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        rom: PathBuf::new(),
+        length: None,
+        quirks: Quirks::default(),
+        speed: DEFAULT_INSTRUCTIONS_PER_SECOND,
+        seed: 1,
+        keys: None,
+        movie: None,
+        dumps: BTreeSet::new(),
+        format: Format::Ascii,
+        out: None,
+        trace: None,
+        cached: false,
+        out_of_range: OutOfRange::default(),
+        wav: None,
+        audio: AudioSettings::default(),
+    };
+    let mut rom = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--frames" => options.length = Some(Length::Frames(parse_number(&arg, args.next())?)),
+            "--cycles" => options.length = Some(Length::Cycles(parse_number(&arg, args.next())?)),
+            "--speed" => options.speed = parse_number(&arg, args.next())?.max(1),
+            "--seed" => options.seed = parse_number(&arg, args.next())?,
+            "--quirks" => {
+                let name = args.next().ok_or("--quirks needs a value")?;
+                options.quirks = Quirks::from_name(&name).ok_or(format!("Unknown quirk profile `{}`", name))?;
+            }
+            "--keys" => options.keys = Some(PathBuf::from(args.next().ok_or("--keys needs a file")?)),
+            "--movie" => options.movie = Some(PathBuf::from(args.next().ok_or("--movie needs a file")?)),
+            "--out" => options.out = Some(PathBuf::from(args.next().ok_or("--out needs a directory")?)),
+            "--trace" => options.trace = Some(PathBuf::from(args.next().ok_or("--trace needs a file")?)),
+            "--cached" => options.cached = true,
+            "--out-of-range" => {
+                let name = args.next().ok_or("--out-of-range needs a value")?;
+                options.out_of_range = OutOfRange::from_name(&name).ok_or("--out-of-range expects wrap, fault or zero")?;
+            }
+            "--wav" => options.wav = Some(PathBuf::from(args.next().ok_or("--wav needs a file")?)),
+            "--volume" => options.audio.volume = parse_number(&arg, args.next())?.min(100) as f32 / 100.0,
+            "--tone" => options.audio.frequency = parse_number(&arg, args.next())? as f32,
+            "--mute" => options.audio.muted = true,
+            "--format" => {
+                options.format = match args.next().as_deref() {
+                    Some("ascii") => Format::Ascii,
+                    Some("pbm") => Format::Pbm,
+                    _ => return Err("--format expects ascii or pbm".to_string()),
+                }
+            }
+            "--dump" => {
+                let frames = args.next().ok_or("--dump needs a list of frames")?;
+                for frame in frames.split(',') {
+                    options.dumps.insert(parse_number("--dump", Some(frame.trim().to_string()))?);
+                }
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option `{}`", arg)),
+            _ if rom.is_none() => rom = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument `{}`", arg)),
+        }
+    }
+
+    options.rom = rom.ok_or(USAGE)?;
+    Ok(options)
+}
+
+fn dump(options: &Options, frame: u64, cpu: &MyChips8) -> Result<(), String> {
+    let (text, extension) = match options.format {
+        Format::Ascii => (to_ascii(&cpu.gfx), "txt"),
+        Format::Pbm => (to_pbm(&cpu.gfx), "pbm"),
+    };
+
+    match &options.out {
+        Some(dir) => {
+            let path = dir.join(format!("frame_{}.{}", frame, extension));
+            fs::write(&path, text).map_err(|err| format!("Could not write {}: {}", path.display(), err))
+        }
+        None => {
+            println!("frame {}", frame);
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let rom = fs::read(&options.rom).map_err(|err| format!("Could not read {}: {}", options.rom.display(), err))?;
+    let movie = match &options.movie {
+        Some(path) => {
+            let source = fs::read_to_string(path).map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+            let movie = Movie::parse(&source).map_err(|err| format!("{}: {}", path.display(), err))?;
+            if !movie.matches_rom(&rom) {
+                return Err(format!("{} was recorded with a different ROM than {}", path.display(), options.rom.display()));
+            }
+            if options.keys.is_some() {
+                return Err(String::from("--keys and --movie can not be used together"));
+            }
+            Some(movie)
+        }
+        None => None,
+    };
+    if let Some(dir) = &options.out {
+        fs::create_dir_all(dir).map_err(|err| format!("Could not create {}: {}", dir.display(), err))?;
+    }
+
+    let (mut machine, mut script) = match &movie {
+        Some(movie) => movie.start(&rom).map_err(|err| format!("{}: {}", options.rom.display(), err))?,
+        None => {
+            let script = match &options.keys {
+                Some(path) => {
+                    let source = fs::read_to_string(path).map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+                    KeyScript::parse(&source).map_err(|err| format!("{}: {}", path.display(), err))?
+                }
+                None => KeyScript::default(),
+            };
+            let mut cpu = MyChips8::new_with_quirks(options.quirks);
+            cpu.set_random_source(Box::new(XorShift::new(options.seed)));
+            cpu.load_rom(&rom).map_err(|err| format!("{}: {}", options.rom.display(), err))?;
+            (Machine::new_with_speed(cpu, options.speed), script)
+        }
+    };
+    if let Some(path) = &options.trace {
+        let tracer = TraceWriter::create(path).map_err(|err| format!("Could not create {}: {}", path.display(), err))?;
+        machine.cpu.set_tracer(Box::new(tracer));
+    }
+    machine.set_block_cache(options.cached);
+    machine.cpu.set_out_of_range(options.out_of_range);
+    let mut audio = options.wav.as_ref().map(|path| {
+        let mut audio = Audio::new(WavSink::to_file(path, DEFAULT_SAMPLE_RATE));
+        audio.settings = options.audio;
+        audio
+    });
+    let length = options.length.unwrap_or(Length::Frames(movie.map_or(FRAMES_PER_SECOND, |movie| movie.frames)));
+
+    let mut dumps = options.dumps.clone();
+    let mut fault = None;
+    match length {
+        Length::Frames(frames) => {
+            while machine.frame() < frames {
+                if dumps.remove(&machine.frame()) {
+                    dump(options, machine.frame(), &machine.cpu)?;
+                }
+                script.apply(machine.frame(), &mut machine.cpu);
+                let outcome = machine.run_frame();
+                if let (Some(audio), Ok(_)) = (&mut audio, &outcome) {
+                    audio.run_frame(&machine.cpu);
+                }
+                match outcome {
+                    Ok(outcome) if outcome.exited => break,
+                    Ok(_) => {}
+                    Err(err) => {
+                        fault = Some(err);
+                        break;
+                    }
+                }
+            }
+        }
+        // Every instruction counts, even ones spent waiting, Machine::step crosses into the next frame (timer
+        // tick, vertical blank) at the same points run_frame does
+        Length::Cycles(cycles) => {
+            let mut started = None;
+            for _ in 0..cycles {
+                let frame = machine.frame();
+                if started != Some(frame) {
+                    started = Some(frame);
+                    if dumps.remove(&frame) {
+                        dump(options, frame, &machine.cpu)?;
+                    }
+                    script.apply(frame, &mut machine.cpu);
+                }
+                if let Err(err) = machine.step() {
+                    fault = Some(err);
+                    break;
+                }
+                if let (Some(audio), true) = (&mut audio, machine.frame() != frame) {
+                    audio.run_frame(&machine.cpu);
+                }
+            }
+        }
+    }
+
+    // Frames asked for past the end of the run get the final display, same as no --dump at all
+    let last_frame = machine.frame();
+    if options.dumps.is_empty() {
+        dumps.insert(last_frame);
+    }
+    for frame in dumps {
+        dump(options, frame, &machine.cpu)?;
+    }
+
+    if let (Some(path), Some(mut tracer)) = (&options.trace, machine.cpu.take_tracer()) {
+        tracer.flush().map_err(|err| format!("Could not write {}: {}", path.display(), err))?;
+    }
+
+    if let (Some(path), Some(audio)) = (&options.wav, &mut audio) {
+        audio.flush().map_err(|err| format!("Could not write {}: {}", path.display(), err))?;
+    }
+
+    match fault {
+        Some(err) => Err(format!("Halted: {}", err)),
+        None => Ok(()),
+    }
+}
+
+fn main() {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    };
+
+    if let Err(message) = run(&options) {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use chips_8::core::quirks::Quirks;
+use chips_8::core::recompile::recompile;
+
+const USAGE: &str = "Usage: chip8-recompile <rom> [--quirks NAME] [-o OUT]
+
+Turns every basic block of a ROM into a Rust function and prints the module, or writes it to OUT
+Add the module to a crate depending on chips_8 and pass its engine() to Machine::set_engine
+The quirks (vip, chip48, schip, xo-chip or original, default original) are compiled in and have to match the CPU's";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut rom = None;
+    let mut out = None;
+    let mut quirks = Quirks::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--out" => match args.next() {
+                Some(path) => out = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{} needs a file", arg);
+                    process::exit(2);
+                }
+            },
+            "--quirks" => match args.next().as_deref().and_then(Quirks::from_name) {
+                Some(profile) => quirks = profile,
+                None => {
+                    eprintln!("--quirks expects vip, chip48, schip, xo-chip or original");
+                    process::exit(2);
+                }
+            },
+            _ if rom.is_none() && !arg.starts_with('-') => rom = Some(PathBuf::from(arg)),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+    let rom = rom.unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+
+    let bytes = match fs::read(&rom) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("{}: {}", rom.display(), err);
+            process::exit(1);
+        }
+    };
+    let module = recompile(&bytes, quirks);
+    match out {
+        Some(path) => {
+            if let Err(err) = fs::write(&path, module) {
+                eprintln!("Could not write {}: {}", path.display(), err);
+                process::exit(1);
+            }
+        }
+        None => print!("{}", module),
+    }
+}
@@ -0,0 +1,419 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use super::instruction::Instruction;
+use super::ops::PROGRAM_START;
+
+// Turns source written in the disassembler's syntax back into a ROM
+//   ; comments start with a semicolon
+//   SPRITE_HEIGHT equ 5        ; constants, `NAME = value` works too
+//   include "font.asm"         ; paths are relative to the including file
+//   start:
+//       LD I, logo
+//       DRW V0, V1, SPRITE_HEIGHT
+//       JMP start
+//   logo: db F0, 90, %10010000, #144
+//         dw 1234
+// Bare numbers are hex like the disassembler prints them, 0x / $ are hex too, % is binary and # is decimal
+// Operands can add and subtract, e.g. `logo+5`, and symbols win over bare hex when a name is both
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    pub file: String,
+    pub line: usize, // 1 based, 0 when the file itself could not be read
+    pub source: String,
+    pub message: String,
+}
+
+impl Display for AssembleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}: {}", self.file, self.message);
+        }
+        write!(f, "{}:{}: {}\n    {}", self.file, self.line, self.message, self.source.trim())
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+// Loads an include, given the file doing the including and the path it asked for
+// Returns the name to report errors against along with the contents
+pub type IncludeLoader<'a> = dyn FnMut(&str, &str) -> Result<(String, String), String> + 'a;
+
+// Includes nested deeper than this are almost certainly a file including itself
+const MAX_INCLUDE_DEPTH: usize = 16;
+// Same for constants defined in terms of each other
+const MAX_CONSTANT_DEPTH: usize = 32;
+
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    assemble_with("<source>", source, &mut read_include)
+}
+
+pub fn assemble_file(path: &Path) -> Result<Vec<u8>, AssembleError> {
+    let name = path.display().to_string();
+    let source = fs::read_to_string(path).map_err(|err| AssembleError { file: name.clone(), line: 0, source: String::new(), message: err.to_string() })?;
+    assemble_with(&name, &source, &mut read_include)
+}
+
+fn read_include(from: &str, include: &str) -> Result<(String, String), String> {
+    let path = Path::new(from).parent().unwrap_or_else(|| Path::new("")).join(include);
+    let source = fs::read_to_string(&path).map_err(|err| format!("Could not include {}: {}", path.display(), err))?;
+    Ok((path.display().to_string(), source))
+}
+
+// Same as assemble() with includes coming from load instead of the file system
+pub fn assemble_with(name: &str, source: &str, load: &mut IncludeLoader) -> Result<Vec<u8>, AssembleError> {
+    let mut assembler = Assembler { lines: Vec::new(), statements: Vec::new(), symbols: HashMap::new() };
+    assembler.read(name, source, load, 0, None)?;
+    assembler.layout()?;
+    assembler.emit()
+}
+
+struct Line {
+    file: String,
+    number: usize,
+    text: String,
+}
+
+enum Item {
+    Code { mnemonic: String, operands: Vec<String> },
+    Bytes(Vec<String>),
+    Words(Vec<String>),
+}
+
+struct Statement {
+    line: usize, // Index into Assembler::lines
+    address: usize,
+    item: Item,
+}
+
+enum Symbol {
+    Label(usize),
+    Constant { expression: String, line: usize },
+}
+
+struct Assembler {
+    lines: Vec<Line>,
+    statements: Vec<Statement>,
+    symbols: HashMap<String, Symbol>,
+}
+
+impl Assembler {
+    fn error(&self, line: usize, message: String) -> AssembleError {
+        let line = &self.lines[line];
+        AssembleError { file: line.file.clone(), line: line.number, source: line.text.clone(), message }
+    }
+
+    // Flattens the source and everything it includes into one list of lines
+    fn read(&mut self, name: &str, source: &str, load: &mut IncludeLoader, depth: usize, included_by: Option<usize>) -> Result<(), AssembleError> {
+        for (idx, text) in source.lines().enumerate() {
+            self.lines.push(Line { file: name.to_string(), number: idx + 1, text: text.to_string() });
+            let line = self.lines.len() - 1;
+
+            let code = strip_comment(text).trim();
+            let mut words = code.splitn(2, char::is_whitespace);
+            if !words.next().unwrap_or("").eq_ignore_ascii_case("include") {
+                continue;
+            }
+            let path = words.next().unwrap_or("").trim().trim_matches('"');
+            if path.is_empty() {
+                return Err(self.error(line, String::from("include needs a file")));
+            }
+            if depth >= MAX_INCLUDE_DEPTH {
+                return Err(self.error(included_by.unwrap_or(line), format!("includes nest deeper than {}, is a file including itself?", MAX_INCLUDE_DEPTH)));
+            }
+            let (included_name, included_source) = load(name, path).map_err(|message| self.error(line, message))?;
+            self.read(&included_name, &included_source, load, depth + 1, Some(included_by.unwrap_or(line)))?;
+        }
+        Ok(())
+    }
+
+    // First pass, places every statement and label, sizes never depend on a symbol so one pass is enough
+    fn layout(&mut self) -> Result<(), AssembleError> {
+        let mut address = PROGRAM_START;
+        for line in 0..self.lines.len() {
+            let mut code = strip_comment(&self.lines[line].text).trim().to_string();
+
+            if let Some(colon) = code.find(':') {
+                let label = code[..colon].trim().to_string();
+                if !is_symbol(&label) {
+                    return Err(self.error(line, format!("bad label `{}`", label)));
+                }
+                self.define(line, label, Symbol::Label(address))?;
+                code = code[colon + 1..].trim().to_string();
+            }
+            if code.is_empty() {
+                continue;
+            }
+
+            let (first, rest) = match code.find(|c: char| c.is_whitespace() || c == '=') {
+                Some(end) => (&code[..end], code[end..].trim()),
+                None => (code.as_str(), ""),
+            };
+            if first.eq_ignore_ascii_case("include") {
+                continue;
+            }
+
+            // NAME equ value / NAME = value
+            let constant = if let Some(expression) = rest.strip_prefix('=') {
+                Some(expression)
+            } else if rest.get(..3).is_some_and(|word| word.eq_ignore_ascii_case("equ")) && rest[3..].starts_with(char::is_whitespace) {
+                Some(&rest[3..])
+            } else {
+                None
+            };
+            if let Some(expression) = constant {
+                if !is_symbol(first) {
+                    return Err(self.error(line, format!("bad constant name `{}`", first)));
+                }
+                let symbol = Symbol::Constant { expression: expression.trim().to_string(), line };
+                self.define(line, first.to_string(), symbol)?;
+                continue;
+            }
+
+            let operands: Vec<String> = if rest.is_empty() { Vec::new() } else { rest.split(',').map(|operand| operand.trim().to_string()).collect() };
+            if operands.iter().any(|operand| operand.is_empty()) {
+                return Err(self.error(line, String::from("empty operand")));
+            }
+            let mnemonic = first.to_ascii_uppercase();
+            let (item, size) = match mnemonic.as_str() {
+                "DB" => (Item::Bytes(operands.clone()), operands.len()),
+                "DW" => (Item::Words(operands.clone()), operands.len() * 2),
+                _ => {
+                    // LD I, LONG nnnn is the only instruction with a second word
+                    let long = operands.get(1).is_some_and(|operand| long_address(operand).is_some());
+                    (Item::Code { mnemonic, operands }, if long { 4 } else { 2 })
+                }
+            };
+            self.statements.push(Statement { line, address, item });
+            address += size;
+        }
+        Ok(())
+    }
+
+    fn define(&mut self, line: usize, name: String, symbol: Symbol) -> Result<(), AssembleError> {
+        if self.symbols.contains_key(&name) {
+            return Err(self.error(line, format!("`{}` is already defined", name)));
+        }
+        self.symbols.insert(name, symbol);
+        Ok(())
+    }
+
+    // Second pass, every symbol is known now
+    fn emit(&self) -> Result<Vec<u8>, AssembleError> {
+        let mut rom = Vec::new();
+        for statement in &self.statements {
+            let eval = |text: &str| self.evaluate(text, 0);
+            let bytes = match &statement.item {
+                Item::Bytes(values) => values.iter().map(|value| checked(eval(value)?, 0xFF, value).map(|byte| byte as u8)).collect(),
+                Item::Words(values) => values
+                    .iter()
+                    .map(|value| checked(eval(value)?, 0xFFFF, value).map(|word| (word as u16).to_be_bytes()))
+                    .collect::<Result<Vec<[u8; 2]>, String>>()
+                    .map(|words| words.concat()),
+                Item::Code { mnemonic, operands } => encode(mnemonic, operands, &eval),
+            };
+            let bytes = bytes.map_err(|message| self.error(statement.line, message))?;
+            debug_assert_eq!(PROGRAM_START + rom.len(), statement.address);
+            rom.extend(bytes);
+        }
+        Ok(rom)
+    }
+
+    // Terms joined by + and -, each one a symbol or a number
+    fn evaluate(&self, expression: &str, depth: usize) -> Result<u32, String> {
+        let mut total: i64 = 0;
+        let mut sign = 1;
+        let mut term = String::new();
+        for c in expression.chars().chain(std::iter::once('+')) {
+            if c != '+' && c != '-' {
+                term.push(c);
+                continue;
+            }
+            let name = term.trim();
+            if name.is_empty() {
+                return Err(format!("bad expression `{}`", expression));
+            }
+            total += sign * self.term(name, depth)? as i64;
+            sign = if c == '-' { -1 } else { 1 };
+            term.clear();
+        }
+        u32::try_from(total).map_err(|_| format!("`{}` is negative", expression))
+    }
+
+    fn term(&self, name: &str, depth: usize) -> Result<u32, String> {
+        match self.symbols.get(name) {
+            Some(Symbol::Label(address)) => Ok(*address as u32),
+            Some(Symbol::Constant { expression, line }) => {
+                if depth >= MAX_CONSTANT_DEPTH {
+                    return Err(format!("`{}` is defined in terms of itself", name));
+                }
+                self.evaluate(expression, depth + 1).map_err(|message| format!("{} (in `{}` from line {})", message, name, self.lines[*line].number))
+            }
+            None => parse_number(name).ok_or(format!("unknown symbol or bad number `{}`", name)),
+        }
+    }
+}
+
+fn strip_comment(text: &str) -> &str {
+    text.split(';').next().unwrap_or("")
+}
+
+fn is_symbol(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn parse_number(text: &str) -> Option<u32> {
+    let (digits, radix) = if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).or_else(|| text.strip_prefix('$')) {
+        (hex, 16)
+    } else if let Some(binary) = text.strip_prefix('%') {
+        (binary, 2)
+    } else if let Some(decimal) = text.strip_prefix('#') {
+        (decimal, 10)
+    } else {
+        (text, 16)
+    };
+    u32::from_str_radix(digits, radix).ok()
+}
+
+fn checked(value: u32, max: u32, text: &str) -> Result<u32, String> {
+    if value > max {
+        return Err(format!("`{}` is {:X}, more than the {:X} that fits", text, value, max));
+    }
+    Ok(value)
+}
+
+// What an operand is, anything that is not a register or keyword gets evaluated as a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    V(u8),
+    I,
+    IndirectI,
+    Dt,
+    St,
+    K,
+    F,
+    Hf,
+    B,
+    R,
+    Long,
+    Value,
+}
+
+// The nnnn of a `LONG nnnn` operand, only the whole keyword counts so names like long_count stay names
+fn long_address(operand: &str) -> Option<&str> {
+    let keyword = operand.get(..4)?;
+    let rest = &operand[4..];
+    if keyword.eq_ignore_ascii_case("LONG") && rest.starts_with(char::is_whitespace) {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+fn kind(operand: &str) -> Kind {
+    let upper = operand.to_ascii_uppercase();
+    match upper.as_str() {
+        "I" => Kind::I,
+        "[I]" => Kind::IndirectI,
+        "DT" => Kind::Dt,
+        "ST" => Kind::St,
+        "K" => Kind::K,
+        "F" => Kind::F,
+        "HF" => Kind::Hf,
+        "B" => Kind::B,
+        "R" => Kind::R,
+        _ if long_address(operand).is_some() => Kind::Long,
+        _ if upper.len() == 2 && upper.starts_with('V') => match u8::from_str_radix(&upper[1..], 16) {
+            Ok(x) => Kind::V(x),
+            Err(_) => Kind::Value,
+        },
+        _ => Kind::Value,
+    }
+}
+
+const MNEMONICS: &[&str] = &[
+    "CLS", "RET", "SCR", "SCL", "EXIT", "LOW", "HIGH", "AUDIO", "SYS", "JMP", "JP", "CALL", "SE", "SNE", "LD", "ADD", "OR", "AND", "XOR", "SUB",
+    "SUBN", "SHR", "SHL", "RND", "DRW", "SKP", "SKNP", "SCD", "SCU", "SAVE", "LOAD", "PLANE", "PITCH",
+];
+
+fn encode(mnemonic: &str, operands: &[String], eval: &dyn Fn(&str) -> Result<u32, String>) -> Result<Vec<u8>, String> {
+    let kinds: Vec<Kind> = operands.iter().map(|operand| kind(operand)).collect();
+    let value = |idx: usize, max: u32| checked(eval(&operands[idx])?, max, &operands[idx]);
+    let nnn = |idx: usize| value(idx, 0xFFF).map(|nnn| nnn as u16);
+    let kk = |idx: usize| value(idx, 0xFF).map(|kk| kk as u8);
+    let n = |idx: usize| value(idx, 0xF).map(|n| n as u8);
+
+    let instruction = match (mnemonic, kinds.as_slice()) {
+        ("CLS", []) => Instruction::Cls,
+        ("RET", []) => Instruction::Ret,
+        ("SCR", []) => Instruction::Scr,
+        ("SCL", []) => Instruction::Scl,
+        ("EXIT", []) => Instruction::Exit,
+        ("LOW", []) => Instruction::Low,
+        ("HIGH", []) => Instruction::High,
+        ("AUDIO", []) => Instruction::Audio,
+        ("SYS", [_]) => Instruction::Sys { nnn: nnn(0)? },
+        ("JMP", [Kind::V(0), _]) | ("JP", [Kind::V(0), _]) => Instruction::JpV0 { nnn: nnn(1)? },
+        ("JMP", [_]) | ("JP", [_]) => Instruction::Jp { nnn: nnn(0)? },
+        ("CALL", [_]) => Instruction::Call { nnn: nnn(0)? },
+        ("SE", [Kind::V(x), Kind::V(y)]) => Instruction::SeReg { x: *x, y: *y },
+        ("SE", [Kind::V(x), _]) => Instruction::SeByte { x: *x, kk: kk(1)? },
+        ("SNE", [Kind::V(x), Kind::V(y)]) => Instruction::SneReg { x: *x, y: *y },
+        ("SNE", [Kind::V(x), _]) => Instruction::SneByte { x: *x, kk: kk(1)? },
+        ("LD", [Kind::V(x), Kind::V(y)]) => Instruction::LdReg { x: *x, y: *y },
+        ("LD", [Kind::V(x), Kind::Dt]) => Instruction::LdVxDt { x: *x },
+        ("LD", [Kind::V(x), Kind::K]) => Instruction::LdVxK { x: *x },
+        ("LD", [Kind::V(x), Kind::IndirectI]) => Instruction::LdVxI { x: *x },
+        ("LD", [Kind::V(x), Kind::R]) => Instruction::LdVxR { x: *x },
+        ("LD", [Kind::V(x), _]) => Instruction::LdByte { x: *x, kk: kk(1)? },
+        ("LD", [Kind::Dt, Kind::V(x)]) => Instruction::LdDtVx { x: *x },
+        ("LD", [Kind::St, Kind::V(x)]) => Instruction::LdStVx { x: *x },
+        ("LD", [Kind::F, Kind::V(x)]) => Instruction::LdFVx { x: *x },
+        ("LD", [Kind::Hf, Kind::V(x)]) => Instruction::LdHfVx { x: *x },
+        ("LD", [Kind::B, Kind::V(x)]) => Instruction::LdBVx { x: *x },
+        ("LD", [Kind::IndirectI, Kind::V(x)]) => Instruction::LdIVx { x: *x },
+        ("LD", [Kind::R, Kind::V(x)]) => Instruction::LdRVx { x: *x },
+        ("LD", [Kind::I, Kind::Long]) => {
+            let address = long_address(&operands[1]).unwrap_or_default();
+            let address = checked(eval(address)?, 0xFFFF, address)? as u16;
+            let mut bytes = Instruction::LdILong.encode().to_be_bytes().to_vec();
+            bytes.extend(address.to_be_bytes());
+            return Ok(bytes);
+        }
+        ("LD", [Kind::I, _]) => Instruction::LdI { nnn: nnn(1)? },
+        ("ADD", [Kind::V(x), Kind::V(y)]) => Instruction::AddReg { x: *x, y: *y },
+        ("ADD", [Kind::V(x), _]) => Instruction::AddByte { x: *x, kk: kk(1)? },
+        ("ADD", [Kind::I, Kind::V(x)]) => Instruction::AddIVx { x: *x },
+        ("OR", [Kind::V(x), Kind::V(y)]) => Instruction::Or { x: *x, y: *y },
+        ("AND", [Kind::V(x), Kind::V(y)]) => Instruction::And { x: *x, y: *y },
+        ("XOR", [Kind::V(x), Kind::V(y)]) => Instruction::Xor { x: *x, y: *y },
+        ("SUB", [Kind::V(x), Kind::V(y)]) => Instruction::Sub { x: *x, y: *y },
+        ("SUBN", [Kind::V(x), Kind::V(y)]) => Instruction::Subn { x: *x, y: *y },
+        // Vy is optional since only some quirk profiles use it
+        ("SHR", [Kind::V(x)]) => Instruction::Shr { x: *x, y: 0 },
+        ("SHR", [Kind::V(x), Kind::V(y)]) => Instruction::Shr { x: *x, y: *y },
+        ("SHL", [Kind::V(x)]) => Instruction::Shl { x: *x, y: 0 },
+        ("SHL", [Kind::V(x), Kind::V(y)]) => Instruction::Shl { x: *x, y: *y },
+        ("RND", [Kind::V(x), _]) => Instruction::Rnd { x: *x, kk: kk(1)? },
+        ("DRW", [Kind::V(x), Kind::V(y), _]) => Instruction::Drw { x: *x, y: *y, n: n(2)? },
+        ("SKP", [Kind::V(x)]) => Instruction::Skp { x: *x },
+        ("SKNP", [Kind::V(x)]) => Instruction::Sknp { x: *x },
+        ("SCD", [_]) => Instruction::Scd { n: n(0)? },
+        ("SCU", [_]) => Instruction::Scu { n: n(0)? },
+        ("SAVE", [Kind::V(x), Kind::V(y)]) => Instruction::SaveRange { x: *x, y: *y },
+        ("LOAD", [Kind::V(x), Kind::V(y)]) => Instruction::LoadRange { x: *x, y: *y },
+        ("PLANE", [_]) => Instruction::Plane { n: n(0)? },
+        ("PITCH", [Kind::V(x)]) => Instruction::Pitch { x: *x },
+        _ if MNEMONICS.contains(&mnemonic) => return Err(format!("{} does not take `{}`", mnemonic, operands.join(", "))),
+        _ => return Err(format!("unknown instruction `{}`", mnemonic)),
+    };
+    Ok(instruction.encode().to_be_bytes().to_vec())
+}
+
+#[cfg(test)]
+#[path ="./assembler_test.rs"]
+mod assembler_test;
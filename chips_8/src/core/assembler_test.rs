@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use super::*;
+use crate::core::disassemble::DisassembledChip8;
+
+const IBM_LOGO: &[u8] = include_bytes!("../IBM_Logo.ch8");
+
+#[test]
+fn assembles_the_disassembler_syntax() {
+    let rom = assemble("CLS\nLD V1, 0A\nDRW V0, V1, 5\nLD I, 22A\nLD [I], V3\nLD V2, DT\nLD B, V4\nJMP 200\nJMP V0, 300\nSHR V1").unwrap();
+
+    assert_eq!(
+        vec![0x00, 0xE0, 0x61, 0x0A, 0xD0, 0x15, 0xA2, 0x2A, 0xF3, 0x55, 0xF2, 0x07, 0xF4, 0x33, 0x12, 0x00, 0xB3, 0x00, 0x81, 0x06],
+        rom
+    );
+}
+
+#[test]
+fn round_trips_the_ibm_logo() {
+    let source = DisassembledChip8::new(IBM_LOGO).to_source();
+    let rom = assemble(&source).unwrap();
+
+    assert_eq!(IBM_LOGO, &rom[..]);
+    assert_eq!(DisassembledChip8::new(IBM_LOGO).to_string(), DisassembledChip8::new(&rom).to_string());
+}
+
+#[test]
+fn resolves_labels_constants_and_data() {
+    let source = "
+        HEIGHT equ 3
+        LAST = HEIGHT - 1 ; constants can use each other
+        start:  LD I, sprite
+                DRW V0, V0, HEIGHT
+                JMP start
+        sprite: db %11110000, #144, F0
+                dw sprite+LAST, 0x1234
+        loop:   JP loop
+    ";
+    let rom = assemble(source).unwrap();
+
+    assert_eq!(vec![0xA2, 0x06, 0xD0, 0x03, 0x12, 0x00, 0xF0, 0x90, 0xF0, 0x02, 0x08, 0x12, 0x34, 0x12, 0x0D], rom);
+}
+
+#[test]
+fn names_starting_with_long_are_not_the_keyword() {
+    let source = "
+        long_count equ 5
+                ADD V1, long_count
+                LD I, longtable
+                LD I, long 0x1234
+        end:    JMP end
+        longtable: db 1
+    ";
+    let rom = assemble(source).unwrap();
+
+    assert_eq!(vec![0x71, 0x05, 0xA2, 0x0A, 0xF0, 0x00, 0x12, 0x34, 0x12, 0x08, 0x01], rom);
+}
+
+#[test]
+fn includes_other_files() {
+    let mut files = HashMap::new();
+    files.insert("font.asm", "digit: db F0, 90\n");
+    files.insert("loop.asm", "include \"loop.asm\"\n");
+    let mut load = |_: &str, path: &str| files.get(path).map(|source| (path.to_string(), source.to_string())).ok_or(format!("no {}", path));
+
+    let rom = assemble_with("main.asm", "LD I, digit\ninclude \"font.asm\"", &mut load).unwrap();
+    assert_eq!(vec![0xA2, 0x02, 0xF0, 0x90], rom);
+
+    let err = assemble_with("main.asm", "CLS\ninclude \"loop.asm\"", &mut load).unwrap_err();
+    assert_eq!(("main.asm".to_string(), 2), (err.file, err.line));
+}
+
+#[test]
+fn errors_point_at_the_source_line() {
+    let mut load = |_: &str, _: &str| Ok((String::from("sprites.asm"), String::from("db 1\ndb 100\n")));
+
+    let err = assemble_with("game.asm", "CLS\ninclude sprites.asm\nRET", &mut load).unwrap_err();
+    assert_eq!(("sprites.asm".to_string(), 2, "db 100".to_string()), (err.file.clone(), err.line, err.source.clone()));
+    assert!(err.to_string().starts_with("sprites.asm:2: "));
+
+    assert_eq!(3, assemble("CLS\n\nLD V0, nowhere").unwrap_err().line);
+    assert_eq!(2, assemble("CLS\nFOO V1").unwrap_err().line);
+    assert_eq!(1, assemble("DRW V0, V1").unwrap_err().line);
+    assert_eq!(1, assemble("LD V0, 100").unwrap_err().line);
+    assert_eq!(2, assemble("a: CLS\na: RET").unwrap_err().line);
+
+    let err = assemble("A = B\nB = A\nLD V0, A").unwrap_err();
+    assert_eq!(3, err.line);
+    assert!(err.message.contains("itself"));
+}
+
+#[test]
+fn non_ascii_source_is_an_error() {
+    assert_eq!(1, assemble("X ééé").unwrap_err().line);
+    assert_eq!(2, assemble("CLS\nLD V0, é").unwrap_err().line);
+    assert_eq!(1, assemble("é equ 1").unwrap_err().line);
+}
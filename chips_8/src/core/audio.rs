@@ -0,0 +1,197 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use super::machine::FRAMES_PER_SECOND;
+use super::ops::MyChips8;
+
+pub const DEFAULT_SAMPLE_RATE: u32 = 44100;
+
+// Where generated samples go, signed 16 bit mono at the rate the sink asks for
+pub trait AudioSink {
+    fn sample_rate(&self) -> u32;
+
+    fn write(&mut self, samples: &[i16]);
+
+    // Called once playback is over, sinks that buffer write out here
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: AudioSink + ?Sized> AudioSink for Box<S> {
+    fn sample_rate(&self) -> u32 {
+        (**self).sample_rate()
+    }
+
+    fn write(&mut self, samples: &[i16]) {
+        (**self).write(samples)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioSettings {
+    pub volume: f32,    // 0.0 - 1.0
+    pub frequency: f32, // Hz of the square wave, XO-CHIP patterns use the CPU's pitch instead
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings { volume: 0.25, frequency: 440.0, muted: false }
+    }
+}
+
+// Turns the buzzer into samples one frame at a time, a square wave while the sound timer runs and the
+// loaded pattern instead for XO-CHIP programs that gave one
+pub struct Audio<S: AudioSink> {
+    pub settings: AudioSettings,
+    sink: S,
+    sample_rate: u32,
+    frame: u64,
+    phase: f64, // Position in the wave or pattern, carried across frames so the tone has no clicks
+}
+
+impl<S: AudioSink> Audio<S> {
+    // Samples are generated at the sink's rate, so what gets written always matches what it plays back at
+    pub fn new(sink: S) -> Audio<S> {
+        let sample_rate = sink.sample_rate().max(1);
+        Audio { settings: AudioSettings::default(), sink, sample_rate, frame: 0, phase: 0.0 }
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    // Generates the samples for the frame the CPU just ran, call it once after every frame
+    pub fn run_frame(&mut self, cpu: &MyChips8) {
+        // Spread evenly so rates that do not divide by 60 still average out
+        let start = self.frame * self.sample_rate as u64 / FRAMES_PER_SECOND;
+        self.frame += 1;
+        let count = (self.frame * self.sample_rate as u64 / FRAMES_PER_SECOND - start) as usize;
+
+        let amplitude = if self.settings.muted { 0.0 } else { self.settings.volume.clamp(0.0, 1.0) as f64 * i16::MAX as f64 };
+        let pattern = cpu.audio_pattern();
+        let use_pattern = cpu.quirks().xo_chip && pattern.iter().any(|byte| *byte != 0);
+
+        let samples: Vec<i16> = if !cpu.is_buzzer_on() {
+            self.phase = 0.0;
+            vec![0; count]
+        } else if use_pattern {
+            // 128 one bit samples played at the pitch rate, looping
+            let step = cpu.playback_rate() / self.sample_rate as f64;
+            (0..count)
+                .map(|_| {
+                    let bit = self.phase as usize % 128;
+                    self.phase = (self.phase + step) % 128.0;
+                    if pattern[bit / 8] >> (7 - bit % 8) & 1 == 1 { amplitude as i16 } else { -amplitude as i16 }
+                })
+                .collect()
+        } else {
+            let step = self.settings.frequency.max(0.0) as f64 / self.sample_rate as f64;
+            (0..count)
+                .map(|_| {
+                    let high = self.phase < 0.5;
+                    self.phase = (self.phase + step) % 1.0;
+                    if high { amplitude as i16 } else { -amplitude as i16 }
+                })
+                .collect()
+        };
+        self.sink.write(&samples);
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+}
+
+// Keeps everything in memory and writes a 16 bit mono PCM WAV file on flush, when given a path
+pub struct WavSink {
+    path: Option<PathBuf>,
+    sample_rate: u32,
+    samples: Vec<i16>,
+}
+
+impl WavSink {
+    pub fn new(sample_rate: u32) -> WavSink {
+        WavSink { path: None, sample_rate, samples: Vec::new() }
+    }
+
+    pub fn to_file(path: &Path, sample_rate: u32) -> WavSink {
+        WavSink { path: Some(path.to_path_buf()), sample_rate, samples: Vec::new() }
+    }
+
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let data_len = self.samples.len() as u32 * 2;
+        let mut bytes = Vec::with_capacity(44 + data_len as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // Mono
+        bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(self.sample_rate * 2).to_le_bytes()); // Bytes per second
+        bytes.extend_from_slice(&2u16.to_le_bytes()); // Bytes per sample
+        bytes.extend_from_slice(&16u16.to_le_bytes()); // Bits per sample
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        self.samples.iter().for_each(|sample| bytes.extend_from_slice(&sample.to_le_bytes()));
+        bytes
+    }
+}
+
+impl AudioSink for WavSink {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn write(&mut self, samples: &[i16]) {
+        self.samples.extend_from_slice(samples);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => fs::write(path, self.to_bytes()),
+            None => Ok(()),
+        }
+    }
+}
+
+// Rings the terminal bell whenever a tone starts, for front ends without a sound device
+#[derive(Default)]
+pub struct BellSink {
+    sounding: bool,
+}
+
+impl AudioSink for BellSink {
+    // Only whether a tone is playing matters here
+    fn sample_rate(&self) -> u32 {
+        DEFAULT_SAMPLE_RATE
+    }
+
+    fn write(&mut self, samples: &[i16]) {
+        let sounding = samples.iter().any(|sample| *sample != 0);
+        if sounding && !self.sounding {
+            print!("\x07");
+            io::stdout().flush().ok();
+        }
+        self.sounding = sounding;
+    }
+}
+
+#[cfg(test)]
+#[path ="./audio_test.rs"]
+mod audio_test;
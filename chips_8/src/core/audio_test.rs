@@ -0,0 +1,81 @@
+use super::*;
+use crate::core::machine::Machine;
+use crate::core::quirks::Quirks;
+
+// LD V0, 03 / LD ST, V0 / JP 204
+const BEEP: &[u8] = &[0x60, 0x03, 0xF0, 0x18, 0x12, 0x04];
+
+fn play(rom: &[u8], quirks: Quirks, frames: u64, settings: AudioSettings) -> Vec<i16> {
+    let mut cpu = MyChips8::new_with_quirks(quirks);
+    cpu.load_rom(rom).unwrap();
+    let mut machine = Machine::new(cpu);
+    let mut audio = Audio::new(WavSink::new(DEFAULT_SAMPLE_RATE));
+    audio.settings = settings;
+    for _ in 0..frames {
+        machine.run_frame().unwrap();
+        audio.run_frame(&machine.cpu);
+    }
+    audio.sink().samples().to_vec()
+}
+
+#[test]
+fn tone_lasts_as_many_frames_as_the_sound_timer() {
+    let samples = play(BEEP, Quirks::default(), 6, AudioSettings::default());
+    assert_eq!(samples.len(), 6 * 735);
+    let sounding = samples.chunks(735).map(|frame| frame.iter().any(|sample| *sample != 0)).collect::<Vec<_>>();
+    assert_eq!(sounding, vec![true, true, true, false, false, false]);
+}
+
+#[test]
+fn square_wave_follows_frequency_and_volume() {
+    let settings = AudioSettings { volume: 0.5, frequency: 441.0, muted: false };
+    let samples = play(BEEP, Quirks::default(), 1, settings);
+    let high = (i16::MAX as f64 * 0.5) as i16;
+    // 441Hz at 44100 is 100 samples a period, half high then half low
+    assert!(samples[..50].iter().all(|sample| *sample == high));
+    assert!(samples[50..100].iter().all(|sample| *sample == -high));
+    assert_eq!(samples[100], high);
+}
+
+#[test]
+fn muted_audio_is_silent_but_keeps_time() {
+    let settings = AudioSettings { muted: true, ..AudioSettings::default() };
+    let samples = play(BEEP, Quirks::default(), 4, settings);
+    assert_eq!(samples.len(), 4 * 735);
+    assert!(samples.iter().all(|sample| *sample == 0));
+}
+
+#[test]
+fn sample_count_does_not_drift_at_odd_rates() {
+    // The rate comes from the sink, there is no second one to disagree with the WAV header
+    let mut audio = Audio::new(WavSink::new(22050));
+    assert_eq!(22050, audio.sample_rate());
+    let cpu = MyChips8::new();
+    (0..60).for_each(|_| audio.run_frame(&cpu));
+    assert_eq!(audio.sink().samples().len(), 22050);
+}
+
+#[test]
+fn xo_chip_plays_the_loaded_pattern() {
+    // I = 20A / F002 loads the pattern / ST = 3 / loop, pattern alternates 8 bits on and 8 off
+    let rom = &[0xA2, 0x0C, 0xF0, 0x02, 0x60, 0x03, 0xF0, 0x18, 0x12, 0x08, 0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00];
+    let samples = play(rom, Quirks::xo_chip(), 1, AudioSettings::default());
+    let high = (i16::MAX as f64 * 0.25) as i16;
+    // 4000Hz pattern at 44100 is about 11 samples a bit, so 88 high then 88 low
+    assert!(samples[..85].iter().all(|sample| *sample == high));
+    assert!(samples[90..170].iter().all(|sample| *sample == -high));
+}
+
+#[test]
+fn wav_bytes_have_a_pcm_header() {
+    let mut sink = WavSink::new(8000);
+    sink.write(&[1, -2]);
+    let bytes = sink.to_bytes();
+    assert_eq!(bytes.len(), 48);
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]), 40);
+    assert_eq!(&bytes[8..16], b"WAVEfmt ");
+    assert_eq!(u32::from_le_bytes([bytes[24], bytes[25], bytes[26], bytes[27]]), 8000);
+    assert_eq!(&bytes[36..40], b"data");
+    assert_eq!(&bytes[44..], &[0x01, 0x00, 0xFE, 0xFF]);
+}
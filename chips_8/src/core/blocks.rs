@@ -0,0 +1,206 @@
+use super::error::Chip8Error;
+use super::instruction::Instruction;
+use super::machine::Engine;
+use super::ops::{MyChips8, StepOutcome};
+
+// Longest run of instructions decoded in one go, also bounds how far back a write has to look for blocks
+const MAX_BLOCK_LENGTH: usize = 64;
+
+// Straight-line run of decoded instructions, the last one is the only one that can branch
+struct Block {
+    ops: Vec<(u16, Instruction)>, // Opcode and instruction, two bytes each from the start address
+}
+
+// Alternative to MyChips8::enumlate_cycle for batch runs, every block is decoded once and then replayed
+// from the cache until something writes over it (Fx55, Fx33, SAVE, restore, poke...)
+// Instruction counts, outcomes and faults are the same as the plain interpreter, so frame timing is too
+#[derive(Default)]
+pub struct BlockCache {
+    blocks: Vec<Option<Block>>,     // Indexed by start address, a jump into a block starts a new one
+    code: Vec<bool>,                // Bytes some block was decoded from, writes anywhere else are free
+    count: usize,
+    current: Option<(usize, usize)>, // Start of the block being run and the index of the next instruction
+    invalidations: u64,
+}
+
+impl BlockCache {
+    pub fn new() -> BlockCache {
+        BlockCache::default()
+    }
+
+    // Blocks currently decoded
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    // Blocks thrown away because memory under them changed
+    pub fn invalidations(&self) -> u64 {
+        self.invalidations
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.iter_mut().for_each(|block| *block = None);
+        self.code.iter_mut().for_each(|code| *code = false);
+        self.count = 0;
+        self.current = None;
+    }
+
+    fn sync(&mut self, cpu: &mut MyChips8) {
+        if self.blocks.len() != cpu.memory().len() {
+            self.blocks = (0..cpu.memory().len()).map(|_| None).collect();
+            self.code = vec![false; cpu.memory().len()];
+            self.count = 0;
+            self.current = None;
+        }
+        if let Some((low, high)) = cpu.take_written() {
+            self.invalidate(low, high);
+        }
+    }
+
+    // False when not even one instruction at pc decodes
+    fn decode(&mut self, cpu: &MyChips8, pc: usize) -> bool {
+        match self.blocks.get(pc) {
+            Some(Some(_)) => return true,
+            Some(None) => {}
+            None => return false,
+        }
+
+        let memory = cpu.memory();
+        let mut ops = Vec::new();
+        let mut address = pc;
+        while ops.len() < MAX_BLOCK_LENGTH && address + 1 < memory.len() {
+            let opcode = (memory[address] as u16) << 8 | memory[address + 1] as u16;
+            let instruction = match Instruction::decode(opcode) {
+                Ok(instruction) => instruction,
+                Err(_) => break,
+            };
+            ops.push((opcode, instruction));
+            address += 2;
+            if ends_block(instruction) {
+                break;
+            }
+        }
+        if ops.is_empty() {
+            return false;
+        }
+        self.code[pc..address].iter_mut().for_each(|code| *code = true);
+        self.blocks[pc] = Some(Block { ops });
+        self.count += 1;
+        true
+    }
+
+    fn invalidate(&mut self, low: usize, high: usize) {
+        // Only blocks starting up to one block length before the write can reach into it
+        let first = low.saturating_sub(MAX_BLOCK_LENGTH * 2 - 1);
+        let last = high.min(self.blocks.len().saturating_sub(1));
+        if low > last || !self.code[low..=last].contains(&true) {
+            return;
+        }
+        for start in first..=last {
+            let stale = match &self.blocks[start] {
+                Some(block) => low < start + block.ops.len() * 2,
+                None => false,
+            };
+            if stale {
+                self.blocks[start] = None;
+                self.count -= 1;
+                self.invalidations += 1;
+                self.current = None;
+            }
+        }
+        // Nothing left overlaps the write
+        self.code[low..=last].iter_mut().for_each(|code| *code = false);
+    }
+}
+
+impl Engine for BlockCache {
+    // Single instructions for debuggers and anything else that steps, a cursor keeps it to one lookup per block
+    fn step(&mut self, cpu: &mut MyChips8) -> Result<StepOutcome, Chip8Error> {
+        self.sync(cpu);
+        if cpu.is_waiting_for_key() {
+            return cpu.enumlate_cycle();
+        }
+
+        let pc = cpu.pc as usize;
+        let (start, index) = match self.current {
+            Some((start, index)) if start + index * 2 == pc => (start, index),
+            _ => {
+                // Nothing decodable here, the interpreter reports the fault
+                if !self.decode(cpu, pc) {
+                    return cpu.enumlate_cycle();
+                }
+                (pc, 0)
+            }
+        };
+        let ops = &self.blocks[start].as_ref().unwrap().ops;
+        let (opcode, instruction) = ops[index];
+        self.current = if index + 1 < ops.len() { Some((start, index + 1)) } else { None };
+        cpu.run_decoded(opcode, instruction)
+    }
+
+    // A whole block per lookup
+    fn run(&mut self, cpu: &mut MyChips8, limit: u64) -> Result<(u64, bool, StepOutcome), Chip8Error> {
+        self.sync(cpu);
+        self.current = None;
+        let mut executed = 0;
+        let mut drew = false;
+        while executed < limit {
+            let pc = cpu.pc as usize;
+            if cpu.is_waiting_for_key() || !self.decode(cpu, pc) {
+                match cpu.enumlate_cycle()? {
+                    StepOutcome::Executed => {}
+                    StepOutcome::Drew => drew = true,
+                    outcome => return Ok((executed, drew, outcome)),
+                }
+                executed += 1;
+                continue;
+            }
+
+            let ops = &self.blocks[pc].as_ref().unwrap().ops;
+            for &(opcode, instruction) in ops.iter().take((limit - executed) as usize) {
+                match cpu.run_decoded(opcode, instruction)? {
+                    StepOutcome::Executed => {}
+                    StepOutcome::Drew => drew = true,
+                    outcome => return Ok((executed, drew, outcome)),
+                }
+                executed += 1;
+                // The rest of this block may just have been overwritten
+                if cpu.has_written() {
+                    break;
+                }
+            }
+            if let Some((low, high)) = cpu.take_written() {
+                self.invalidate(low, high);
+            }
+        }
+        Ok((executed, drew, StepOutcome::Executed))
+    }
+}
+
+// Anything that can move PC somewhere other than the next instruction, LD I, LONG included since
+// its address word would otherwise be decoded as an instruction
+fn ends_block(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Jp { .. }
+            | Instruction::JpV0 { .. }
+            | Instruction::Call { .. }
+            | Instruction::Ret
+            | Instruction::Exit
+            | Instruction::SeByte { .. }
+            | Instruction::SneByte { .. }
+            | Instruction::SeReg { .. }
+            | Instruction::SneReg { .. }
+            | Instruction::Skp { .. }
+            | Instruction::Sknp { .. }
+            | Instruction::LdILong
+    )
+}
+
+#[cfg(test)]
+#[path ="./blocks_test.rs"]
+mod blocks_test;
@@ -0,0 +1,131 @@
+use super::*;
+use crate::core::machine::{Engine, Machine};
+use crate::core::quirks::Quirks;
+use crate::core::random::XorShift;
+
+const IBM_LOGO: &[u8] = include_bytes!("../IBM_Logo.ch8");
+
+fn machine(rom: &[u8], quirks: Quirks, cached: bool) -> Machine {
+    let mut cpu = MyChips8::new_with_quirks(quirks);
+    cpu.set_random_source(Box::new(XorShift::new(3)));
+    cpu.load_rom(rom).unwrap();
+    let mut machine = Machine::new(cpu);
+    machine.set_block_cache(cached);
+    machine
+}
+
+// Runs both engines side by side and compares every frame, faults included
+fn assert_same_as_interpreter(rom: &[u8], quirks: Quirks, frames: u64) -> Machine {
+    let mut plain = machine(rom, quirks, false);
+    let mut cached = machine(rom, quirks, true);
+    for frame in 0..frames {
+        let expected = plain.run_frame();
+        assert_eq!(expected, cached.run_frame(), "frame {}", frame);
+        assert_eq!(plain.cpu.snapshot(), cached.cpu.snapshot(), "frame {}", frame);
+        if expected.is_err() || expected.unwrap().exited {
+            break;
+        }
+    }
+    cached
+}
+
+#[test]
+fn ibm_logo_matches_the_interpreter() {
+    for quirks in [Quirks::cosmac_vip(), Quirks::chip48(), Quirks::super_chip(), Quirks::xo_chip()] {
+        assert_same_as_interpreter(IBM_LOGO, quirks, 30);
+    }
+}
+
+#[test]
+fn code_written_by_fx55_is_decoded_again() {
+    let rom = &[
+        0xA2, 0x10, // LD I, 210
+        0x22, 0x10, // CALL 210, V5 = 05
+        0x60, 0x75, // LD V0, 75
+        0x61, 0x01, // LD V1, 01
+        0xF1, 0x55, // LD [I], V1 turns 210 into ADD V5, 01
+        0x22, 0x10, // CALL 210, V5 = 06
+        0x12, 0x0C, // JP 20C
+        0x00, 0x00,
+        0x65, 0x05, // 210: LD V5, 05
+        0x00, 0xEE, // RET
+    ];
+    let machine = assert_same_as_interpreter(rom, Quirks::default(), 2);
+    assert_eq!(0x06, machine.cpu.registers()[5]);
+
+    let mut cpu = MyChips8::new();
+    cpu.load_rom(rom).unwrap();
+    let mut cache = BlockCache::new();
+    cache.run(&mut cpu, 20).unwrap();
+    assert!(!cache.is_empty());
+    assert_eq!(1, cache.invalidations());
+}
+
+#[test]
+fn code_written_by_fx33_is_decoded_again() {
+    let rom = &[
+        0xA2, 0x11, // LD I, 211
+        0x60, 0x7B, // LD V0, 123
+        0x22, 0x10, // CALL 210, adds 0
+        0xF0, 0x33, // LD B, V0 turns 210 into ADD V5, 01 and 212 into SYS 203
+        0x22, 0x10, // CALL 210, adds 1
+        0x12, 0x0A, // JP 20A
+        0x00, 0x00,
+        0x00, 0x00,
+        0x75, 0x00, // 210: ADD V5, 00
+        0x00, 0x00, // SYS 000
+        0x00, 0xEE, // RET
+    ];
+    let machine = assert_same_as_interpreter(rom, Quirks::default(), 2);
+    assert_eq!(0x01, machine.cpu.registers()[5]);
+}
+
+#[test]
+fn poke_and_restore_invalidate_the_cache() {
+    // LD V0, 00 / ADD V0, 01 / JP 202
+    let rom = &[0x60, 0x00, 0x70, 0x01, 0x12, 0x02];
+    let mut machine = machine(rom, Quirks::default(), true);
+    let start = machine.cpu.snapshot();
+    machine.run_frame().unwrap();
+
+    // ADD V0, 02 from now on
+    machine.cpu.poke(0x203, 0x02);
+    let before = machine.cpu.registers()[0];
+    machine.run_frame().unwrap();
+    assert_eq!(before.wrapping_add(12), machine.cpu.registers()[0]);
+
+    machine.cpu.restore(&start);
+    machine.run_frame().unwrap();
+    assert_eq!(6, machine.cpu.registers()[0]);
+}
+
+#[test]
+fn random_programs_match_the_interpreter() {
+    let mut rng = XorShift::new(0xC8);
+    for _ in 0..200 {
+        let mut rom = Vec::new();
+        while rom.len() < 64 {
+            let opcode = rng.next_u64() as u16;
+            // Only decodable opcodes so programs get somewhere before faulting, jumps kept inside the ROM
+            let opcode = match opcode >> 12 {
+                0x1 | 0x2 | 0xB => opcode & 0xF03F | 0x0200,
+                _ => opcode,
+            };
+            if Instruction::decode(opcode).is_ok() {
+                rom.extend_from_slice(&opcode.to_be_bytes());
+            }
+        }
+        assert_same_as_interpreter(&rom, Quirks::default(), 10);
+        assert_same_as_interpreter(&rom, Quirks::xo_chip(), 10);
+    }
+}
+
+#[test]
+fn stepping_matches_the_interpreter() {
+    let mut plain = machine(IBM_LOGO, Quirks::chip48(), false);
+    let mut cached = machine(IBM_LOGO, Quirks::chip48(), true);
+    for _ in 0..200 {
+        assert_eq!(plain.step(), cached.step());
+        assert_eq!(plain.cpu.snapshot(), cached.cpu.snapshot());
+    }
+}
@@ -0,0 +1,170 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+// What a read or write past the end of memory does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutOfRange {
+    Wrap,  // Address modulo the memory size
+    #[default]
+    Fault, // MemoryOutOfBounds, nothing is read or written
+    Zero,  // Reads give 0 and writes are dropped
+}
+
+impl OutOfRange {
+    // Names used on the command line
+    pub fn from_name(name: &str) -> Option<OutOfRange> {
+        match name {
+            "wrap" => Some(OutOfRange::Wrap),
+            "fault" => Some(OutOfRange::Fault),
+            "zero" => Some(OutOfRange::Zero),
+            _ => None,
+        }
+    }
+}
+
+// Memory as the CPU sees it, the out of range policy is applied before read and write are called
+// so the address is always inside bytes(). Instructions are fetched from bytes() without a read
+pub trait Bus {
+    // The whole address space, for snapshots, disassembly and the block cache
+    fn bytes(&self) -> &[u8];
+
+    fn read(&mut self, address: usize) -> u8 {
+        self.bytes()[address]
+    }
+
+    fn write(&mut self, address: usize, value: u8);
+}
+
+// Plain memory, 4K or 64K going by the quirks unless the front end picks another size
+pub struct Ram {
+    bytes: Vec<u8>,
+}
+
+impl Ram {
+    pub fn new(size: usize) -> Ram {
+        Ram { bytes: vec![0; size] }
+    }
+}
+
+impl From<Vec<u8>> for Ram {
+    fn from(bytes: Vec<u8>) -> Ram {
+        Ram { bytes }
+    }
+}
+
+impl Bus for Ram {
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn read(&mut self, address: usize) -> u8 {
+        self.bytes[address]
+    }
+
+    fn write(&mut self, address: usize, value: u8) {
+        self.bytes[address] = value;
+    }
+}
+
+// Sees every data read and write an instruction makes, instruction fetches are not included
+// Both return the value that actually gets used, which is how a cheat pins an address
+pub trait BusObserver {
+    fn read(&mut self, _pc: u16, _address: usize, value: u8) -> u8 {
+        value
+    }
+
+    fn write(&mut self, _pc: u16, _address: usize, value: u8) -> u8 {
+        value
+    }
+}
+
+// Lets the front end keep a handle on an observer it gave to the CPU
+impl<O: BusObserver> BusObserver for Rc<RefCell<O>> {
+    fn read(&mut self, pc: u16, address: usize, value: u8) -> u8 {
+        self.borrow_mut().read(pc, address, value)
+    }
+
+    fn write(&mut self, pc: u16, address: usize, value: u8) -> u8 {
+        self.borrow_mut().write(pc, address, value)
+    }
+}
+
+// Reads and writes per address, for showing which parts of memory a program works on
+#[derive(Default)]
+pub struct Heatmap {
+    reads: Vec<u32>,
+    writes: Vec<u32>,
+}
+
+impl Heatmap {
+    pub fn new() -> Heatmap {
+        Heatmap::default()
+    }
+
+    pub fn reads(&self, address: usize) -> u32 {
+        self.reads.get(address).copied().unwrap_or(0)
+    }
+
+    pub fn writes(&self, address: usize) -> u32 {
+        self.writes.get(address).copied().unwrap_or(0)
+    }
+
+    pub fn clear(&mut self) {
+        self.reads.clear();
+        self.writes.clear();
+    }
+}
+
+fn count(counts: &mut Vec<u32>, address: usize) {
+    if counts.len() <= address {
+        counts.resize(address + 1, 0);
+    }
+    counts[address] = counts[address].saturating_add(1);
+}
+
+impl BusObserver for Heatmap {
+    fn read(&mut self, _pc: u16, address: usize, value: u8) -> u8 {
+        count(&mut self.reads, address);
+        value
+    }
+
+    fn write(&mut self, _pc: u16, address: usize, value: u8) -> u8 {
+        count(&mut self.writes, address);
+        value
+    }
+}
+
+// Cheat freezes, the program reads the frozen value and anything it writes there is replaced by it
+#[derive(Default)]
+pub struct Freeze {
+    values: BTreeMap<usize, u8>,
+}
+
+impl Freeze {
+    pub fn new() -> Freeze {
+        Freeze::default()
+    }
+
+    pub fn set(&mut self, address: usize, value: u8) {
+        self.values.insert(address, value);
+    }
+
+    pub fn remove(&mut self, address: usize) {
+        self.values.remove(&address);
+    }
+}
+
+impl BusObserver for Freeze {
+    fn read(&mut self, _pc: u16, address: usize, value: u8) -> u8 {
+        self.values.get(&address).copied().unwrap_or(value)
+    }
+
+    fn write(&mut self, _pc: u16, address: usize, value: u8) -> u8 {
+        self.values.get(&address).copied().unwrap_or(value)
+    }
+}
+
+#[cfg(test)]
+#[path ="./bus_test.rs"]
+mod bus_test;
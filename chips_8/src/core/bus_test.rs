@@ -0,0 +1,110 @@
+use super::*;
+use crate::core::error::Chip8Error;
+use crate::core::ops::MyChips8;
+
+// LD I, FFE / LD V0, 7B / LD B, V0 writes 0FFE, 0FFF and one byte past the end / LD V2, [I] reads them back
+const EDGE: [u8; 8] = [0xAF, 0xFE, 0x60, 0x7B, 0xF0, 0x33, 0xF2, 0x65];
+
+fn run(my_chip_8: &mut MyChips8, rom: &[u8]) -> Result<(), Chip8Error> {
+    my_chip_8.load_rom(rom).unwrap();
+    for _ in 0..rom.len() / 2 {
+        my_chip_8.enumlate_cycle()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn out_of_range_policies() {
+    let mut my_chip_8 = MyChips8::new();
+    assert_eq!(OutOfRange::Fault, my_chip_8.out_of_range());
+    assert_eq!(Err(Chip8Error::MemoryOutOfBounds { pc: 0x204, address: 0x1000 }), run(&mut my_chip_8, &EDGE));
+
+    let mut my_chip_8 = MyChips8::new();
+    my_chip_8.set_out_of_range(OutOfRange::Wrap);
+    run(&mut my_chip_8, &EDGE).unwrap();
+    assert_eq!([0x01, 0x02, 0x03], [my_chip_8.memory()[0xFFE], my_chip_8.memory()[0xFFF], my_chip_8.memory()[0x000]]);
+    assert_eq!([0x01, 0x02, 0x03], my_chip_8.registers()[0..3]);
+
+    let mut my_chip_8 = MyChips8::new();
+    my_chip_8.set_out_of_range(OutOfRange::Zero);
+    run(&mut my_chip_8, &EDGE).unwrap();
+    assert_eq!(0x00, my_chip_8.memory()[0x000]);
+    assert_eq!([0x01, 0x02, 0x00], my_chip_8.registers()[0..3]);
+}
+
+// Counts up every time the program reads 0x800, a stand-in for a memory mapped device
+struct Counter {
+    bytes: Vec<u8>,
+}
+
+impl Bus for Counter {
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn read(&mut self, address: usize) -> u8 {
+        if address == 0x800 {
+            self.bytes[address] += 1;
+        }
+        self.bytes[address]
+    }
+
+    fn write(&mut self, address: usize, value: u8) {
+        self.bytes[address] = value;
+    }
+}
+
+#[test]
+fn memory_mapped_bus() {
+    // LD I, 800 / LD V0, [I] / LD V0, [I] under the default quirks, which leave I alone
+    let rom = [0xA8, 0x00, 0xF0, 0x65, 0xF0, 0x65];
+    let mut my_chip_8 = MyChips8::new();
+    my_chip_8.set_bus(Box::new(Counter { bytes: vec![0; 0x1000] })).unwrap();
+    run(&mut my_chip_8, &rom).unwrap();
+    assert_eq!(0x02, my_chip_8.registers()[0]);
+
+    // The font came along and a restore writes through instead of swapping the bus out
+    assert_eq!(0xF0, my_chip_8.memory()[0x50]);
+    let state = my_chip_8.snapshot();
+    my_chip_8.restore(&state);
+    my_chip_8.pc = 0x204;
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x03, my_chip_8.registers()[0]);
+}
+
+#[test]
+fn other_memory_sizes() {
+    let mut my_chip_8 = MyChips8::new();
+    assert_eq!(Err(Chip8Error::MemoryTooSmall { size: 0x200 }), my_chip_8.set_bus(Box::new(Ram::new(0x200))));
+
+    my_chip_8.set_bus(Box::new(Ram::new(0x2000))).unwrap();
+    assert_eq!(0x2000, my_chip_8.memory().len());
+    // LD I, FFF / LD B, V0 now fits
+    run(&mut my_chip_8, &[0xAF, 0xFF, 0xF0, 0x33]).unwrap();
+    assert!(my_chip_8.load_rom(&vec![0; 0x1E00]).is_ok());
+    assert!(my_chip_8.load_rom(&vec![0; 0x1E01]).is_err());
+}
+
+#[test]
+fn observers_see_data_accesses() {
+    let heatmap = Rc::new(RefCell::new(Heatmap::new()));
+    let mut freeze = Freeze::new();
+    freeze.set(0xFFF, 0x09);
+
+    let mut my_chip_8 = MyChips8::new();
+    my_chip_8.set_out_of_range(OutOfRange::Wrap);
+    my_chip_8.add_observer(Box::new(Rc::clone(&heatmap)));
+    my_chip_8.add_observer(Box::new(freeze));
+    run(&mut my_chip_8, &EDGE).unwrap();
+
+    // Counted where the access landed, fetches left out
+    let heatmap = heatmap.borrow();
+    assert_eq!((1, 1), (heatmap.reads(0xFFE), heatmap.writes(0xFFE)));
+    assert_eq!((1, 1), (heatmap.reads(0x000), heatmap.writes(0x000)));
+    assert_eq!((0, 0), (heatmap.reads(0x200), heatmap.writes(0x1000)));
+
+    // The frozen byte kept its value however the program wrote it
+    assert_eq!(0x09, my_chip_8.memory()[0xFFF]);
+    assert_eq!([0x01, 0x09, 0x03], my_chip_8.registers()[0..3]);
+    assert_eq!(2, my_chip_8.take_observers().len());
+}
@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use super::disassemble::{DisassembledChip8, DisassembledOp};
+use super::instruction::Instruction;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Fallthrough,
+    Jump,
+    Indirect, // JP V0, only the first entry of the table is known
+    Skip,     // Taken side of a skip instruction
+    Call,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize, // First address past the block
+    pub ops: Vec<DisassembledOp>,
+    pub successors: Vec<(usize, EdgeKind)>,
+}
+
+// Basic blocks of the code a recursive disassembly reaches
+// Blocks end at jumps, calls, returns and skips, or where another block starts
+pub struct ControlFlowGraph {
+    blocks: BTreeMap<usize, BasicBlock>,
+    subroutines: BTreeSet<usize>,
+    labels: BTreeMap<usize, String>,
+}
+
+impl ControlFlowGraph {
+    pub fn new(bytes: &[u8]) -> ControlFlowGraph {
+        ControlFlowGraph::from_disassembly(&DisassembledChip8::recursive(bytes))
+    }
+
+    pub fn from_disassembly(listing: &DisassembledChip8) -> ControlFlowGraph {
+        let code: Vec<&DisassembledOp> = listing.iter().filter(|op| op.instruction.is_some()).collect();
+        let labels = listing.iter().filter_map(|op| op.label.clone().map(|label| (op.address, label))).collect();
+
+        // Every instruction's successors, then leaders are the start, every target and everything after a branch
+        let mut leaders: BTreeSet<usize> = code.first().map(|op| op.address).into_iter().collect();
+        let mut subroutines = BTreeSet::new();
+        let mut exits: BTreeMap<usize, (bool, Vec<(usize, EdgeKind)>)> = BTreeMap::new();
+        for op in &code {
+            let next = op.address + op.bytes.len();
+            let (ends, successors) = match op.instruction.unwrap() {
+                Instruction::Jp { nnn } => (true, vec![(nnn as usize, EdgeKind::Jump)]),
+                Instruction::JpV0 { nnn } => (true, vec![(nnn as usize, EdgeKind::Indirect)]),
+                Instruction::Call { nnn } => {
+                    subroutines.insert(nnn as usize);
+                    (true, vec![(nnn as usize, EdgeKind::Call), (next, EdgeKind::Fallthrough)])
+                }
+                Instruction::Ret | Instruction::Exit => (true, Vec::new()),
+                Instruction::SeByte { .. }
+                | Instruction::SneByte { .. }
+                | Instruction::SeReg { .. }
+                | Instruction::SneReg { .. }
+                | Instruction::Skp { .. }
+                | Instruction::Sknp { .. } => {
+                    let skipped = code.iter().find(|other| other.address == next).map_or(2, |other| other.bytes.len());
+                    (true, vec![(next, EdgeKind::Fallthrough), (next + skipped, EdgeKind::Skip)])
+                }
+                _ => (false, vec![(next, EdgeKind::Fallthrough)]),
+            };
+            if ends {
+                leaders.extend(successors.iter().map(|(target, _)| *target));
+            }
+            exits.insert(op.address, (ends, successors));
+        }
+
+        let mut blocks = BTreeMap::new();
+        let mut current: Option<BasicBlock> = None;
+        for op in code {
+            if leaders.contains(&op.address) || current.as_ref().is_some_and(|block| block.end != op.address) {
+                blocks.extend(current.take().map(|block| (block.start, block)));
+            }
+            let block = current.get_or_insert_with(|| BasicBlock { start: op.address, end: op.address, ops: Vec::new(), successors: Vec::new() });
+            block.ops.push(op.clone());
+            block.end = op.address + op.bytes.len();
+
+            let (ends, successors) = &exits[&op.address];
+            block.successors = successors.clone();
+            if *ends || leaders.contains(&block.end) {
+                blocks.extend(current.take().map(|block| (block.start, block)));
+            }
+        }
+        blocks.extend(current.map(|block| (block.start, block)));
+
+        ControlFlowGraph { blocks, subroutines, labels }
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &BasicBlock> {
+        self.blocks.values()
+    }
+
+    pub fn block_at(&self, start: usize) -> Option<&BasicBlock> {
+        self.blocks.get(&start)
+    }
+
+    // Entry points of everything that is CALLed
+    pub fn subroutines(&self) -> &BTreeSet<usize> {
+        &self.subroutines
+    }
+
+    // Blocks reachable from entry without following calls, the body of the program or of a subroutine
+    pub fn body(&self, entry: usize) -> BTreeSet<usize> {
+        let mut body = BTreeSet::new();
+        let mut pending = vec![entry];
+        while let Some(start) = pending.pop() {
+            if let Some(block) = self.blocks.get(&start) {
+                if body.insert(start) {
+                    pending.extend(block.successors.iter().filter(|(_, kind)| *kind != EdgeKind::Call).map(|(target, _)| *target));
+                }
+            }
+        }
+        body
+    }
+
+    // Graphviz listing, each subroutine and the main program get their own cluster
+    // Skips are dashed, calls dotted and jumps bold so the shape of the code reads at a glance
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cfg {\n    node [shape=box, fontname=\"monospace\"];\n");
+
+        let mut placed = HashSet::new();
+        let entries = self.blocks.keys().next().copied().into_iter().filter(|entry| !self.subroutines.contains(entry));
+        for entry in entries.chain(self.subroutines.iter().copied()) {
+            let name = if self.subroutines.contains(&entry) { self.name(entry) } else { String::from("main") };
+            let members: Vec<usize> = self.body(entry).into_iter().filter(|start| placed.insert(*start)).collect();
+            if members.is_empty() {
+                continue;
+            }
+            dot.push_str(&format!("    subgraph cluster_{} {{\n        label=\"{}\";\n", name, name));
+            for start in members {
+                dot.push_str(&format!("        {};\n", node(start)));
+            }
+            dot.push_str("    }\n");
+        }
+
+        for block in self.blocks.values() {
+            let mut text = String::new();
+            if let Some(label) = self.labels.get(&block.start) {
+                text.push_str(&format!("{}:\\l", label));
+            }
+            for op in &block.ops {
+                let line = format!("{:03X}  {} {}", op.address, op.mnemonic, op.operand_text());
+                text.push_str(&format!("{}\\l", line.trim_end().replace('"', "\\\"")));
+            }
+            dot.push_str(&format!("    {} [label=\"{}\"];\n", node(block.start), text));
+        }
+
+        for block in self.blocks.values() {
+            for (target, kind) in &block.successors {
+                if !self.blocks.contains_key(target) {
+                    continue;
+                }
+                let style = match kind {
+                    EdgeKind::Fallthrough => "",
+                    EdgeKind::Jump => " [style=bold]",
+                    EdgeKind::Indirect => " [style=bold, label=\"+V0\"]",
+                    EdgeKind::Skip => " [style=dashed, label=\"skip\"]",
+                    EdgeKind::Call => " [style=dotted, label=\"call\"]",
+                };
+                dot.push_str(&format!("    {} -> {}{};\n", node(block.start), node(*target), style));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn name(&self, address: usize) -> String {
+        self.labels.get(&address).cloned().unwrap_or_else(|| format!("sub_{:03X}", address))
+    }
+}
+
+fn node(address: usize) -> String {
+    format!("b_{:03X}", address)
+}
+
+#[cfg(test)]
+#[path ="./cfg_test.rs"]
+mod cfg_test;
@@ -0,0 +1,44 @@
+use super::*;
+
+// 200 CALL 20A
+// 202 SE V0, 01
+// 204 JMP 202
+// 206 LD V1, 02
+// 208 EXIT
+// 20A ADD V0, 01
+// 20C RET
+const ROM: &[u8] = &[0x22, 0x0A, 0x30, 0x01, 0x12, 0x02, 0x61, 0x02, 0x00, 0xFD, 0x70, 0x01, 0x00, 0xEE];
+
+#[test]
+fn splits_blocks_at_branches() {
+    let cfg = ControlFlowGraph::new(ROM);
+    let starts: Vec<(usize, usize)> = cfg.blocks().map(|block| (block.start, block.end)).collect();
+
+    assert_eq!(vec![(0x200, 0x202), (0x202, 0x204), (0x204, 0x206), (0x206, 0x20A), (0x20A, 0x20E)], starts);
+    assert_eq!(vec![(0x20A, EdgeKind::Call), (0x202, EdgeKind::Fallthrough)], cfg.block_at(0x200).unwrap().successors);
+    assert_eq!(vec![(0x204, EdgeKind::Fallthrough), (0x206, EdgeKind::Skip)], cfg.block_at(0x202).unwrap().successors);
+    assert!(cfg.block_at(0x20A).unwrap().successors.is_empty());
+}
+
+#[test]
+fn finds_subroutines_and_their_bodies() {
+    let cfg = ControlFlowGraph::new(ROM);
+
+    assert_eq!(&[0x20A].iter().copied().collect::<BTreeSet<usize>>(), cfg.subroutines());
+    assert_eq!(vec![0x200, 0x202, 0x204, 0x206], cfg.body(0x200).into_iter().collect::<Vec<usize>>());
+    assert_eq!(vec![0x20A], cfg.body(0x20A).into_iter().collect::<Vec<usize>>());
+}
+
+#[test]
+fn exports_dot() {
+    let dot = ControlFlowGraph::new(ROM).to_dot();
+
+    assert!(dot.starts_with("digraph cfg {\n"));
+    assert!(dot.contains("    subgraph cluster_main {\n        label=\"main\";\n        b_200;\n"));
+    assert!(dot.contains("    subgraph cluster_sub_20A {\n        label=\"sub_20A\";\n        b_20A;\n    }\n"));
+    assert!(dot.contains("    b_20A [label=\"sub_20A:\\l20A  ADD V0, 01\\l20C  RET\\l\"];\n"));
+    assert!(dot.contains("    b_200 -> b_20A [style=dotted, label=\"call\"];\n"));
+    assert!(dot.contains("    b_202 -> b_206 [style=dashed, label=\"skip\"];\n"));
+    assert!(dot.contains("    b_204 -> b_202 [style=bold];\n"));
+    assert!(dot.ends_with("}\n"));
+}
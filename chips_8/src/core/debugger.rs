@@ -0,0 +1,210 @@
+use std::fmt::Display;
+
+use super::error::Chip8Error;
+use super::machine::Machine;
+use super::ops::{AccessKind, StepOutcome};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn holds(&self, left: u16, right: u16) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+}
+
+// Register a condition looks at, V0-VF plus the two 16 bit registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    V(u8),
+    I,
+    Pc,
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Register::V(x) => write!(f, "V{:X}", x),
+            Register::I => write!(f, "I"),
+            Register::Pc => write!(f, "PC"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    // Stops before the instruction at this address runs
+    Pc(u16),
+    // Stops after an instruction reads and/or writes anything from start to end inclusive
+    Watch { start: usize, end: usize, read: bool, write: bool },
+    // Stops after an instruction makes the condition true, it has to turn false again to fire a second time
+    Register { register: Register, comparison: Comparison, value: u16 },
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Pc(address) => write!(f, "pc {:03X}", address),
+            Breakpoint::Watch { start, end, read, write } => {
+                let access = match (read, write) {
+                    (true, true) => "rw",
+                    (true, false) => "r",
+                    _ => "w",
+                };
+                write!(f, "watch {} {:03X}-{:03X}", access, start, end)
+            }
+            Breakpoint::Register { register, comparison, value } => write!(f, "{} {} {:X}", register, comparison.symbol(), value),
+        }
+    }
+}
+
+// Why a continue gave control back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Breakpoint(Breakpoint),
+    WaitingForKey,
+    Exited,
+    StepLimit,
+}
+
+// Drives a Machine one instruction at a time, stopping on breakpoints
+// Timing stays the same as a normal run since every step goes through Machine::step
+pub struct Debugger {
+    pub machine: Machine,
+    breakpoints: Vec<(usize, Breakpoint)>,
+    next_id: usize,
+    stopped: bool, // Control came back on a stop rather than the step limit, the next run starts by leaving it
+}
+
+impl Debugger {
+    pub fn new(mut machine: Machine) -> Debugger {
+        machine.cpu.set_track_accesses(true);
+        Debugger { machine, breakpoints: Vec::new(), next_id: 1, stopped: true }
+    }
+
+    // Returns the id used to remove it again
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.breakpoints.push((id, breakpoint));
+        id
+    }
+
+    pub fn remove_breakpoint(&mut self, id: usize) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints.retain(|(breakpoint_id, _)| *breakpoint_id != id);
+        count != self.breakpoints.len()
+    }
+
+    pub fn breakpoints(&self) -> &[(usize, Breakpoint)] {
+        &self.breakpoints
+    }
+
+    pub fn step(&mut self) -> Result<StepOutcome, Chip8Error> {
+        self.stopped = true;
+        self.machine.step()
+    }
+
+    // Runs until a registered breakpoint, a key wait, EXIT or max_steps instructions, whichever comes first
+    pub fn run(&mut self, max_steps: u64) -> Result<Stop, Chip8Error> {
+        self.run_until(None, max_steps)
+    }
+
+    // Same as run() with one extra breakpoint that only lives for this call, "run to here"
+    pub fn continue_until(&mut self, until: Breakpoint, max_steps: u64) -> Result<Stop, Chip8Error> {
+        self.run_until(Some(until), max_steps)
+    }
+
+    fn run_until(&mut self, until: Option<Breakpoint>, max_steps: u64) -> Result<Stop, Chip8Error> {
+        let result = self.run_steps(until, max_steps);
+        self.stopped = result != Ok(Stop::StepLimit);
+        result
+    }
+
+    fn run_steps(&mut self, until: Option<Breakpoint>, max_steps: u64) -> Result<Stop, Chip8Error> {
+        let mut active: Vec<Breakpoint> = self.breakpoints.iter().map(|(_, breakpoint)| *breakpoint).collect();
+        active.extend(until);
+
+        for step in 0..max_steps {
+            // The first instruction after a stop always runs, otherwise continuing from a PC breakpoint would
+            // never move. A run carried on past its step limit still checks it
+            if step > 0 || !self.stopped {
+                if let Some(hit) = active.iter().find(|&&breakpoint| breakpoint == Breakpoint::Pc(self.machine.cpu.pc)) {
+                    return Ok(Stop::Breakpoint(*hit));
+                }
+            }
+
+            let before: Vec<bool> = active.iter().map(|breakpoint| self.condition_holds(breakpoint)).collect();
+            match self.machine.step()? {
+                StepOutcome::WaitingForKey => return Ok(Stop::WaitingForKey),
+                StepOutcome::Exited => return Ok(Stop::Exited),
+                _ => {}
+            }
+
+            for (breakpoint, held_before) in active.iter().zip(before) {
+                let hit = match breakpoint {
+                    Breakpoint::Pc(_) => false,
+                    Breakpoint::Watch { start, end, read, write } => {
+                        self.machine.cpu.last_accesses().iter().any(|access| {
+                            let kind_matches = match access.kind {
+                                AccessKind::Read => *read,
+                                AccessKind::Write => *write,
+                            };
+                            kind_matches && access.address >= *start && access.address <= *end
+                        })
+                    }
+                    Breakpoint::Register { .. } => !held_before && self.condition_holds(breakpoint),
+                };
+                if hit {
+                    return Ok(Stop::Breakpoint(*breakpoint));
+                }
+            }
+        }
+
+        Ok(Stop::StepLimit)
+    }
+
+    fn condition_holds(&self, breakpoint: &Breakpoint) -> bool {
+        match breakpoint {
+            Breakpoint::Register { register, comparison, value } => {
+                let cpu = &self.machine.cpu;
+                let current = match register {
+                    Register::V(x) => cpu.registers()[(*x & 0xF) as usize] as u16,
+                    Register::I => cpu.i(),
+                    Register::Pc => cpu.pc,
+                };
+                comparison.holds(current, *value)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+#[path ="./debugger_test.rs"]
+mod debugger_test;
@@ -0,0 +1,114 @@
+use super::*;
+use crate::core::ops::MyChips8;
+use crate::core::quirks::Quirks;
+
+// 200 LD V0, 00 / 202 CALL 20C / 204 ADD V0, 01 / 206 LD I, 300 / 208 LD [I], V0 / 20A JP 202
+// 20C LD V1, 05 / 20E RET
+const LOOP: [u8; 16] = [
+    0x60, 0x00, 0x22, 0x0C, 0x70, 0x01, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x02, 0x61, 0x05, 0x00, 0xEE,
+];
+
+fn debugger_with_rom(rom: &[u8]) -> Debugger {
+    let mut cpu = MyChips8::new_with_quirks(Quirks::default());
+    cpu.load_rom(rom).unwrap();
+    Debugger::new(Machine::new(cpu))
+}
+
+#[test]
+fn steps_one_instruction_at_a_time() {
+    let mut debugger = debugger_with_rom(&LOOP);
+
+    debugger.step().unwrap();
+    assert_eq!(0x202, debugger.machine.cpu.pc);
+    debugger.step().unwrap();
+    assert_eq!(0x20C, debugger.machine.cpu.pc);
+    assert_eq!(&[0x204], debugger.machine.cpu.call_stack());
+    debugger.step().unwrap();
+    debugger.step().unwrap();
+    assert!(debugger.machine.cpu.call_stack().is_empty());
+}
+
+#[test]
+fn stops_on_pc_breakpoints() {
+    let mut debugger = debugger_with_rom(&LOOP);
+    let id = debugger.add_breakpoint(Breakpoint::Pc(0x20A));
+
+    assert_eq!(Ok(Stop::Breakpoint(Breakpoint::Pc(0x20A))), debugger.run(100));
+    assert_eq!(0x20A, debugger.machine.cpu.pc);
+
+    // Continuing from the breakpoint goes round the loop once more
+    assert_eq!(Ok(Stop::Breakpoint(Breakpoint::Pc(0x20A))), debugger.run(100));
+    assert_eq!(0x2, debugger.machine.cpu.registers()[0x0]);
+
+    assert!(debugger.remove_breakpoint(id));
+    assert!(!debugger.remove_breakpoint(id));
+    assert_eq!(Ok(Stop::StepLimit), debugger.run(100));
+}
+
+#[test]
+fn runs_carried_on_past_the_step_limit_still_stop_on_the_first_instruction() {
+    let mut debugger = debugger_with_rom(&LOOP);
+    debugger.add_breakpoint(Breakpoint::Pc(0x206));
+
+    // 200, 202, 20C, 20E, 204 and the limit is up right on the breakpoint
+    assert_eq!(Ok(Stop::StepLimit), debugger.run(5));
+    assert_eq!(0x206, debugger.machine.cpu.pc);
+    assert_eq!(Ok(Stop::Breakpoint(Breakpoint::Pc(0x206))), debugger.run(100));
+    assert_eq!(0x206, debugger.machine.cpu.pc);
+
+    // Stopped there now, so the next run leaves it
+    assert_eq!(Ok(Stop::Breakpoint(Breakpoint::Pc(0x206))), debugger.run(100));
+    assert_eq!(0x2, debugger.machine.cpu.registers()[0x0]);
+}
+
+#[test]
+fn continue_until_is_one_shot() {
+    let mut debugger = debugger_with_rom(&LOOP);
+
+    assert_eq!(Ok(Stop::Breakpoint(Breakpoint::Pc(0x20E))), debugger.continue_until(Breakpoint::Pc(0x20E), 100));
+    assert!(debugger.breakpoints().is_empty());
+}
+
+#[test]
+fn stops_on_watchpoints() {
+    let mut debugger = debugger_with_rom(&LOOP);
+    let write = Breakpoint::Watch { start: 0x300, end: 0x300, read: false, write: true };
+    debugger.add_breakpoint(write);
+    debugger.add_breakpoint(Breakpoint::Watch { start: 0x301, end: 0x3FF, read: true, write: true });
+
+    // Stops straight after the store
+    assert_eq!(Ok(Stop::Breakpoint(write)), debugger.run(100));
+    assert_eq!(0x20A, debugger.machine.cpu.pc);
+    assert_eq!(0x1, debugger.machine.cpu.memory()[0x300]);
+}
+
+#[test]
+fn stops_when_register_conditions_become_true() {
+    let mut debugger = debugger_with_rom(&LOOP);
+    let condition = Breakpoint::Register { register: Register::V(0x0), comparison: Comparison::Ge, value: 3 };
+    debugger.add_breakpoint(condition);
+
+    assert_eq!(Ok(Stop::Breakpoint(condition)), debugger.run(100));
+    assert_eq!(0x3, debugger.machine.cpu.registers()[0x0]);
+    assert_eq!(0x206, debugger.machine.cpu.pc);
+
+    // Still true, so it does not fire again
+    assert_eq!(Ok(Stop::StepLimit), debugger.run(100));
+}
+
+#[test]
+fn stops_on_key_waits_and_exit() {
+    // LD V0, K / EXIT
+    let mut debugger = debugger_with_rom(&[0xF0, 0x0A, 0x00, 0xFD]);
+    assert_eq!(Ok(Stop::WaitingForKey), debugger.run(100));
+
+    debugger.machine.cpu.press_key(0x1);
+    debugger.machine.cpu.release_key(0x1);
+    assert_eq!(Ok(Stop::Exited), debugger.run(100));
+}
+
+#[test]
+fn faults_are_returned() {
+    let mut debugger = debugger_with_rom(&[0xFF, 0xFF]);
+    assert_eq!(Err(Chip8Error::UnknownOpcode { pc: 0x200, opcode: 0xFFFF }), debugger.run(100));
+}
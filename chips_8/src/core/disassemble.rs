@@ -1,237 +1,550 @@
-use std::{vec::Vec, fmt::Display};
+use std::{vec::Vec, fmt::Display, collections::{BTreeMap, HashSet}};
 
-use super::utils::{get_nnn, get_x, get_kk, get_y, get_nibble};
+use super::instruction::Instruction;
 
-type DisassembledOpTuple = (String, String, String, String, String, Option<String>);
+// Typed operand of a disassembled row, Display gives the same text the listing shows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    Register(u8),   // V0 - VF
+    I,
+    IndirectI,      // [I]
+    DelayTimer,     // DT
+    SoundTimer,     // ST
+    Key,            // K
+    Font,           // F
+    BigFont,        // HF
+    Bcd,            // B
+    Flags,          // R, the RPL user flags
+    Address(u16),   // nnn
+    Byte(u8),       // kk and db values
+    Nibble(u8),     // n
+    Long(u16),      // Address following F000
+    Word(u16),      // dw values
+    Bits(u8),       // Sprite row
+    Label { name: String, address: u16, long: bool },
+}
 
-pub struct DisassembledChip8 {
-    op_tuple_vec: Vec<DisassembledOpTuple>
+impl Operand {
+    // Name used for the type in JSON output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Operand::Register(_) => "register",
+            Operand::I => "i",
+            Operand::IndirectI => "indirect_i",
+            Operand::DelayTimer => "dt",
+            Operand::SoundTimer => "st",
+            Operand::Key => "k",
+            Operand::Font => "f",
+            Operand::BigFont => "hf",
+            Operand::Bcd => "b",
+            Operand::Flags => "r",
+            Operand::Address(_) => "address",
+            Operand::Byte(_) => "byte",
+            Operand::Nibble(_) => "nibble",
+            Operand::Long(_) => "long",
+            Operand::Word(_) => "word",
+            Operand::Bits(_) => "bits",
+            Operand::Label { .. } => "label",
+        }
+    }
+
+    // Numeric part, if there is one
+    pub fn value(&self) -> Option<u16> {
+        match *self {
+            Operand::Register(x) | Operand::Byte(x) | Operand::Nibble(x) | Operand::Bits(x) => Some(x as u16),
+            Operand::Address(value) | Operand::Long(value) | Operand::Word(value) => Some(value),
+            Operand::Label { address, .. } => Some(address),
+            _ => None,
+        }
+    }
+
+    fn to_json(&self) -> String {
+        match (self, self.value()) {
+            (Operand::Label { name, address, long }, _) => {
+                format!("{{\"type\":\"label\",\"name\":{},\"value\":{},\"long\":{}}}", json_string(name), address, long)
+            }
+            (_, Some(value)) => format!("{{\"type\":\"{}\",\"value\":{}}}", self.kind(), value),
+            (_, None) => format!("{{\"type\":\"{}\"}}", self.kind()),
+        }
+    }
 }
 
-impl DisassembledChip8 {
-    pub fn new(bytes: &[u8]) -> DisassembledChip8 {
-        let op_tuple_vec = bytes.iter().enumerate().step_by(2).map(|(idx, b_val)| {
-            let assembled_opcode = (*b_val as u16) << 8
-                | bytes[idx + 1] as u16;
-            get_disassembled_op_tuple(&idx, &assembled_opcode)
-        }).collect();
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Register(x) => write!(f, "V{:X}", x),
+            Operand::I => write!(f, "I"),
+            Operand::IndirectI => write!(f, "[I]"),
+            Operand::DelayTimer => write!(f, "DT"),
+            Operand::SoundTimer => write!(f, "ST"),
+            Operand::Key => write!(f, "K"),
+            Operand::Font => write!(f, "F"),
+            Operand::BigFont => write!(f, "HF"),
+            Operand::Bcd => write!(f, "B"),
+            Operand::Flags => write!(f, "R"),
+            Operand::Address(nnn) => write!(f, "{:03X}", nnn),
+            Operand::Byte(kk) => write!(f, "{:02X}", kk),
+            Operand::Nibble(n) => write!(f, "{:X}", n),
+            Operand::Long(address) => write!(f, "LONG {:04X}", address),
+            Operand::Word(word) => write!(f, "{:04X}", word),
+            Operand::Bits(bits) => write!(f, "%{:08b}", bits),
+            Operand::Label { name, long: true, .. } => write!(f, "LONG {}", name),
+            Operand::Label { name, .. } => write!(f, "{}", name),
+        }
+    }
+}
 
-        DisassembledChip8 { op_tuple_vec }
+// One row of a listing, an instruction or a run of data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisassembledOp {
+    pub address: usize,
+    pub bytes: Vec<u8>,                 // Everything the row covers, F000 nnnn is four bytes
+    pub instruction: Option<Instruction>, // None for db / dw rows
+    pub mnemonic: &'static str,
+    pub operands: Vec<Operand>,
+    pub comment: Option<String>,
+    pub label: Option<String>,          // Only filled in by recursive disassembly
+}
+
+impl DisassembledOp {
+    // First word of an instruction row
+    pub fn opcode(&self) -> Option<u16> {
+        self.instruction.map(|_| (self.bytes[0] as u16) << 8 | self.bytes[1] as u16)
+    }
+
+    pub fn operand_text(&self) -> String {
+        self.operands.iter().map(|operand| operand.to_string()).collect::<Vec<String>>().join(", ")
+    }
+
+    fn hex(&self) -> String {
+        self.bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
     }
 }
 
-fn create_disassembled_op_tuple(idx: &usize, op: &u16, op_type: &str, description: &str) -> DisassembledOpTuple {
-    let hex_string = format!("{:0>4X}", op);
-    let op_string = hex_string.split_at(2);
-    (format!("{:X}", idx + 0x200), String::from(op_string.0), String::from(op_string.1), String::from(op_type), String::from(description), None)
+impl Display for DisassembledOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Instructions and words show the first word split in two, db rows show their bytes together
+        let (high, low) = if self.mnemonic == "db" {
+            (self.hex(), String::new())
+        } else {
+            (format!("{:02X}", self.bytes[0]), format!("{:02X}", self.bytes[1]))
+        };
+        write!(f, "{:X} {:>4} {:>2} {:>5} {} {}", self.address, high, low, self.mnemonic, self.operand_text(), self.comment.as_deref().unwrap_or(""))
+    }
 }
 
-fn create_disassembled_op_tuple_desc_string(idx: &usize, op: &u16, op_type: &str, description: String, comment: Option<&str>) -> DisassembledOpTuple {
-    let comment_result = match comment {
-        Some(comment_exists) => Some(String::from(comment_exists)),
-        None => None
-    };
+pub struct DisassembledChip8 {
+    ops: Vec<DisassembledOp>,
+}
 
-    let hex_string = format!("{:X}", op);
-    let op_string = hex_string.split_at(2);
+// What the recursive walk decided a byte is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteUse {
+    Unknown,
+    Code(Instruction, usize), // Instruction starting here and its size
+    Operand,                  // Covered by the instruction before it
+    Sprite,
+}
 
-    (format!("{:X}", idx + 0x200), String::from(op_string.0), String::from(op_string.1), String::from(op_type), description, comment_result)
+// Kinds of label in order of precedence, an address that is both called and jumped to is named as a subroutine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LabelKind {
+    Data,
+    Sprite,
+    Label,
+    Sub,
 }
 
-fn get_disassembled_op_tuple(idx: &usize, op: &u16) -> DisassembledOpTuple {
-    match op & 0xF000 {
-        0x0000 => {
-            match op & 0x00FF {
-                // 0x00E0 - CLS - Clears the screen
-                0x00E0 => {
-                    // Compiles to memset so fastest possible solution
-                    return create_disassembled_op_tuple(idx, op, "CLS", "Clears the screen")
+impl DisassembledChip8 {
+    pub fn new(bytes: &[u8]) -> DisassembledChip8 {
+        DisassembledChip8::new_at(bytes, 0x200)
+    }
+
+    // Listing of bytes that were taken from memory at origin instead of the start of the program
+    pub fn new_at(bytes: &[u8], origin: usize) -> DisassembledChip8 {
+        let mut ops = Vec::with_capacity(bytes.len() / 2);
+        let mut idx = 0;
+        while idx + 1 < bytes.len() {
+            let assembled_opcode = (bytes[idx] as u16) << 8
+                | bytes[idx + 1] as u16;
+
+            let op = match Instruction::decode(assembled_opcode) {
+                // XO-CHIP F000 nnnn carries its address in the following word
+                Ok(Instruction::LdILong) if idx + 3 < bytes.len() => {
+                    let address = (bytes[idx + 2] as u16) << 8 | bytes[idx + 3] as u16;
+                    code_op(origin + idx, &bytes[idx..idx + 4], Instruction::LdILong, vec![Operand::I, Operand::Long(address)])
                 }
-                // 0x00EE - RET - Returns from subroutine
-                0x00EE => {
-                    return create_disassembled_op_tuple(idx, op, "RET", "Returns from subroutine")
+                Ok(Instruction::LdILong) => data_op(origin + idx, &bytes[idx..idx + 2]),
+                Ok(instruction) => code_op(origin + idx, &bytes[idx..idx + 2], instruction, operands_of(&instruction)),
+                Err(_) => DisassembledOp {
+                    address: origin + idx,
+                    bytes: bytes[idx..idx + 2].to_vec(),
+                    instruction: None,
+                    mnemonic: "dw",
+                    operands: vec![Operand::Word(assembled_opcode)],
+                    comment: Some(String::from("Unsupported op found")),
+                    label: None,
+                },
+            };
+            idx += op.bytes.len();
+            ops.push(op);
+        }
+
+        // A trailing odd byte can not be an instruction
+        if idx < bytes.len() {
+            ops.push(data_op(origin + idx, &bytes[idx..]));
+        }
+
+        DisassembledChip8 { ops }
+    }
+
+    // Disassembles only what execution can reach from the start of the program, following jumps, calls and skips
+    // Targets get labels, bytes drawn after an LD I become sprite rows and everything else is left as db
+    pub fn recursive(bytes: &[u8]) -> DisassembledChip8 {
+        let origin = 0x200;
+        let mut uses = vec![ByteUse::Unknown; bytes.len()];
+        let mut targets: BTreeMap<usize, LabelKind> = BTreeMap::new();
+        let mut visited = HashSet::new();
+        // Each entry carries where I points on the way there, so DRW knows what it draws
+        let mut pending: Vec<(usize, Option<usize>)> = vec![(origin, None)];
+
+        while let Some((start, i)) = pending.pop() {
+            let mut address = start;
+            let mut i = i;
+            while visited.insert(address) {
+                let idx = match address.checked_sub(origin) {
+                    Some(idx) if idx + 1 < bytes.len() && is_free(uses[idx]) && is_free(uses[idx + 1]) => idx,
+                    _ => break,
+                };
+                let instruction = match Instruction::decode((bytes[idx] as u16) << 8 | bytes[idx + 1] as u16) {
+                    Ok(instruction) => instruction,
+                    Err(_) => break,
+                };
+                let size = if instruction == Instruction::LdILong { 4 } else { 2 };
+                if idx + size > bytes.len() || !uses[idx..idx + size].iter().all(|byte| is_free(*byte)) {
+                    break;
                 }
-                // 0x0nnn - SYS addr - no-op this is ignored on modern compilers
-                _ => {
-                    return create_disassembled_op_tuple(idx, op, "SYS", "Feature used by old comnputers")
+                uses[idx] = ByteUse::Code(instruction, size);
+                uses[idx + 1..idx + size].iter_mut().for_each(|byte| *byte = ByteUse::Operand);
+
+                let mut label = |address: usize, kind: LabelKind| {
+                    let entry = targets.entry(address).or_insert(kind);
+                    *entry = (*entry).max(kind);
+                };
+                let next = address + size;
+                match instruction {
+                    // JP V0 is usually a jump table starting at nnn, the entries past the first can not be known
+                    Instruction::Jp { nnn } | Instruction::JpV0 { nnn } => {
+                        label(nnn as usize, LabelKind::Label);
+                        pending.push((nnn as usize, i));
+                        break;
+                    }
+                    Instruction::Call { nnn } => {
+                        label(nnn as usize, LabelKind::Sub);
+                        pending.push((nnn as usize, None));
+                    }
+                    Instruction::Ret | Instruction::Exit => break,
+                    Instruction::SeByte { .. }
+                    | Instruction::SneByte { .. }
+                    | Instruction::SeReg { .. }
+                    | Instruction::SneReg { .. }
+                    | Instruction::Skp { .. }
+                    | Instruction::Sknp { .. } => {
+                        // Skipping over F000 nnnn skips all four bytes
+                        let skipped = match bytes.get(next - origin..next - origin + 2) {
+                            Some([0xF0, 0x00]) => 4,
+                            _ => 2,
+                        };
+                        pending.push((next + skipped, i));
+                    }
+                    Instruction::LdI { nnn } => {
+                        label(nnn as usize, LabelKind::Data);
+                        i = Some(nnn as usize);
+                    }
+                    Instruction::LdILong => {
+                        let long = (bytes[idx + 2] as usize) << 8 | bytes[idx + 3] as usize;
+                        label(long, LabelKind::Data);
+                        i = Some(long);
+                    }
+                    Instruction::Drw { n, .. } => {
+                        if let Some(sprite) = i {
+                            // DRW with n = 0 is a 16x16 SUPER-CHIP sprite
+                            let len = if n == 0 { 32 } else { n as usize };
+                            label(sprite, LabelKind::Sprite);
+                            let first = sprite.saturating_sub(origin).min(bytes.len());
+                            let last = (sprite + len).saturating_sub(origin).min(bytes.len());
+                            if sprite >= origin {
+                                uses[first..last].iter_mut().filter(|byte| **byte == ByteUse::Unknown).for_each(|byte| *byte = ByteUse::Sprite);
+                            }
+                        }
+                    }
+                    // Anything else that moves I loses track of it
+                    Instruction::AddIVx { .. } | Instruction::LdFVx { .. } | Instruction::LdHfVx { .. } | Instruction::LdIVx { .. } | Instruction::LdVxI { .. } => i = None,
+                    _ => {}
                 }
+                address = next;
             }
         }
 
-        // 0x1nnn - JP addr - JMP to addr nnn
-        0x1000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "JMP", format!("JMP {:X}", get_nnn(&op)), None)
+        // Lay out the rows, runs of unknown bytes are cut at labels so every label lands on a row
+        let mut rows: Vec<(usize, usize)> = Vec::new(); // (index into bytes, length)
+        let mut idx = 0;
+        while idx < bytes.len() {
+            let len = match uses[idx] {
+                ByteUse::Code(_, size) => size,
+                ByteUse::Sprite | ByteUse::Operand => 1,
+                ByteUse::Unknown => {
+                    let mut len = 1;
+                    while len < 8
+                        && idx + len < bytes.len()
+                        && uses[idx + len] == ByteUse::Unknown
+                        && !targets.contains_key(&(origin + idx + len))
+                    {
+                        len += 1;
+                    }
+                    len
+                }
+            };
+            rows.push((idx, len));
+            idx += len;
         }
 
-        // 0x2nnn - CALL addr - Calls subroutine at address nnn
-        0x2000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "CALL", format!("S, #${:X}", get_nnn(op)), None)
-        }
+        // Only labels that start a row can be printed, anything else stays a plain address
+        let row_starts: HashSet<usize> = rows.iter().map(|(idx, _)| origin + idx).collect();
+        let names: BTreeMap<usize, String> = targets
+            .iter()
+            .filter(|(address, _)| row_starts.contains(address))
+            .map(|(address, kind)| {
+                let prefix = match kind {
+                    LabelKind::Sub => "sub",
+                    LabelKind::Label => "label",
+                    LabelKind::Sprite => "sprite",
+                    LabelKind::Data => "data",
+                };
+                (*address, format!("{}_{:03X}", prefix, address))
+            })
+            .collect();
 
-        // 0x3xkk - SE Vx, byte - Skip next if Vx == kk
-        0x3000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "SE", format!("V{:X}, {:X}", get_x(op), get_kk(op)), Some("Vx == kk"));
-        }
+        let name = |address: u16, long: bool| match names.get(&(address as usize)) {
+            Some(name) => Operand::Label { name: name.clone(), address, long },
+            None if long => Operand::Long(address),
+            None => Operand::Address(address),
+        };
 
-        // 0x4xkk - SNE Vx, byte - Skip next if Vx != kk
-        0x4000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "SNE", format!("V{:X}, {:X}", get_x(op), get_kk(op)), Some("Vx != kk"));
+        let mut ops = Vec::with_capacity(rows.len());
+        for (idx, len) in rows {
+            let address = origin + idx;
+            let mut op = match uses[idx] {
+                ByteUse::Code(instruction, size) => {
+                    let operands = match instruction {
+                        Instruction::Jp { nnn } | Instruction::Call { nnn } => vec![name(nnn, false)],
+                        Instruction::JpV0 { nnn } => vec![Operand::Register(0), name(nnn, false)],
+                        Instruction::LdI { nnn } => vec![Operand::I, name(nnn, false)],
+                        Instruction::LdILong => vec![Operand::I, name((bytes[idx + 2] as u16) << 8 | bytes[idx + 3] as u16, true)],
+                        _ => operands_of(&instruction),
+                    };
+                    code_op(address, &bytes[idx..idx + size], instruction, operands)
+                }
+                ByteUse::Sprite => {
+                    let pattern: String = (0..8).rev().map(|bit| if bytes[idx] >> bit & 1 == 1 { '#' } else { '.' }).collect();
+                    let mut op = data_op(address, &bytes[idx..idx + 1]);
+                    op.operands = vec![Operand::Bits(bytes[idx])];
+                    op.comment = Some(pattern);
+                    op
+                }
+                _ => data_op(address, &bytes[idx..idx + len]),
+            };
+            op.label = names.get(&address).cloned();
+            ops.push(op);
         }
 
-        // 0x5xy0 - SE Vx, Vy - Skip next if Vx == Vy
-        0x5000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "SE", format!("V{:X}, {:X}", get_x(op), get_y(op)), Some("Vx == Vy"));
-        }
+        DisassembledChip8 { ops }
+    }
 
-        // 0x6xkk - LD Vx, byte - Load kk into Vx
-        0x6000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "LD", format!("V{:X}, {:X}", get_x(op), get_kk(op)), None)
-        }
+    pub fn ops(&self) -> &[DisassembledOp] {
+        &self.ops
+    }
 
-        // 0x7xkk - ADD Vx, byte - Add value of byte
-        0x7000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "ADD", format!("V{:X}, {:X}", get_x(op), get_kk(op)), None)
-        }
+    pub fn iter(&self) -> std::slice::Iter<'_, DisassembledOp> {
+        self.ops.iter()
+    }
 
-        0x8000 => match op & 0x000F {
-            // LD Vx, Vy
-            0x0000 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "LD", format!("V{:X}, V{:X}", get_x(op), get_y(op)), None)
-            }
-            // OR Vx, Vy
-            0x0001 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "OR", format!("V{:X}, V{:X}", get_x(op), get_y(op)), None)
-            }
-            // AND Vx, Vy
-            0x0002 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "AND", format!("V{:X}, V{:X}", get_x(op), get_y(op)), None)
-            }
-            // XOR Vx, Vy
-            0x0003 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "XOR", format!("V{:X}, V{:X}", get_x(op), get_y(op)), None)
-            }
-            // ADD Vx, Vy - Add Vx to Vy
-            0x0004 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "ADD", format!("V{:X}, V{:X}", get_x(op), get_y(op)), None)
-            }
-            // SUB Vx, Vy - Subtract Vx from Vy
-            0x0005 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "SUB", format!("V{:X}, V{:X}", get_x(op), get_y(op)), None)
-            }
-            // SHR Vx - RHS 1
-            0x0006 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "SHR", format!("V{:X}", get_x(op)), None)
-            }
-            // SUBN Vx, Vy - Subtract Vy from Vx
-            0x0007 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "SUBN", format!("V{:X}, V{:X}", get_x(op), get_y(op)), None)
+    // Listing the assembler takes back, anything that does not decode is kept as data
+    pub fn to_source(&self) -> String {
+        self.ops.iter().map(|op| {
+            let label = op.label.as_ref().map(|label| format!("{}:\n", label)).unwrap_or_default();
+            let line = format!("{} {}", op.mnemonic, op.operand_text());
+            match &op.comment {
+                Some(comment) => format!("{}{} ; {}\n", label, line.trim_end(), comment),
+                None => format!("{}{}\n", label, line.trim_end()),
             }
-            // SHL Vx - LHS 1
-            0x000E => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "SHL", format!("V{:X}", get_x(op)), None)
-            }
-            _ => create_disassembled_op_tuple(idx, op, "X", "Unsupported op found"),
-        },
+        }).collect()
+    }
 
-        // 0x9xy0 - SNE Vx, Vy - Skip next instruction if Vx != Vy
-        0x9000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "SNE", format!("V{:X}, V{:X}", get_x(op), get_y(op)), None)
-        }
+    // One object per row, numbers are plain decimal so other tools need no hex parsing
+    pub fn to_json(&self) -> String {
+        let rows: Vec<String> = self.ops.iter().map(|op| {
+            let operands: Vec<String> = op.operands.iter().map(Operand::to_json).collect();
+            format!(
+                "  {{\"address\":{},\"bytes\":\"{}\",\"label\":{},\"mnemonic\":\"{}\",\"operands\":[{}],\"text\":{},\"comment\":{}}}",
+                op.address,
+                op.hex(),
+                op.label.as_deref().map_or(String::from("null"), json_string),
+                op.mnemonic,
+                operands.join(","),
+                json_string(&op.operand_text()),
+                op.comment.as_deref().map_or(String::from("null"), json_string)
+            )
+        }).collect();
+        format!("[\n{}\n]\n", rows.join(",\n"))
+    }
 
-        // 0xAnnn - LD I, addr - Sets I to the address nnn
-        0xA000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "LD", format!("I, {:X}", get_nnn(op)), None)
+    // address,bytes,label,mnemonic,operands,comment with the address in hex like the listing
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("address,bytes,label,mnemonic,operands,comment\n");
+        for op in &self.ops {
+            let fields = [
+                format!("{:03X}", op.address),
+                op.hex(),
+                op.label.clone().unwrap_or_default(),
+                op.mnemonic.to_string(),
+                op.operand_text(),
+                op.comment.clone().unwrap_or_default(),
+            ];
+            let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
         }
+        csv
+    }
+}
 
-        // Bnnn - JP V0, addr - Jump to location addr
-        0xB000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "JMP", format!("V0, {:X}", get_nnn(op)), None)
-        }
+impl<'a> IntoIterator for &'a DisassembledChip8 {
+    type Item = &'a DisassembledOp;
+    type IntoIter = std::slice::Iter<'a, DisassembledOp>;
 
-        // Cxkk - RND Vx, byte - Random number from 0 to 255, then &'d w/ byte which is stored into Vx
-        0xC000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "RND", format!("V{:X}, {:X}", get_x(op), get_nnn(op)), None)
-        }
+    fn into_iter(self) -> Self::IntoIter {
+        self.ops.iter()
+    }
+}
 
-        // 0xDxyn - DRW Vx, Vy, nibble - Draw n-byte sprite starting at mem loc I @ (vx, Vy), set VF = collision
-        0xD000 => {
-            return create_disassembled_op_tuple_desc_string(idx, op, "DRW", format!("V{:X}, V{:X}, {:X}", get_x(op), get_y(op), get_nibble(op)), None)
-        }
+// Code found later wins over a guess that the bytes were a sprite
+fn is_free(byte: ByteUse) -> bool {
+    matches!(byte, ByteUse::Unknown | ByteUse::Sprite)
+}
 
-        0xE000 => match op & 0x00FF {
-            // 0xEx9E - SKP Vx - Skip next instruction if key pressed
-            0x009E => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "SKP", format!("V{:X}", get_x(op)), None)
-            }
+fn code_op(address: usize, bytes: &[u8], instruction: Instruction, operands: Vec<Operand>) -> DisassembledOp {
+    DisassembledOp {
+        address,
+        bytes: bytes.to_vec(),
+        instruction: Some(instruction),
+        mnemonic: instruction.mnemonic(),
+        operands,
+        comment: instruction.comment().map(String::from),
+        label: None,
+    }
+}
 
-            // 0xExA1 - SKNP Vx - Skip next instruction if key not pressed
-            0x00A1 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "SKNP", format!("V{:X}", get_x(op)), None)
-            }
-            _ => create_disassembled_op_tuple(idx, op, "X", "Unsupported op found"),
-        },
+fn data_op(address: usize, data: &[u8]) -> DisassembledOp {
+    DisassembledOp {
+        address,
+        bytes: data.to_vec(),
+        instruction: None,
+        mnemonic: "db",
+        operands: data.iter().map(|byte| Operand::Byte(*byte)).collect(),
+        comment: None,
+        label: None,
+    }
+}
 
-        0xF000 => match op & 0x00FF {
-            // 0xFx07 - LD Vx, DT - Loading Delay Timer into Vx
-            0x0007 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "LD", format!("V{:X}, DT", get_x(op)), Some("DT - Delay Timer"))
-            }
-            // 0xFx0A - LD Vx, K - Stop execution till key press
-            0x000A => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "LD", format!("V{:X}, K", get_x(op)), Some("Stop execution till keypress"))
-            }
-            // 0xFx15 - LD DT, Vx - Load Vx into DT
-            0x0015 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "LD", format!("DT, V{:X}", get_x(op)), None)
-            }
-            // 0xFx18 - LD ST, Vx - Set sound timer to Vx
-            0x0018 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "LD", format!("ST, V{:X}", get_x(op)), Some("ST - Sound Timer"))
-            }
-            // 0xFx1E - ADD I, Vx - Add I and Vx then store in I
-            0x001E => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "ADD", format!("I, V{:X}", get_x(op)), None)
-            }
-            // 0xFx29 - LD F, Vx - Set I = location of sprite for digit Vx
-            0x0029 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "LD", format!("F, V{:X}", get_x(op)), Some("Load font"))
-            }
-            // 0xFx33 - LD F, Vx - set_BCD
-            0x0033 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "LD", format!("F, V{:X}", get_x(op)), Some("Set BCD"))
-            }
-            // 0xFx55 - LD [I], Vx -reg_dump
-            0x0055 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "LD", format!("[I], V{:X}", get_x(op)), Some("Reg Dump"))
-            }
-            // 0xFx65 - LD Vx, [I] - reg_load
-            0x0065 => {
-                return create_disassembled_op_tuple_desc_string(idx, op, "LD", format!("V{:X}, I", get_x(op)), None)
-            }
-            _ => create_disassembled_op_tuple(idx, op, "X", "Unsupported op found"),
-        },
+// Typed version of Instruction::operands()
+fn operands_of(instruction: &Instruction) -> Vec<Operand> {
+    let v = Operand::Register;
+    match *instruction {
+        Instruction::Cls
+        | Instruction::Ret
+        | Instruction::Scr
+        | Instruction::Scl
+        | Instruction::Exit
+        | Instruction::Low
+        | Instruction::High
+        | Instruction::Audio
+        | Instruction::LdILong => Vec::new(),
+        Instruction::Plane { n } | Instruction::Scd { n } | Instruction::Scu { n } => vec![Operand::Nibble(n)],
+        Instruction::Sys { nnn } | Instruction::Jp { nnn } | Instruction::Call { nnn } => vec![Operand::Address(nnn)],
+        Instruction::SeByte { x, kk }
+        | Instruction::SneByte { x, kk }
+        | Instruction::LdByte { x, kk }
+        | Instruction::AddByte { x, kk }
+        | Instruction::Rnd { x, kk } => vec![v(x), Operand::Byte(kk)],
+        Instruction::SeReg { x, y }
+        | Instruction::LdReg { x, y }
+        | Instruction::Or { x, y }
+        | Instruction::And { x, y }
+        | Instruction::Xor { x, y }
+        | Instruction::AddReg { x, y }
+        | Instruction::Sub { x, y }
+        | Instruction::Shr { x, y }
+        | Instruction::Subn { x, y }
+        | Instruction::Shl { x, y }
+        | Instruction::SneReg { x, y }
+        | Instruction::SaveRange { x, y }
+        | Instruction::LoadRange { x, y } => vec![v(x), v(y)],
+        Instruction::LdI { nnn } => vec![Operand::I, Operand::Address(nnn)],
+        Instruction::JpV0 { nnn } => vec![v(0), Operand::Address(nnn)],
+        Instruction::Drw { x, y, n } => vec![v(x), v(y), Operand::Nibble(n)],
+        Instruction::Skp { x } | Instruction::Sknp { x } | Instruction::Pitch { x } => vec![v(x)],
+        Instruction::LdVxDt { x } => vec![v(x), Operand::DelayTimer],
+        Instruction::LdVxK { x } => vec![v(x), Operand::Key],
+        Instruction::LdDtVx { x } => vec![Operand::DelayTimer, v(x)],
+        Instruction::LdStVx { x } => vec![Operand::SoundTimer, v(x)],
+        Instruction::AddIVx { x } => vec![Operand::I, v(x)],
+        Instruction::LdFVx { x } => vec![Operand::Font, v(x)],
+        Instruction::LdBVx { x } => vec![Operand::Bcd, v(x)],
+        Instruction::LdIVx { x } => vec![Operand::IndirectI, v(x)],
+        Instruction::LdVxI { x } => vec![v(x), Operand::IndirectI],
+        Instruction::LdHfVx { x } => vec![Operand::BigFont, v(x)],
+        Instruction::LdRVx { x } => vec![Operand::Flags, v(x)],
+        Instruction::LdVxR { x } => vec![v(x), Operand::Flags],
+    }
+}
 
-        // TODO: Impl other opcodes
-        _ => create_disassembled_op_tuple(idx, op, "X", "Unsupported op found"),
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
     }
 }
 
 impl Display for DisassembledChip8 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let default_comment = String::from("");
-        self.op_tuple_vec.iter().for_each(|op_tuple| {
-            let comment = if let Some(comment_result) = &op_tuple.5 {
-                comment_result
-            } else {
-                &default_comment
-            };
-
-            match write!(f, "{} {:>4} {:>2} {:>5} {} {}\n", op_tuple.0, op_tuple.1, op_tuple.2, op_tuple.3, op_tuple.4, comment) {
-                Err(x) => println!("{:?}", x),
-                Ok(_) => {}
+        for op in &self.ops {
+            if let Some(label) = &op.label {
+                writeln!(f, "{}:", label)?;
             }
-        });
+            writeln!(f, "{}", op)?;
+        }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+#[path ="./disassemble_test.rs"]
+mod disassemble_test;
@@ -0,0 +1,91 @@
+use super::*;
+use crate::core::assembler::assemble;
+
+const IBM_LOGO: &[u8] = include_bytes!("../IBM_Logo.ch8");
+
+#[test]
+fn keeps_a_trailing_odd_byte_as_data() {
+    let listing = DisassembledChip8::new(&[0x00, 0xE0, 0xAB]).to_string();
+
+    assert_eq!("200   00 E0   CLS  Clears the screen\n202   AB       db AB \n", listing);
+    assert_eq!("db AB\n", DisassembledChip8::recursive(&[0xAB]).to_source());
+}
+
+#[test]
+fn follows_jumps_calls_and_skips() {
+    let rom = [
+        0x22, 0x08, // 200 CALL 208
+        0x30, 0x01, // 202 SE V0, 01
+        0x12, 0x02, // 204 JMP 202
+        0x12, 0x0C, // 206 JMP 20C, only reachable through the skip
+        0x00, 0xEE, // 208 RET
+        0xFF, 0xFF, // 20A never reached
+        0x00, 0xFD, // 20C EXIT
+        0x12,       // 20E odd byte
+    ];
+    let source = DisassembledChip8::recursive(&rom).to_source();
+
+    assert_eq!(
+        "CALL sub_208\nlabel_202:\nSE V0, 01 ; Vx == kk\nJMP label_202\nJMP label_20C\nsub_208:\nRET ; Returns from subroutine\ndb FF, FF\nlabel_20C:\nEXIT ; Exit interpreter\ndb 12\n",
+        source
+    );
+    assert_eq!(&rom[..], &assemble(&source).unwrap()[..]);
+}
+
+#[test]
+fn draws_sprites_as_bit_patterns() {
+    let listing = DisassembledChip8::recursive(IBM_LOGO).to_string();
+
+    assert!(listing.contains("202   A2 2A    LD I, sprite_22A \n"));
+    assert!(listing.contains("label_228:\n228   12 28   JMP label_228 \n"));
+    assert!(listing.contains("sprite_22A:\n22A   FF       db %11111111 ########\n22B   00       db %00000000 ........\n"));
+    assert!(!listing.contains("Unsupported"));
+}
+
+#[test]
+fn round_trips_the_ibm_logo() {
+    let source = DisassembledChip8::recursive(IBM_LOGO).to_source();
+
+    assert_eq!(IBM_LOGO, &assemble(&source).unwrap()[..]);
+}
+
+#[test]
+fn typed_operands_print_like_the_instruction() {
+    for opcode in 0..=0xFFFF_u16 {
+        if let Ok(instruction) = Instruction::decode(opcode) {
+            if instruction != Instruction::LdILong {
+                let text: Vec<String> = operands_of(&instruction).iter().map(Operand::to_string).collect();
+                assert_eq!(instruction.operands(), text.join(", "), "{:04X}", opcode);
+            }
+        }
+    }
+}
+
+#[test]
+fn iterates_typed_rows() {
+    let listing = DisassembledChip8::new(&[0x61, 0x0A, 0xF0, 0x00, 0x12, 0x34, 0xFF, 0xFF]);
+    let ops: Vec<&DisassembledOp> = listing.iter().collect();
+
+    assert_eq!(3, ops.len());
+    assert_eq!((0x200, Some(0x610A)), (ops[0].address, ops[0].opcode()));
+    assert_eq!(vec![Operand::Register(1), Operand::Byte(0x0A)], ops[0].operands);
+    assert_eq!(vec![Operand::I, Operand::Long(0x1234)], ops[1].operands);
+    assert_eq!((None, "dw"), (ops[2].instruction, ops[2].mnemonic));
+    assert_eq!(3, (&listing).into_iter().count());
+}
+
+#[test]
+fn serializes_to_json_and_csv() {
+    let listing = DisassembledChip8::recursive(&[0x12, 0x00, 0xAB]);
+
+    assert_eq!(
+        "[\n  {\"address\":512,\"bytes\":\"1200\",\"label\":\"label_200\",\"mnemonic\":\"JMP\",\"operands\":[{\"type\":\"label\",\"name\":\"label_200\",\"value\":512,\"long\":false}],\"text\":\"label_200\",\"comment\":null},\n  {\"address\":514,\"bytes\":\"AB\",\"label\":null,\"mnemonic\":\"db\",\"operands\":[{\"type\":\"byte\",\"value\":171}],\"text\":\"AB\",\"comment\":null}\n]\n",
+        listing.to_json()
+    );
+    assert_eq!(
+        "address,bytes,label,mnemonic,operands,comment\n200,1200,label_200,JMP,label_200,\n202,AB,,db,AB,\n",
+        listing.to_csv()
+    );
+    assert_eq!("\"V1, 0A\"", csv_field("V1, 0A"));
+    assert_eq!("\"say \\\"hi\\\"\"", json_string("say \"hi\""));
+}
@@ -0,0 +1,33 @@
+use super::framebuffer::FrameBuffer;
+
+// Text renderings of the display for golden image comparisons, no GL needed
+
+// One character per pixel, row per line: '.' off, '#' plane 1, '+' plane 2, '@' both
+pub fn to_ascii(gfx: &FrameBuffer) -> String {
+    let mut ascii = String::with_capacity((gfx.width() + 1) * gfx.height());
+    for row in gfx.pixels().chunks(gfx.width()) {
+        row.iter().for_each(|pixel| ascii.push(match pixel {
+            0x0 => '.',
+            0x1 => '#',
+            0x2 => '+',
+            _ => '@',
+        }));
+        ascii.push('\n');
+    }
+    ascii
+}
+
+// Plain (P1) portable bitmap, any lit plane counts as black
+pub fn to_pbm(gfx: &FrameBuffer) -> String {
+    let mut pbm = format!("P1\n{} {}\n", gfx.width(), gfx.height());
+    for row in gfx.pixels().chunks(gfx.width()) {
+        let bits: Vec<&str> = row.iter().map(|&pixel| if pixel != 0x0 { "1" } else { "0" }).collect();
+        pbm.push_str(&bits.join(" "));
+        pbm.push('\n');
+    }
+    pbm
+}
+
+#[cfg(test)]
+#[path ="./dump_test.rs"]
+mod dump_test;
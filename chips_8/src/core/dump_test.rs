@@ -0,0 +1,32 @@
+use super::*;
+use crate::core::framebuffer::{PLANE_1, PLANE_2};
+
+#[test]
+fn ascii_marks_each_plane() {
+    let mut gfx = FrameBuffer::new();
+    gfx.toggle(0, 0, PLANE_1);
+    gfx.toggle(1, 0, PLANE_2);
+    gfx.toggle(2, 0, PLANE_1);
+    gfx.toggle(2, 0, PLANE_2);
+    gfx.toggle(63, 31, PLANE_1);
+
+    let ascii = to_ascii(&gfx);
+    let lines: Vec<&str> = ascii.lines().collect();
+    assert_eq!(32, lines.len());
+    assert_eq!(format!("#+@{}", ".".repeat(61)), lines[0]);
+    assert_eq!(format!("{}#", ".".repeat(63)), lines[31]);
+}
+
+#[test]
+fn pbm_header_and_bits() {
+    let mut gfx = FrameBuffer::new();
+    gfx.set_hires(true);
+    gfx.toggle(1, 0, PLANE_2);
+
+    let pbm = to_pbm(&gfx);
+    let mut lines = pbm.lines();
+    assert_eq!(Some("P1"), lines.next());
+    assert_eq!(Some("128 64"), lines.next());
+    assert!(lines.next().unwrap().starts_with("0 1 0 0"));
+    assert_eq!(63, lines.count());
+}
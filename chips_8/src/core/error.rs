@@ -0,0 +1,27 @@
+use std::fmt::Display;
+
+// Faults raised by the CPU or while loading a program, these halt the program rather than the whole process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chip8Error {
+    UnknownOpcode { pc: u16, opcode: u16 },
+    StackOverflow { pc: u16 },
+    StackUnderflow { pc: u16 },
+    MemoryOutOfBounds { pc: u16, address: usize },
+    RomTooLarge { size: usize, capacity: usize },
+    MemoryTooSmall { size: usize },
+}
+
+impl Display for Chip8Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Chip8Error::UnknownOpcode { pc, opcode } => write!(f, "Unknown opcode {:04X} at {:03X}", opcode, pc),
+            Chip8Error::StackOverflow { pc } => write!(f, "Stack overflow at {:03X}", pc),
+            Chip8Error::StackUnderflow { pc } => write!(f, "Stack underflow at {:03X}", pc),
+            Chip8Error::MemoryOutOfBounds { pc, address } => write!(f, "Memory access out of bounds ({:X}) at {:03X}", address, pc),
+            Chip8Error::RomTooLarge { size, capacity } => write!(f, "ROM is {} bytes but only {} fit in memory", size, capacity),
+            Chip8Error::MemoryTooSmall { size } => write!(f, "Memory of {} bytes ends before the program start", size),
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {}
@@ -0,0 +1,137 @@
+pub const LORES_WIDTH: usize = 64;
+pub const LORES_HEIGHT: usize = 32;
+pub const HIRES_WIDTH: usize = 128;
+pub const HIRES_HEIGHT: usize = 64;
+
+// Bit per drawing plane, XO-CHIP has two so a pixel can take one of four colours
+pub const PLANE_1: u8 = 0x1;
+pub const PLANE_2: u8 = 0x2;
+pub const ALL_PLANES: u8 = PLANE_1 | PLANE_2;
+
+// Display buffer that can switch between the 64x32 CHIP-8 and 128x64 SUPER-CHIP resolutions
+// Every entry is one pixel holding a bit per plane, so plain CHIP-8 only ever sees 0 (off) and 1 (on)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameBuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    selected_planes: u8, // Planes affected by clearing, scrolling and drawing
+}
+
+impl FrameBuffer {
+    pub fn new() -> FrameBuffer {
+        FrameBuffer {
+            width: LORES_WIDTH,
+            height: LORES_HEIGHT,
+            pixels: vec![0; LORES_WIDTH * LORES_HEIGHT],
+            selected_planes: PLANE_1,
+        }
+    }
+
+    // Rebuilds a buffer from pixels(), None when the pixel count does not fit the resolution
+    pub fn from_pixels(hires: bool, selected_planes: u8, pixels: Vec<u8>) -> Option<FrameBuffer> {
+        let (width, height) = if hires { (HIRES_WIDTH, HIRES_HEIGHT) } else { (LORES_WIDTH, LORES_HEIGHT) };
+        if pixels.len() != width * height {
+            return None;
+        }
+        Some(FrameBuffer { width, height, pixels, selected_planes: selected_planes & ALL_PLANES })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.width == HIRES_WIDTH
+    }
+
+    // Switching resolution clears every plane, same as the SUPER-CHIP interpreter
+    pub fn set_hires(&mut self, hires: bool) {
+        let (width, height) = if hires { (HIRES_WIDTH, HIRES_HEIGHT) } else { (LORES_WIDTH, LORES_HEIGHT) };
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width * height];
+    }
+
+    pub fn selected_planes(&self) -> u8 {
+        self.selected_planes
+    }
+
+    pub fn select_planes(&mut self, planes: u8) {
+        self.selected_planes = planes & ALL_PLANES;
+    }
+
+    // Clears the selected planes only
+    pub fn clear(&mut self) {
+        let keep = !self.selected_planes;
+        self.pixels.iter_mut().for_each(|entry| *entry &= keep);
+    }
+
+    // Row major, width() pixels per row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    // XORs a lit pixel onto one plane, returns true when it turned an already lit pixel off
+    pub fn toggle(&mut self, x: usize, y: usize, plane: u8) -> bool {
+        let offset = y * self.width + x;
+        let collision = self.pixels[offset] & plane != 0x0;
+        self.pixels[offset] ^= plane;
+        collision
+    }
+
+    // Moves the selected planes from src to dst, anything shifted in is blank
+    fn move_selected(&mut self, moves: impl Iterator<Item = (usize, Option<usize>)>) {
+        let planes = self.selected_planes;
+        moves.for_each(|(dst, src)| {
+            let value = src.map_or(0, |src| self.pixels[src] & planes);
+            self.pixels[dst] = (self.pixels[dst] & !planes) | value;
+        });
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        let (width, height) = (self.width, self.height);
+        let moves = (0..height).rev().flat_map(move |y| {
+            (0..width).map(move |x| (y * width + x, if y >= rows { Some((y - rows) * width + x) } else { None }))
+        });
+        self.move_selected(moves);
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        let (width, height) = (self.width, self.height);
+        let moves = (0..height).flat_map(move |y| {
+            (0..width).map(move |x| (y * width + x, if y + rows < height { Some((y + rows) * width + x) } else { None }))
+        });
+        self.move_selected(moves);
+    }
+
+    pub fn scroll_right(&mut self, cols: usize) {
+        let (width, height) = (self.width, self.height);
+        let moves = (0..height).flat_map(move |y| {
+            (0..width).rev().map(move |x| (y * width + x, if x >= cols { Some(y * width + x - cols) } else { None }))
+        });
+        self.move_selected(moves);
+    }
+
+    pub fn scroll_left(&mut self, cols: usize) {
+        let (width, height) = (self.width, self.height);
+        let moves = (0..height).flat_map(move |y| {
+            (0..width).map(move |x| (y * width + x, if x + cols < width { Some(y * width + x + cols) } else { None }))
+        });
+        self.move_selected(moves);
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        FrameBuffer::new()
+    }
+}
@@ -0,0 +1,392 @@
+use std::fmt::Display;
+
+use super::utils::{get_kk, get_nibble, get_nnn, get_x, get_y};
+
+// Single decoded form of a CHIP-8 opcode, shared by the interpreter and the disassembler
+// x / y are register indices, kk is a byte, n is a nibble and nnn is a 12 bit address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    Cls,                             // 00E0 - CLS
+    Ret,                             // 00EE - RET
+    Sys { nnn: u16 },                // 0nnn - SYS addr
+    Jp { nnn: u16 },                 // 1nnn - JP addr
+    Call { nnn: u16 },               // 2nnn - CALL addr
+    SeByte { x: u8, kk: u8 },        // 3xkk - SE Vx, byte
+    SneByte { x: u8, kk: u8 },       // 4xkk - SNE Vx, byte
+    SeReg { x: u8, y: u8 },          // 5xy0 - SE Vx, Vy
+    LdByte { x: u8, kk: u8 },        // 6xkk - LD Vx, byte
+    AddByte { x: u8, kk: u8 },       // 7xkk - ADD Vx, byte
+    LdReg { x: u8, y: u8 },          // 8xy0 - LD Vx, Vy
+    Or { x: u8, y: u8 },             // 8xy1 - OR Vx, Vy
+    And { x: u8, y: u8 },            // 8xy2 - AND Vx, Vy
+    Xor { x: u8, y: u8 },            // 8xy3 - XOR Vx, Vy
+    AddReg { x: u8, y: u8 },         // 8xy4 - ADD Vx, Vy
+    Sub { x: u8, y: u8 },            // 8xy5 - SUB Vx, Vy
+    Shr { x: u8, y: u8 },            // 8xy6 - SHR Vx {, Vy}
+    Subn { x: u8, y: u8 },           // 8xy7 - SUBN Vx, Vy
+    Shl { x: u8, y: u8 },            // 8xyE - SHL Vx {, Vy}
+    SneReg { x: u8, y: u8 },         // 9xy0 - SNE Vx, Vy
+    LdI { nnn: u16 },                // Annn - LD I, addr
+    JpV0 { nnn: u16 },               // Bnnn - JP V0, addr
+    Rnd { x: u8, kk: u8 },           // Cxkk - RND Vx, byte
+    Drw { x: u8, y: u8, n: u8 },     // Dxyn - DRW Vx, Vy, nibble
+    Skp { x: u8 },                   // Ex9E - SKP Vx
+    Sknp { x: u8 },                  // ExA1 - SKNP Vx
+    LdVxDt { x: u8 },                // Fx07 - LD Vx, DT
+    LdVxK { x: u8 },                 // Fx0A - LD Vx, K
+    LdDtVx { x: u8 },                // Fx15 - LD DT, Vx
+    LdStVx { x: u8 },                // Fx18 - LD ST, Vx
+    AddIVx { x: u8 },                // Fx1E - ADD I, Vx
+    LdFVx { x: u8 },                 // Fx29 - LD F, Vx
+    LdBVx { x: u8 },                 // Fx33 - LD B, Vx
+    LdIVx { x: u8 },                 // Fx55 - LD [I], Vx
+    LdVxI { x: u8 },                 // Fx65 - LD Vx, [I]
+
+    // SUPER-CHIP 1.1
+    Scd { n: u8 },                   // 00Cn - SCD nibble
+    Scr,                             // 00FB - SCR
+    Scl,                             // 00FC - SCL
+    Exit,                            // 00FD - EXIT
+    Low,                             // 00FE - LOW
+    High,                            // 00FF - HIGH
+    LdHfVx { x: u8 },                // Fx30 - LD HF, Vx
+    LdRVx { x: u8 },                 // Fx75 - LD R, Vx
+    LdVxR { x: u8 },                 // Fx85 - LD Vx, R
+
+    // XO-CHIP
+    Scu { n: u8 },                   // 00Dn - SCU nibble
+    SaveRange { x: u8, y: u8 },      // 5xy2 - SAVE Vx, Vy
+    LoadRange { x: u8, y: u8 },      // 5xy3 - LOAD Vx, Vy
+    LdILong,                         // F000 nnnn - LD I, LONG nnnn, the address is the following word
+    Plane { n: u8 },                 // Fn01 - PLANE n
+    Audio,                           // F002 - AUDIO
+    Pitch { x: u8 },                 // Fx3A - PITCH Vx
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    UnknownOpcode(u16),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnknownOpcode(op) => write!(f, "Unsupported opcode detected: {:04X}", op),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl Instruction {
+    pub fn decode(op: u16) -> Result<Instruction, DecodeError> {
+        let x = get_x(&op) as u8;
+        let y = get_y(&op) as u8;
+        let kk = get_kk(&op) as u8;
+        let n = get_nibble(&op) as u8;
+        let nnn = get_nnn(&op);
+
+        let instruction = match op & 0xF000 {
+            0x0000 => match op {
+                0x00C0..=0x00CF => Instruction::Scd { n },
+                0x00D0..=0x00DF => Instruction::Scu { n },
+                0x00E0 => Instruction::Cls,
+                0x00EE => Instruction::Ret,
+                0x00FB => Instruction::Scr,
+                0x00FC => Instruction::Scl,
+                0x00FD => Instruction::Exit,
+                0x00FE => Instruction::Low,
+                0x00FF => Instruction::High,
+                _ => Instruction::Sys { nnn },
+            },
+            0x1000 => Instruction::Jp { nnn },
+            0x2000 => Instruction::Call { nnn },
+            0x3000 => Instruction::SeByte { x, kk },
+            0x4000 => Instruction::SneByte { x, kk },
+            0x5000 => match n {
+                0x0 => Instruction::SeReg { x, y },
+                0x2 => Instruction::SaveRange { x, y },
+                0x3 => Instruction::LoadRange { x, y },
+                _ => return Err(DecodeError::UnknownOpcode(op)),
+            },
+            0x6000 => Instruction::LdByte { x, kk },
+            0x7000 => Instruction::AddByte { x, kk },
+            0x8000 => match n {
+                0x0 => Instruction::LdReg { x, y },
+                0x1 => Instruction::Or { x, y },
+                0x2 => Instruction::And { x, y },
+                0x3 => Instruction::Xor { x, y },
+                0x4 => Instruction::AddReg { x, y },
+                0x5 => Instruction::Sub { x, y },
+                0x6 => Instruction::Shr { x, y },
+                0x7 => Instruction::Subn { x, y },
+                0xE => Instruction::Shl { x, y },
+                _ => return Err(DecodeError::UnknownOpcode(op)),
+            },
+            0x9000 if n == 0x0 => Instruction::SneReg { x, y },
+            0xA000 => Instruction::LdI { nnn },
+            0xB000 => Instruction::JpV0 { nnn },
+            0xC000 => Instruction::Rnd { x, kk },
+            0xD000 => Instruction::Drw { x, y, n },
+            0xE000 => match kk {
+                0x9E => Instruction::Skp { x },
+                0xA1 => Instruction::Sknp { x },
+                _ => return Err(DecodeError::UnknownOpcode(op)),
+            },
+            0xF000 => match kk {
+                0x00 if x == 0x0 => Instruction::LdILong,
+                0x01 => Instruction::Plane { n: x },
+                0x02 if x == 0x0 => Instruction::Audio,
+                0x07 => Instruction::LdVxDt { x },
+                0x0A => Instruction::LdVxK { x },
+                0x15 => Instruction::LdDtVx { x },
+                0x18 => Instruction::LdStVx { x },
+                0x1E => Instruction::AddIVx { x },
+                0x29 => Instruction::LdFVx { x },
+                0x30 => Instruction::LdHfVx { x },
+                0x3A => Instruction::Pitch { x },
+                0x33 => Instruction::LdBVx { x },
+                0x55 => Instruction::LdIVx { x },
+                0x65 => Instruction::LdVxI { x },
+                0x75 => Instruction::LdRVx { x },
+                0x85 => Instruction::LdVxR { x },
+                _ => return Err(DecodeError::UnknownOpcode(op)),
+            },
+            _ => return Err(DecodeError::UnknownOpcode(op)),
+        };
+
+        Ok(instruction)
+    }
+
+    pub fn encode(&self) -> u16 {
+        // Small helpers for packing the operand fields back into place
+        let xy = |prefix: u16, x: u8, y: u8, n: u16| prefix | (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4 | n;
+        let xkk = |prefix: u16, x: u8, kk: u8| prefix | (x as u16 & 0xF) << 8 | kk as u16;
+        let xop = |x: u8, low: u16| 0xF000 | (x as u16 & 0xF) << 8 | low;
+        let ex = |x: u8, low: u16| 0xE000 | (x as u16 & 0xF) << 8 | low;
+
+        match *self {
+            Instruction::Cls => 0x00E0,
+            Instruction::Ret => 0x00EE,
+            Instruction::Sys { nnn } => nnn & 0x0FFF,
+            Instruction::Jp { nnn } => 0x1000 | (nnn & 0x0FFF),
+            Instruction::Call { nnn } => 0x2000 | (nnn & 0x0FFF),
+            Instruction::SeByte { x, kk } => xkk(0x3000, x, kk),
+            Instruction::SneByte { x, kk } => xkk(0x4000, x, kk),
+            Instruction::SeReg { x, y } => xy(0x5000, x, y, 0x0),
+            Instruction::LdByte { x, kk } => xkk(0x6000, x, kk),
+            Instruction::AddByte { x, kk } => xkk(0x7000, x, kk),
+            Instruction::LdReg { x, y } => xy(0x8000, x, y, 0x0),
+            Instruction::Or { x, y } => xy(0x8000, x, y, 0x1),
+            Instruction::And { x, y } => xy(0x8000, x, y, 0x2),
+            Instruction::Xor { x, y } => xy(0x8000, x, y, 0x3),
+            Instruction::AddReg { x, y } => xy(0x8000, x, y, 0x4),
+            Instruction::Sub { x, y } => xy(0x8000, x, y, 0x5),
+            Instruction::Shr { x, y } => xy(0x8000, x, y, 0x6),
+            Instruction::Subn { x, y } => xy(0x8000, x, y, 0x7),
+            Instruction::Shl { x, y } => xy(0x8000, x, y, 0xE),
+            Instruction::SneReg { x, y } => xy(0x9000, x, y, 0x0),
+            Instruction::LdI { nnn } => 0xA000 | (nnn & 0x0FFF),
+            Instruction::JpV0 { nnn } => 0xB000 | (nnn & 0x0FFF),
+            Instruction::Rnd { x, kk } => xkk(0xC000, x, kk),
+            Instruction::Drw { x, y, n } => xy(0xD000, x, y, n as u16 & 0xF),
+            Instruction::Skp { x } => ex(x, 0x9E),
+            Instruction::Sknp { x } => ex(x, 0xA1),
+            Instruction::LdVxDt { x } => xop(x, 0x07),
+            Instruction::LdVxK { x } => xop(x, 0x0A),
+            Instruction::LdDtVx { x } => xop(x, 0x15),
+            Instruction::LdStVx { x } => xop(x, 0x18),
+            Instruction::AddIVx { x } => xop(x, 0x1E),
+            Instruction::LdFVx { x } => xop(x, 0x29),
+            Instruction::LdBVx { x } => xop(x, 0x33),
+            Instruction::LdIVx { x } => xop(x, 0x55),
+            Instruction::LdVxI { x } => xop(x, 0x65),
+            Instruction::Scd { n } => 0x00C0 | (n as u16 & 0xF),
+            Instruction::Scr => 0x00FB,
+            Instruction::Scl => 0x00FC,
+            Instruction::Exit => 0x00FD,
+            Instruction::Low => 0x00FE,
+            Instruction::High => 0x00FF,
+            Instruction::LdHfVx { x } => xop(x, 0x30),
+            Instruction::LdRVx { x } => xop(x, 0x75),
+            Instruction::LdVxR { x } => xop(x, 0x85),
+            Instruction::Scu { n } => 0x00D0 | (n as u16 & 0xF),
+            Instruction::SaveRange { x, y } => xy(0x5000, x, y, 0x2),
+            Instruction::LoadRange { x, y } => xy(0x5000, x, y, 0x3),
+            Instruction::LdILong => 0xF000,
+            Instruction::Plane { n } => xop(n, 0x01),
+            Instruction::Audio => 0xF002,
+            Instruction::Pitch { x } => xop(x, 0x3A),
+        }
+    }
+
+    // The XO-CHIP additions, unknown opcodes unless the xo_chip quirk is on
+    pub fn is_xo_chip(&self) -> bool {
+        matches!(
+            self,
+            Instruction::SaveRange { .. }
+                | Instruction::LoadRange { .. }
+                | Instruction::LdILong
+                | Instruction::Plane { .. }
+                | Instruction::Audio
+                | Instruction::Pitch { .. }
+        )
+    }
+
+    // Mnemonic as shown by the disassembler
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Cls => "CLS",
+            Instruction::Ret => "RET",
+            Instruction::Sys { .. } => "SYS",
+            Instruction::Jp { .. } | Instruction::JpV0 { .. } => "JMP",
+            Instruction::Call { .. } => "CALL",
+            Instruction::SeByte { .. } | Instruction::SeReg { .. } => "SE",
+            Instruction::SneByte { .. } | Instruction::SneReg { .. } => "SNE",
+            Instruction::AddByte { .. } | Instruction::AddReg { .. } | Instruction::AddIVx { .. } => "ADD",
+            Instruction::Or { .. } => "OR",
+            Instruction::And { .. } => "AND",
+            Instruction::Xor { .. } => "XOR",
+            Instruction::Sub { .. } => "SUB",
+            Instruction::Shr { .. } => "SHR",
+            Instruction::Subn { .. } => "SUBN",
+            Instruction::Shl { .. } => "SHL",
+            Instruction::Rnd { .. } => "RND",
+            Instruction::Drw { .. } => "DRW",
+            Instruction::Skp { .. } => "SKP",
+            Instruction::Sknp { .. } => "SKNP",
+            Instruction::Scd { .. } => "SCD",
+            Instruction::Scr => "SCR",
+            Instruction::Scl => "SCL",
+            Instruction::Exit => "EXIT",
+            Instruction::Low => "LOW",
+            Instruction::High => "HIGH",
+            Instruction::Scu { .. } => "SCU",
+            Instruction::SaveRange { .. } => "SAVE",
+            Instruction::LoadRange { .. } => "LOAD",
+            Instruction::Plane { .. } => "PLANE",
+            Instruction::Audio => "AUDIO",
+            Instruction::Pitch { .. } => "PITCH",
+            Instruction::LdByte { .. }
+            | Instruction::LdReg { .. }
+            | Instruction::LdI { .. }
+            | Instruction::LdVxDt { .. }
+            | Instruction::LdVxK { .. }
+            | Instruction::LdDtVx { .. }
+            | Instruction::LdStVx { .. }
+            | Instruction::LdFVx { .. }
+            | Instruction::LdBVx { .. }
+            | Instruction::LdIVx { .. }
+            | Instruction::LdVxI { .. }
+            | Instruction::LdHfVx { .. }
+            | Instruction::LdRVx { .. }
+            | Instruction::LdVxR { .. }
+            | Instruction::LdILong => "LD",
+        }
+    }
+
+    // Operands formatted the same way the disassembler lists them, e.g. "V1, 0A"
+    pub fn operands(&self) -> String {
+        match *self {
+            Instruction::Cls
+            | Instruction::Ret
+            | Instruction::Scr
+            | Instruction::Scl
+            | Instruction::Exit
+            | Instruction::Low
+            | Instruction::High
+            | Instruction::Audio => String::new(),
+            Instruction::LdILong => String::from("I, LONG"),
+            Instruction::Plane { n } => format!("{:X}", n),
+            Instruction::Pitch { x } => format!("V{:X}", x),
+            Instruction::Scd { n } | Instruction::Scu { n } => format!("{:X}", n),
+            Instruction::Sys { nnn } | Instruction::Jp { nnn } | Instruction::Call { nnn } => format!("{:03X}", nnn),
+            Instruction::SeByte { x, kk }
+            | Instruction::SneByte { x, kk }
+            | Instruction::LdByte { x, kk }
+            | Instruction::AddByte { x, kk }
+            | Instruction::Rnd { x, kk } => format!("V{:X}, {:02X}", x, kk),
+            Instruction::SeReg { x, y }
+            | Instruction::LdReg { x, y }
+            | Instruction::Or { x, y }
+            | Instruction::And { x, y }
+            | Instruction::Xor { x, y }
+            | Instruction::AddReg { x, y }
+            | Instruction::Sub { x, y }
+            | Instruction::Shr { x, y }
+            | Instruction::Subn { x, y }
+            | Instruction::Shl { x, y }
+            | Instruction::SneReg { x, y }
+            | Instruction::SaveRange { x, y }
+            | Instruction::LoadRange { x, y } => format!("V{:X}, V{:X}", x, y),
+            Instruction::LdI { nnn } => format!("I, {:03X}", nnn),
+            Instruction::JpV0 { nnn } => format!("V0, {:03X}", nnn),
+            Instruction::Drw { x, y, n } => format!("V{:X}, V{:X}, {:X}", x, y, n),
+            Instruction::Skp { x } | Instruction::Sknp { x } => format!("V{:X}", x),
+            Instruction::LdVxDt { x } => format!("V{:X}, DT", x),
+            Instruction::LdVxK { x } => format!("V{:X}, K", x),
+            Instruction::LdDtVx { x } => format!("DT, V{:X}", x),
+            Instruction::LdStVx { x } => format!("ST, V{:X}", x),
+            Instruction::AddIVx { x } => format!("I, V{:X}", x),
+            Instruction::LdFVx { x } => format!("F, V{:X}", x),
+            Instruction::LdBVx { x } => format!("B, V{:X}", x),
+            Instruction::LdIVx { x } => format!("[I], V{:X}", x),
+            Instruction::LdVxI { x } => format!("V{:X}, [I]", x),
+            Instruction::LdHfVx { x } => format!("HF, V{:X}", x),
+            Instruction::LdRVx { x } => format!("R, V{:X}", x),
+            Instruction::LdVxR { x } => format!("V{:X}, R", x),
+        }
+    }
+
+    // Short human readable note, only for the instructions that benefit from one
+    pub fn comment(&self) -> Option<&'static str> {
+        match self {
+            Instruction::Cls => Some("Clears the screen"),
+            Instruction::Ret => Some("Returns from subroutine"),
+            Instruction::Sys { .. } => Some("Feature used by old computers"),
+            Instruction::SeByte { .. } => Some("Vx == kk"),
+            Instruction::SneByte { .. } => Some("Vx != kk"),
+            Instruction::SeReg { .. } => Some("Vx == Vy"),
+            Instruction::SneReg { .. } => Some("Vx != Vy"),
+            Instruction::LdVxDt { .. } => Some("DT - Delay Timer"),
+            Instruction::LdVxK { .. } => Some("Stop execution till keypress"),
+            Instruction::LdStVx { .. } => Some("ST - Sound Timer"),
+            Instruction::LdFVx { .. } => Some("Load font"),
+            Instruction::LdBVx { .. } => Some("Set BCD"),
+            Instruction::LdIVx { .. } => Some("Reg Dump"),
+            Instruction::LdVxI { .. } => Some("Reg Load"),
+            Instruction::Scd { .. } => Some("Scroll down n lines"),
+            Instruction::Scr => Some("Scroll right 4 pixels"),
+            Instruction::Scl => Some("Scroll left 4 pixels"),
+            Instruction::Exit => Some("Exit interpreter"),
+            Instruction::Low => Some("Low resolution 64x32"),
+            Instruction::High => Some("High resolution 128x64"),
+            Instruction::LdHfVx { .. } => Some("Load big font"),
+            Instruction::LdRVx { .. } => Some("Save to RPL flags"),
+            Instruction::LdVxR { .. } => Some("Load from RPL flags"),
+            Instruction::Scu { .. } => Some("Scroll up n lines"),
+            Instruction::SaveRange { .. } => Some("Save Vx through Vy"),
+            Instruction::LoadRange { .. } => Some("Load Vx through Vy"),
+            Instruction::LdILong => Some("Load 16 bit address"),
+            Instruction::Plane { .. } => Some("Select drawing planes"),
+            Instruction::Audio => Some("Load audio pattern"),
+            Instruction::Pitch { .. } => Some("Set audio pitch"),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operands = self.operands();
+        if operands.is_empty() {
+            write!(f, "{}", self.mnemonic())
+        } else {
+            write!(f, "{} {}", self.mnemonic(), operands)
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./instruction_test.rs"]
+mod instruction_test;
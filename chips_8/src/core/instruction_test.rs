@@ -0,0 +1,84 @@
+use super::*;
+
+#[test]
+fn round_trip_every_opcode() {
+    let mut decoded_count = 0;
+
+    for op in 0x0000..=0xFFFF_u16 {
+        if let Ok(instruction) = Instruction::decode(op) {
+            decoded_count += 1;
+            assert_eq!(op, instruction.encode(), "{:04X} decoded as {:?}", op, instruction);
+            assert_eq!(Ok(instruction), Instruction::decode(instruction.encode()));
+        }
+    }
+
+    // Everything outside of the 5/8/9/E/F sub-opcodes decodes, SUPER-CHIP 00xx calls share the SYS range
+    let rejected = 0x10000 - decoded_count;
+    let expected_rejected =
+        0x100 * (16 - 3)      // 5xyN with N outside of 0, 2, 3
+        + 0x100 * (16 - 9)    // 8xyN with N outside of 0-7,E
+        + 0x100 * 0xF         // 9xyN with N != 0
+        + 0x10 * (256 - 2)    // ExKK outside of 9E, A1
+        + 0x10 * (256 - 16)   // FxKK outside of the sixteen F ops
+        + 0xF * 2;            // F000 and F002 only exist with x == 0
+    assert_eq!(expected_rejected, rejected);
+}
+
+#[test]
+fn rejects_unknown_opcodes() {
+    assert_eq!(Err(DecodeError::UnknownOpcode(0x8008)), Instruction::decode(0x8008));
+    assert_eq!(Err(DecodeError::UnknownOpcode(0x5121)), Instruction::decode(0x5121));
+    assert_eq!(Err(DecodeError::UnknownOpcode(0x5124)), Instruction::decode(0x5124));
+    assert_eq!(Err(DecodeError::UnknownOpcode(0xE1FF)), Instruction::decode(0xE1FF));
+    assert_eq!(Err(DecodeError::UnknownOpcode(0xF1FF)), Instruction::decode(0xF1FF));
+}
+
+#[test]
+fn decodes_operands() {
+    assert_eq!(Ok(Instruction::Cls), Instruction::decode(0x00E0));
+    assert_eq!(Ok(Instruction::Ret), Instruction::decode(0x00EE));
+    assert_eq!(Ok(Instruction::Sys { nnn: 0x123 }), Instruction::decode(0x0123));
+    assert_eq!(Ok(Instruction::LdI { nnn: 0x22A }), Instruction::decode(0xA22A));
+    assert_eq!(Ok(Instruction::Rnd { x: 0x3, kk: 0x0F }), Instruction::decode(0xC30F));
+    assert_eq!(Ok(Instruction::Drw { x: 0x0, y: 0x1, n: 0xF }), Instruction::decode(0xD01F));
+    assert_eq!(Ok(Instruction::Shl { x: 0x4, y: 0x5 }), Instruction::decode(0x845E));
+    assert_eq!(Ok(Instruction::LdVxI { x: 0xA }), Instruction::decode(0xFA65));
+}
+
+#[test]
+fn decodes_super_chip() {
+    assert_eq!(Ok(Instruction::Scd { n: 0x4 }), Instruction::decode(0x00C4));
+    assert_eq!(Ok(Instruction::Scr), Instruction::decode(0x00FB));
+    assert_eq!(Ok(Instruction::Scl), Instruction::decode(0x00FC));
+    assert_eq!(Ok(Instruction::Exit), Instruction::decode(0x00FD));
+    assert_eq!(Ok(Instruction::Low), Instruction::decode(0x00FE));
+    assert_eq!(Ok(Instruction::High), Instruction::decode(0x00FF));
+    assert_eq!(Ok(Instruction::LdHfVx { x: 0x2 }), Instruction::decode(0xF230));
+    assert_eq!(Ok(Instruction::LdRVx { x: 0x7 }), Instruction::decode(0xF775));
+    assert_eq!(Ok(Instruction::LdVxR { x: 0x7 }), Instruction::decode(0xF785));
+    assert_eq!("LD HF, V2", Instruction::LdHfVx { x: 0x2 }.to_string());
+    assert_eq!("SCD 4", Instruction::Scd { n: 0x4 }.to_string());
+}
+
+#[test]
+fn decodes_xo_chip() {
+    assert_eq!(Ok(Instruction::Scu { n: 0x2 }), Instruction::decode(0x00D2));
+    assert_eq!(Ok(Instruction::SaveRange { x: 0x1, y: 0x4 }), Instruction::decode(0x5142));
+    assert_eq!(Ok(Instruction::LoadRange { x: 0x4, y: 0x1 }), Instruction::decode(0x5413));
+    assert_eq!(Ok(Instruction::LdILong), Instruction::decode(0xF000));
+    assert_eq!(Ok(Instruction::Plane { n: 0x3 }), Instruction::decode(0xF301));
+    assert_eq!(Ok(Instruction::Audio), Instruction::decode(0xF002));
+    assert_eq!(Ok(Instruction::Pitch { x: 0x5 }), Instruction::decode(0xF53A));
+    assert_eq!(Err(DecodeError::UnknownOpcode(0xF100)), Instruction::decode(0xF100));
+    assert_eq!(Err(DecodeError::UnknownOpcode(0xF102)), Instruction::decode(0xF102));
+    assert_eq!("SAVE V1, V4", Instruction::SaveRange { x: 0x1, y: 0x4 }.to_string());
+}
+
+#[test]
+fn formats_like_the_disassembler() {
+    assert_eq!("LD V1, 0A", Instruction::LdByte { x: 0x1, kk: 0x0A }.to_string());
+    assert_eq!("DRW V0, V1, 5", Instruction::Drw { x: 0x0, y: 0x1, n: 0x5 }.to_string());
+    assert_eq!("JMP 200", Instruction::Jp { nnn: 0x200 }.to_string());
+    assert_eq!("RND V2, FF", Instruction::Rnd { x: 0x2, kk: 0xFF }.to_string());
+    assert_eq!("CLS", Instruction::Cls.to_string());
+}
@@ -0,0 +1,137 @@
+use super::blocks::BlockCache;
+use super::error::Chip8Error;
+use super::ops::{MyChips8, StepOutcome};
+
+// Timers always count down at 60Hz, every frame of the virtual clock is one timer tick
+pub const FRAMES_PER_SECOND: u64 = 60;
+pub const DEFAULT_INSTRUCTIONS_PER_SECOND: u64 = 700;
+
+// What happened over the course of one frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameOutcome {
+    pub instructions: u64, // Instructions actually executed this frame
+    pub drew: bool,        // Display changed, the front end should redraw
+    pub exited: bool,      // Program ran 00FD, no further frames will execute anything
+}
+
+// Runs instructions in place of MyChips8::enumlate_cycle, with the same results and instruction counts
+pub trait Engine {
+    fn step(&mut self, cpu: &mut MyChips8) -> Result<StepOutcome, Chip8Error>;
+
+    // Runs up to limit instructions, stopping early on anything but Executed or Drew
+    // Hands back how many ran, whether any drew and the outcome it stopped on (Executed at the limit)
+    fn run(&mut self, cpu: &mut MyChips8, limit: u64) -> Result<(u64, bool, StepOutcome), Chip8Error>;
+}
+
+// Drives MyChips8 from a virtual clock so game speed does not depend on how fast the host loop spins
+// Both the GL front end and headless runners step through run_frame()
+pub struct Machine {
+    pub cpu: MyChips8,
+    instructions_per_second: u64,
+    frame: u64,         // Frames run so far
+    instructions: u64,  // Instruction slots used so far, including ones given up while waiting
+    engine: Option<Box<dyn Engine>>, // Runs instructions instead of the plain interpreter when set
+}
+
+impl Machine {
+    pub fn new(cpu: MyChips8) -> Machine {
+        Machine::new_with_speed(cpu, DEFAULT_INSTRUCTIONS_PER_SECOND)
+    }
+
+    pub fn new_with_speed(cpu: MyChips8, instructions_per_second: u64) -> Machine {
+        Machine { cpu, instructions_per_second, frame: 0, instructions: 0, engine: None }
+    }
+
+    // Same results either way, engines only change how fast instructions run
+    pub fn set_engine(&mut self, engine: Option<Box<dyn Engine>>) {
+        self.engine = engine;
+    }
+
+    pub fn set_block_cache(&mut self, enabled: bool) {
+        self.set_engine(if enabled { Some(Box::new(BlockCache::new())) } else { None });
+    }
+
+    pub fn instructions_per_second(&self) -> u64 {
+        self.instructions_per_second
+    }
+
+    // Takes effect from the next frame, the clock restarts its count so speed changes never skip or burst
+    pub fn set_instructions_per_second(&mut self, instructions_per_second: u64) {
+        self.instructions_per_second = instructions_per_second;
+        self.instructions = self.budget_until(self.frame);
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    // Instruction slots the clock allows from the start up to the given frame, spread evenly so
+    // a rate that does not divide by 60 still averages out exactly
+    fn budget_until(&self, frame: u64) -> u64 {
+        frame * self.instructions_per_second / FRAMES_PER_SECOND
+    }
+
+    // Runs a single instruction slot, moving into the next frame (timer tick, vertical blank) once the
+    // current frame's slots are used up, so stepping one instruction at a time keeps the same timing
+    pub fn step(&mut self) -> Result<StepOutcome, Chip8Error> {
+        let outcome = self.cycle()?;
+        self.instructions += 1;
+        if self.instructions >= self.budget_until(self.frame + 1) {
+            self.cpu.tick_timers();
+            self.frame += 1;
+            self.cpu.vblank();
+        }
+        Ok(outcome)
+    }
+
+    // Runs one 1/60th of a second: the instructions that fit in the frame followed by one timer tick
+    pub fn run_frame(&mut self) -> Result<FrameOutcome, Chip8Error> {
+        let mut outcome = FrameOutcome::default();
+        let target = self.budget_until(self.frame + 1).max(self.instructions);
+
+        self.cpu.vblank();
+        match &mut self.engine {
+            // Same rules as the loop below
+            Some(engine) => {
+                let (executed, drew, last) = engine.run(&mut self.cpu, target - self.instructions)?;
+                outcome.instructions = executed;
+                outcome.drew = drew;
+                outcome.exited = last == StepOutcome::Exited;
+            }
+            None => {
+                while self.instructions < target {
+                    match self.cpu.enumlate_cycle()? {
+                        StepOutcome::Executed => {}
+                        StepOutcome::Drew => outcome.drew = true,
+                        // Nothing else can happen until the next frame, the remaining slots are idle time
+                        StepOutcome::WaitingForVBlank | StepOutcome::WaitingForKey => break,
+                        StepOutcome::Exited => {
+                            outcome.exited = true;
+                            break;
+                        }
+                    }
+                    self.instructions += 1;
+                    outcome.instructions += 1;
+                }
+            }
+        }
+        self.instructions = target;
+
+        self.cpu.tick_timers();
+        self.frame += 1;
+
+        Ok(outcome)
+    }
+
+    // One instruction through whichever engine is selected, the clock is left alone
+    pub fn cycle(&mut self) -> Result<StepOutcome, Chip8Error> {
+        match &mut self.engine {
+            Some(engine) => engine.step(&mut self.cpu),
+            None => self.cpu.enumlate_cycle(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./machine_test.rs"]
+mod machine_test;
@@ -0,0 +1,91 @@
+use super::*;
+use crate::core::quirks::Quirks;
+
+// JP 200, spins forever so every instruction slot gets used
+const SPIN: [u8; 2] = [0x12, 0x00];
+
+fn machine_with_rom(quirks: Quirks, rom: &[u8], instructions_per_second: u64) -> Machine {
+    let mut cpu = MyChips8::new_with_quirks(quirks);
+    cpu.load_rom(rom).unwrap();
+    Machine::new_with_speed(cpu, instructions_per_second)
+}
+
+#[test]
+fn runs_instructions_per_second_spread_over_frames() {
+    let mut machine = machine_with_rom(Quirks::default(), &SPIN, 700);
+
+    let per_frame: Vec<u64> = (0..60).map(|_| machine.run_frame().unwrap().instructions).collect();
+    assert_eq!(700, per_frame.iter().sum::<u64>());
+    assert!(per_frame.iter().all(|&count| count == 11 || count == 12));
+    assert_eq!(60, machine.frame());
+}
+
+#[test]
+fn timers_tick_once_per_frame() {
+    // LD V0, 0A / LD DT, V0 / LD ST, V0 / JP 206
+    let rom = [0x60, 0x0A, 0xF0, 0x15, 0xF0, 0x18, 0x12, 0x06];
+    let mut machine = machine_with_rom(Quirks::default(), &rom, 6000);
+
+    machine.run_frame().unwrap();
+    assert_eq!((9, 9), (machine.cpu.delay_timer(), machine.cpu.sound_timer()));
+
+    // Timer speed does not depend on how many instructions run in between
+    machine.set_instructions_per_second(60);
+    (0..4).for_each(|_| { machine.run_frame().unwrap(); });
+    assert_eq!((5, 5), (machine.cpu.delay_timer(), machine.cpu.sound_timer()));
+
+    (0..10).for_each(|_| { machine.run_frame().unwrap(); });
+    assert_eq!((0, 0), (machine.cpu.delay_timer(), machine.cpu.sound_timer()));
+}
+
+#[test]
+fn display_wait_ends_the_frame() {
+    // DRW V0, V0, 1 / JP 200
+    let rom = [0xD0, 0x01, 0x12, 0x00];
+    let mut machine = machine_with_rom(Quirks::cosmac_vip(), &rom, 600);
+
+    // Draw, jump back, then the second draw has to wait for the next frame
+    let outcome = machine.run_frame().unwrap();
+    assert_eq!(FrameOutcome { instructions: 2, drew: true, exited: false }, outcome);
+    assert_eq!(0x200, machine.cpu.pc);
+
+    let outcome = machine.run_frame().unwrap();
+    assert_eq!(2, outcome.instructions);
+}
+
+#[test]
+fn exit_stops_the_machine() {
+    // EXIT
+    let mut machine = machine_with_rom(Quirks::super_chip(), &[0x00, 0xFD], 600);
+
+    assert!(machine.run_frame().unwrap().exited);
+    assert!(machine.run_frame().unwrap().exited);
+}
+
+#[test]
+fn timers_keep_running_during_a_key_wait() {
+    // LD V0, 0A / LD DT, V0 / LD V1, K
+    let rom = [0x60, 0x0A, 0xF0, 0x15, 0xF1, 0x0A];
+    let mut machine = machine_with_rom(Quirks::default(), &rom, 700);
+
+    (0..5).for_each(|_| { machine.run_frame().unwrap(); });
+    assert!(machine.cpu.is_waiting_for_key());
+    assert_eq!(5, machine.cpu.delay_timer());
+}
+
+#[test]
+fn stepping_keeps_the_frame_timing() {
+    // LD V0, 0A / LD DT, V0 / JP 204
+    let rom = [0x60, 0x0A, 0xF0, 0x15, 0x12, 0x04];
+    let mut machine = machine_with_rom(Quirks::default(), &rom, 600);
+
+    // Ten instructions per frame at 600 per second
+    (0..9).for_each(|_| { machine.step().unwrap(); });
+    assert_eq!((0, 10), (machine.frame(), machine.cpu.delay_timer()));
+    machine.step().unwrap();
+    assert_eq!((1, 9), (machine.frame(), machine.cpu.delay_timer()));
+
+    // Stepping and whole frames share the same clock
+    machine.run_frame().unwrap();
+    assert_eq!((2, 8), (machine.frame(), machine.cpu.delay_timer()));
+}
@@ -1,3 +1,22 @@
 pub mod ops;
+pub mod audio;
+pub mod blocks;
+pub mod bus;
+pub mod assembler;
+pub mod cfg;
+pub mod debugger;
 pub mod disassemble;
-pub mod utils;
\ No newline at end of file
+pub mod dump;
+pub mod error;
+pub mod framebuffer;
+pub mod instruction;
+pub mod machine;
+pub mod movie;
+pub mod quirks;
+pub mod random;
+pub mod recompile;
+pub mod rewind;
+pub mod savestate;
+pub mod script;
+pub mod trace;
+pub mod utils;
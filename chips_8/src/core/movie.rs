@@ -0,0 +1,129 @@
+use std::fmt::Display;
+
+use super::error::Chip8Error;
+use super::machine::Machine;
+use super::ops::MyChips8;
+use super::quirks::Quirks;
+use super::random::XorShift;
+use super::savestate::{quirks_from_bits, quirks_to_bits};
+use super::script::{parse_event, KeyEvent, KeyScript, ScriptError};
+
+// A recorded session, everything needed to play it back exactly: the ROM it ran, the RND seed, the quirks,
+// the speed and every keypad change with the frame it happened on. Stored as text, '#' starts a comment:
+//   rom 9A1C0F6B23D84E51   # FNV-1a hash of the ROM, replaying against another ROM is refused
+//   seed 1234
+//   quirks 2D              # One bit per quirk, same as save states
+//   speed 700
+//   frames 600             # Length of the session
+//   120 down 5             # Key events, same as key scripts
+//   130 up 5
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_hash: u64,
+    pub seed: u64,
+    pub quirks: Quirks,
+    pub speed: u64,
+    pub frames: u64,
+    events: Vec<KeyEvent>,
+}
+
+impl Movie {
+    pub fn new(rom: &[u8], seed: u64, quirks: Quirks, speed: u64) -> Movie {
+        Movie { rom_hash: rom_hash(rom), seed, quirks, speed, frames: 0, events: Vec::new() }
+    }
+
+    pub fn events(&self) -> &[KeyEvent] {
+        &self.events
+    }
+
+    // Adds a keypad change, frame is Machine::frame() at the time, i.e. the frame the key first affects
+    pub fn record(&mut self, frame: u64, key: u8, pressed: bool) {
+        self.events.push(KeyEvent { frame, key, pressed });
+        self.frames = self.frames.max(frame);
+    }
+
+    pub fn matches_rom(&self, rom: &[u8]) -> bool {
+        rom_hash(rom) == self.rom_hash
+    }
+
+    // CPU set up the way the recording started, plus the key events to feed it
+    pub fn start(&self, rom: &[u8]) -> Result<(Machine, KeyScript), Chip8Error> {
+        let mut cpu = MyChips8::new_with_quirks(self.quirks);
+        cpu.set_random_source(Box::new(XorShift::new(self.seed)));
+        cpu.load_rom(rom)?;
+        Ok((Machine::new_with_speed(cpu, self.speed), KeyScript::new(self.events.clone())))
+    }
+
+    // Plays the whole session and hands back the machine as it was when recording stopped
+    pub fn replay(&self, rom: &[u8]) -> Result<Machine, Chip8Error> {
+        let (mut machine, mut script) = self.start(rom)?;
+        while machine.frame() < self.frames {
+            script.apply(machine.frame(), &mut machine.cpu);
+            if machine.run_frame()?.exited {
+                break;
+            }
+        }
+        Ok(machine)
+    }
+
+    pub fn parse(source: &str) -> Result<Movie, ScriptError> {
+        let mut movie = Movie { rom_hash: 0, seed: 0, quirks: Quirks::default(), speed: 0, frames: 0, events: Vec::new() };
+        let mut seen = Vec::new();
+        for (idx, line) in source.lines().enumerate() {
+            let line_number = idx + 1;
+            let error = |message: String| ScriptError { line: line_number, message };
+            let words: Vec<&str> = line.split('#').next().unwrap_or("").split_whitespace().collect();
+            let (keyword, value) = match words.as_slice() {
+                [] => continue,
+                [keyword, value] => (*keyword, *value),
+                _ => {
+                    movie.events.push(parse_event(&words).map_err(error)?);
+                    continue;
+                }
+            };
+
+            let number = |radix: u32| u64::from_str_radix(value, radix).map_err(|_| error(format!("bad {} `{}`", keyword, value)));
+            match keyword {
+                "rom" => movie.rom_hash = number(16)?,
+                "seed" => movie.seed = number(10)?,
+                "quirks" => movie.quirks = quirks_from_bits(number(16)?.min(0xFF) as u8),
+                "speed" => movie.speed = number(10)?.max(1),
+                "frames" => movie.frames = number(10)?,
+                _ => return Err(error(format!("unknown setting `{}`", keyword))),
+            }
+            seen.push(keyword);
+        }
+
+        for keyword in ["rom", "seed", "quirks", "speed", "frames"] {
+            if !seen.contains(&keyword) {
+                return Err(ScriptError { line: source.lines().count(), message: format!("missing `{}`", keyword) });
+            }
+        }
+        movie.events.sort_by_key(|event| event.frame);
+        Ok(movie)
+    }
+}
+
+impl Display for Movie {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# chips_8 movie")?;
+        writeln!(f, "rom {:016X}", self.rom_hash)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "quirks {:02X}", quirks_to_bits(self.quirks))?;
+        writeln!(f, "speed {}", self.speed)?;
+        writeln!(f, "frames {}", self.frames)?;
+        for event in &self.events {
+            writeln!(f, "{}", event)?;
+        }
+        Ok(())
+    }
+}
+
+// FNV-1a, only there to catch a movie being played against the wrong ROM
+fn rom_hash(rom: &[u8]) -> u64 {
+    rom.iter().fold(0xCBF2_9CE4_8422_2325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01B3))
+}
+
+#[cfg(test)]
+#[path ="./movie_test.rs"]
+mod movie_test;
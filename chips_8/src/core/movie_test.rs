@@ -0,0 +1,62 @@
+use super::*;
+
+// Waits for a key, draws a random digit at (key, key) and loops
+const ROM: &[u8] = &[0xF0, 0x0A, 0xC1, 0x0F, 0xF1, 0x29, 0xD0, 0x05, 0x12, 0x00];
+
+fn record_session() -> (Movie, Machine) {
+    let mut movie = Movie::new(ROM, 7, Quirks::chip48(), 700);
+    let mut cpu = MyChips8::new_with_quirks(Quirks::chip48());
+    cpu.set_random_source(Box::new(XorShift::new(7)));
+    cpu.load_rom(ROM).unwrap();
+    let mut machine = Machine::new(cpu);
+
+    let presses = [(3, 0x5, true), (5, 0x5, false), (10, 0x2, true), (12, 0x2, false), (20, 0xC, true), (21, 0xC, false)];
+    while machine.frame() < 30 {
+        let frame_now = machine.frame();
+        for &(frame, key, pressed) in presses.iter().filter(|press| press.0 == frame_now) {
+            movie.record(frame, key, pressed);
+            if pressed {
+                machine.cpu.press_key(key);
+            } else {
+                machine.cpu.release_key(key);
+            }
+        }
+        machine.run_frame().unwrap();
+    }
+    movie.frames = machine.frame();
+    (movie, machine)
+}
+
+#[test]
+fn replays_the_recorded_session() {
+    let (movie, recorded) = record_session();
+    let replayed = movie.replay(ROM).unwrap();
+
+    assert_eq!(30, replayed.frame());
+    assert_eq!(recorded.cpu.registers(), replayed.cpu.registers());
+    assert_eq!(recorded.cpu.gfx, replayed.cpu.gfx);
+}
+
+#[test]
+fn round_trips_through_text() {
+    let (movie, _) = record_session();
+    let text = movie.to_string();
+
+    assert!(text.contains("\nseed 7\nquirks 86\nspeed 700\nframes 30\n3 down 5\n5 up 5\n"));
+    assert_eq!(movie, Movie::parse(&text).unwrap());
+}
+
+#[test]
+fn checks_the_rom() {
+    let (movie, _) = record_session();
+
+    assert!(movie.matches_rom(ROM));
+    assert!(!movie.matches_rom(&ROM[..8]));
+}
+
+#[test]
+fn reports_bad_lines() {
+    assert_eq!(2, Movie::parse("seed 1\nspeed fast").unwrap_err().line);
+    assert_eq!(1, Movie::parse("volume 3").unwrap_err().line);
+    assert!(Movie::parse("seed 1\n").unwrap_err().message.contains("missing"));
+}
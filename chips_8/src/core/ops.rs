@@ -1,24 +1,76 @@
 use std::fmt::Debug;
 
-use super::utils::{get_kk, get_nibble, get_nnn, get_x, get_y};
+use super::bus::{Bus, BusObserver, OutOfRange, Ram};
+use super::error::Chip8Error;
+use super::framebuffer::{FrameBuffer, PLANE_1, PLANE_2};
+use super::instruction::Instruction;
+use super::quirks::Quirks;
+use super::random::{RandomSource, XorShift};
+use super::savestate::SaveState;
+use super::trace::{TraceEntry, Tracer};
+
+// What a single step did, so the front end knows whether to redraw or wait
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    Executed,
+    Drew,
+    WaitingForKey,
+    WaitingForVBlank,
+    Exited,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+// Data access made by an instruction, instruction fetches are not included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub address: usize,
+    pub kind: AccessKind,
+}
+
+// Progress of an Fx0A, the VIP only hands over a key once it has been pressed and released again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyWait {
+    Idle,
+    Press { x: u8 },
+    Release { x: u8, key: u8 },
+}
 
 pub struct MyChips8 {
     opcode: u16,          // 2B for storing current opcode
-    memory: [u8; 0x1000],   // Represent 4K block of memory
-    registers: [u16; 0x10], // 15 general purpose and 16th is for carry flag
+    bus: Box<dyn Bus>,    // 4K of memory, 64K for XO-CHIP, unless set_bus put something else there
+    registers: [u8; 0x10], // 15 general purpose and 16th is for carry flag
     i: u16,               // Index register
     pub pc: u16,              // Program Counter
 
-    pub gfx: [u8; 64 * 32], // Stores whether pixel[idx] is on or off (1 or 0)
-    delay_timer: u16,   // Will cound down to 0 when > 0
-    sound_timer: u16,   // Will count down to 0 when > 0
+    pub gfx: FrameBuffer, // Stores whether pixel[idx] is on or off (1 or 0), 64x32 or 128x64
+    delay_timer: u8,   // Will cound down to 0 when > 0
+    sound_timer: u8,   // Will count down to 0 when > 0
+    buzzer: bool,      // Sound timer was running at the last tick, the buzzer sounds for that whole frame
 
     stack: [u16; 0x10], // Storing before JMP, ensure that PC is saved as well
-    sp: u16,          // Stack Pointer
+    sp: u16,          // Stack Pointer, points at the next free slot
     key: [u8; 0x10],    // HEX Based keypad, this is used to store state
+    rpl: [u8; 0x10],    // SUPER-CHIP RPL user flags, saved by Fx75 and restored by Fx85
+    audio_pattern: [u8; 0x10], // XO-CHIP 1 bit audio samples loaded by F002
+    pitch: u8,          // XO-CHIP playback pitch set by Fx3A
+
+    quirks: Quirks,     // Interpreter behaviour the loaded ROM expects
+    rng: Box<dyn RandomSource>, // Feeds Cxkk
+    vblank: bool,       // Set by the front end once per frame, consumed by Dxyn under display_wait
+    key_wait: KeyWait,  // Blocks the CPU while Fx0A is waiting on the keypad
+    track_accesses: bool,           // Only debuggers pay for recording accesses
+    accesses: Vec<MemoryAccess>,    // Data accesses made by the last instruction
+    tracer: Option<Box<dyn Tracer>>, // Sees every executed instruction when set
+    written: Option<(usize, usize)>, // Memory changed since the block cache last looked, first and last address
+    out_of_range: OutOfRange,       // What accesses past the end of memory do
+    observers: Vec<Box<dyn BusObserver>>, // See every data access, in the order they were added
 
     // event flags -- temp
-    pub wait: bool, // Marker for event loop to wait for next key
     pub draw: bool, // Marker for event loop to draw 
 }
 
@@ -41,6 +93,34 @@ const FONT_SET: [u8; 80] = [
         0xF0, 0x80, 0xF0, 0x80, 0x80  // F
     ];
 const FONT_BEGIN: usize = 0x50;
+const FONT_HEIGHT: usize = 5;
+
+// SUPER-CHIP 8x10 digits, loaded straight after the small font
+const BIG_FONT_SET: [u8; 160] = [
+        0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+        0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+        0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+        0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+        0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+        0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+        0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+        0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+        0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+        0x18, 0x3C, 0x66, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+        0xFC, 0xFE, 0xC3, 0xC3, 0xFE, 0xFE, 0xC3, 0xC3, 0xFE, 0xFC, // B
+        0x3C, 0x7E, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0x7E, 0x3C, // C
+        0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0  // F
+    ];
+const BIG_FONT_BEGIN: usize = FONT_BEGIN + 80;
+const BIG_FONT_HEIGHT: usize = 10;
+
+pub const PROGRAM_START: usize = 0x200;
+pub const MEMORY_SIZE: usize = 0x1000;
+pub const XO_CHIP_MEMORY_SIZE: usize = 0x10000;
+const DEFAULT_PITCH: u8 = 64;
 
 impl Debug for MyChips8 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -55,20 +135,37 @@ impl Debug for MyChips8 {
 
 impl MyChips8 {
     pub fn new() -> Self {
+        MyChips8::new_with_quirks(Quirks::default())
+    }
+
+    pub fn new_with_quirks(quirks: Quirks) -> Self {
         let mut my_chips_8 = MyChips8 {
             opcode: 0x0,
-            memory: [0; 4096],
+            bus: Box::new(Ram::new(if quirks.xo_chip { XO_CHIP_MEMORY_SIZE } else { MEMORY_SIZE })),
             registers: [0; 0x10],
             i: 0x0,
-            pc: 0x200,
-            gfx: [0; 64 * 32],
+            pc: PROGRAM_START as u16,
+            gfx: FrameBuffer::new(),
             delay_timer: 0,
             sound_timer: 0,
+            buzzer: false,
 
             stack: [0x0; 0x10],
             sp: 0x0,
             key: [0; 0x10],
-            wait: false,
+            rpl: [0; 0x10],
+            audio_pattern: [0; 0x10],
+            pitch: DEFAULT_PITCH,
+            quirks,
+            rng: Box::new(XorShift::from_time()),
+            vblank: true,
+            key_wait: KeyWait::Idle,
+            track_accesses: false,
+            accesses: Vec::new(),
+            tracer: None,
+            written: None,
+            out_of_range: OutOfRange::default(),
+            observers: Vec::new(),
             draw: false
         };
 
@@ -78,347 +175,793 @@ impl MyChips8 {
         my_chips_8
     }
 
-    // Load bytes into memory
-    pub fn load_rom(&mut self, chip_8_program: &[u8]) {
-        let program_len = chip_8_program.len();
-        (0..program_len).for_each(|idx| {
-            self.memory[0x200 + idx] = chip_8_program[idx];
-        })
+    // Load bytes into memory at 0x200, programs that do not fit are rejected rather than truncated
+    pub fn load_rom(&mut self, chip_8_program: &[u8]) -> Result<(), Chip8Error> {
+        let capacity = self.memory().len().saturating_sub(PROGRAM_START);
+        if chip_8_program.len() > capacity {
+            return Err(Chip8Error::RomTooLarge { size: chip_8_program.len(), capacity });
+        }
+        for (idx, byte) in chip_8_program.iter().enumerate() {
+            self.bus.write(PROGRAM_START + idx, *byte);
+        }
+        self.mark_written(PROGRAM_START, PROGRAM_START + chip_8_program.len());
+        Ok(())
     }
 
     // Load fontsets
     fn load_font_set(&mut self) {
-        (FONT_BEGIN..0x9F).enumerate().for_each(|(count, idx)| {
-            self.memory[idx] = FONT_SET[count];
+        (FONT_BEGIN..FONT_BEGIN + FONT_SET.len()).enumerate().for_each(|(count, idx)| {
+            self.bus.write(idx, FONT_SET[count]);
+        });
+        (BIG_FONT_BEGIN..BIG_FONT_BEGIN + BIG_FONT_SET.len()).enumerate().for_each(|(count, idx)| {
+            self.bus.write(idx, BIG_FONT_SET[count]);
         });
     }
 
-    // Reset Timers
-    // }
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn audio_pattern(&self) -> &[u8; 0x10] {
+        &self.audio_pattern
+    }
+
+    // Rate the XO-CHIP pattern buffer is played back at, 4000Hz at the default pitch of 64
+    pub fn playback_rate(&self) -> f64 {
+        4000.0 * 2f64.powf((self.pitch as f64 - 64.0) / 48.0)
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    // Whether the frame that just ended should be heard, an ST of n sounds for n frames
+    pub fn is_buzzer_on(&self) -> bool {
+        self.buzzer
+    }
+
+    // Signals the start of a new frame to the display_wait quirk
+    pub fn vblank(&mut self) {
+        self.vblank = true;
+    }
+
+    // Keys outside of 0x0-0xF are ignored
+    pub fn press_key(&mut self, key: u8) {
+        if let Some(state) = self.key.get_mut(key as usize) {
+            *state = 1;
+            if let KeyWait::Press { x } = self.key_wait {
+                self.key_wait = KeyWait::Release { x, key };
+            }
+        }
+    }
+
+    pub fn release_key(&mut self, key: u8) {
+        if let Some(state) = self.key.get_mut(key as usize) {
+            *state = 0;
+            if let KeyWait::Release { x, key: waiting } = self.key_wait {
+                if waiting == key {
+                    self.set_register(x, key);
+                    self.key_wait = KeyWait::Idle;
+                }
+            }
+        }
+    }
+
+    pub fn registers(&self) -> &[u8; 0x10] {
+        &self.registers
+    }
+
+    pub fn i(&self) -> u16 {
+        self.i
+    }
+
+    pub fn opcode(&self) -> u16 {
+        self.opcode
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        self.bus.bytes()
+    }
+
+    // Swaps the memory behind the CPU, for memory mapped devices or another size, the fonts are loaded
+    // into it and a ROM has to be loaded afterwards
+    pub fn set_bus(&mut self, bus: Box<dyn Bus>) -> Result<(), Chip8Error> {
+        let size = bus.bytes().len();
+        if size <= PROGRAM_START {
+            return Err(Chip8Error::MemoryTooSmall { size });
+        }
+        self.bus = bus;
+        self.load_font_set();
+        self.mark_written(0, size - 1);
+        Ok(())
+    }
+
+    pub fn out_of_range(&self) -> OutOfRange {
+        self.out_of_range
+    }
+
+    pub fn set_out_of_range(&mut self, out_of_range: OutOfRange) {
+        self.out_of_range = out_of_range;
+    }
+
+    // Observers see data accesses in the order they were added, each one gets the value the last returned
+    pub fn add_observer(&mut self, observer: Box<dyn BusObserver>) {
+        self.observers.push(observer);
+    }
+
+    pub fn take_observers(&mut self) -> Vec<Box<dyn BusObserver>> {
+        std::mem::take(&mut self.observers)
+    }
+
+    // Direct state edits for debuggers, these bypass the usual instruction side effects
+    pub fn set_v(&mut self, x: u8, value: u8) {
+        self.set_register(x & 0xF, value);
+    }
+
+    pub fn set_registers(&mut self, registers: [u8; 0x10]) {
+        self.registers = registers;
+    }
+
+    pub fn set_i(&mut self, value: u16) {
+        self.i = value;
+    }
+
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.delay_timer = value;
+    }
+
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.sound_timer = value;
+    }
+
+    // False when the address is past the end of memory
+    pub fn poke(&mut self, address: usize, value: u8) -> bool {
+        if address >= self.memory().len() {
+            return false;
+        }
+        self.bus.write(address, value);
+        self.mark_written(address, address);
+        true
+    }
+
+    // Return addresses of the active calls, innermost last
+    pub fn call_stack(&self) -> &[u16] {
+        &self.stack[0..self.sp as usize]
+    }
+
+    // Records the memory accesses of every instruction, see last_accesses()
+    pub fn set_track_accesses(&mut self, track_accesses: bool) {
+        self.track_accesses = track_accesses;
+        self.accesses.clear();
+    }
+
+    pub fn last_accesses(&self) -> &[MemoryAccess] {
+        &self.accesses
+    }
+
+    pub fn is_key_pressed(&self, key: u8) -> bool {
+        self.key.get(key as usize) == Some(&1)
+    }
+
+    pub fn is_waiting_for_key(&self) -> bool {
+        self.key_wait != KeyWait::Idle
+    }
+
+    // Captures everything but the random source, restore() puts it back
+    pub fn snapshot(&self) -> SaveState {
+        SaveState {
+            quirks: self.quirks,
+            opcode: self.opcode,
+            pc: self.pc,
+            i: self.i,
+            sp: self.sp,
+            registers: self.registers,
+            stack: self.stack,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            buzzer: self.buzzer,
+            key: self.key,
+            rpl: self.rpl,
+            audio_pattern: self.audio_pattern,
+            pitch: self.pitch,
+            vblank: self.vblank,
+            key_wait: self.key_wait,
+            gfx: self.gfx.clone(),
+            memory: self.memory().to_vec(),
+        }
+    }
+
+    pub fn restore(&mut self, state: &SaveState) {
+        self.quirks = state.quirks;
+        self.opcode = state.opcode;
+        self.pc = state.pc;
+        self.i = state.i;
+        self.sp = state.sp;
+        self.registers = state.registers;
+        self.stack = state.stack;
+        self.delay_timer = state.delay_timer;
+        self.sound_timer = state.sound_timer;
+        self.buzzer = state.buzzer;
+        self.key = state.key;
+        self.rpl = state.rpl;
+        self.audio_pattern = state.audio_pattern;
+        self.pitch = state.pitch;
+        self.vblank = state.vblank;
+        self.key_wait = state.key_wait;
+        self.gfx = state.gfx.clone();
+        // A bus of the same size is written through so it stays in place, anything else becomes plain memory
+        if state.memory.len() == self.memory().len() {
+            for (address, value) in state.memory.iter().enumerate() {
+                if self.bus.bytes()[address] != *value {
+                    self.bus.write(address, *value);
+                }
+            }
+        } else {
+            self.bus = Box::new(Ram::from(state.memory.clone()));
+        }
+        self.mark_written(0, self.memory().len() - 1);
+        self.draw = true;
+    }
+
+    // Swap in a seeded or scripted source for reproducible runs
+    pub fn set_random_source(&mut self, rng: Box<dyn RandomSource>) {
+        self.rng = rng;
+    }
 
+    // Hands every executed instruction to the tracer, see trace::TraceEntry for what it gets
+    pub fn set_tracer(&mut self, tracer: Box<dyn Tracer>) {
+        self.tracer = Some(tracer);
+    }
 
-    // Utilizing a clamped LCG, not sure if this is random enough :/
-    fn get_rand(&self, state: u8) -> u8 {
-        return ((state % 10) * ((((self.pc & 0x00FF) >> 2) as u8) % 122) + 1) % 255;
+    pub fn take_tracer(&mut self) -> Option<Box<dyn Tracer>> {
+        self.tracer.take()
     }
 
-    fn get_register_value(&self, index: u16) -> u16 {
-        return self.registers[index as usize];
+    fn get_register_value(&self, index: u8) -> u8 {
+        self.registers[index as usize]
     }
 
-    fn set_register(&mut self, index: u16, value: u16) {
+    fn set_register(&mut self, index: u8, value: u8) {
         self.registers[index as usize] = value;
     }
 
-    pub fn enumlate_cycle(&mut self) {
+    // Arithmetic results land in Vx first and the flag second, so VF as an operand gets clobbered by the flag
+    fn set_register_with_flag(&mut self, index: u8, value: u8, flag: bool) {
+        self.set_register(index, value);
+        self.set_register(0xF, flag as u8);
+    }
+
+    // Register indices from x to y inclusive, counting down when y < x
+    fn register_range(x: u8, y: u8) -> Box<dyn Iterator<Item = usize>> {
+        if x <= y {
+            Box::new(x as usize..=y as usize)
+        } else {
+            Box::new((y as usize..=x as usize).rev())
+        }
+    }
+
+    fn reset_flag_after_logic(&mut self) {
+        if self.quirks.logic_resets_vf {
+            self.set_register(0xF, 0x0);
+        }
+    }
+
+    fn get_shift_source(&self, x: u8, y: u8) -> u8 {
+        if self.quirks.shift_uses_vy {
+            self.get_register_value(y)
+        } else {
+            self.get_register_value(x)
+        }
+    }
+
+    fn increment_i_after_load_store(&mut self, x: u8) {
+        if self.quirks.load_store_increments_i {
+            let past_last = if self.quirks.load_store_i_on_last { 0 } else { 1 };
+            self.i = self.i.wrapping_add(x as u16 + past_last);
+        }
+    }
+
+    // Skipping over F000 nnnn has to step over the address word as well
+    fn skip_next(&mut self) {
+        let pc = self.pc as usize;
+        // Without the xo_chip quirk F000 is an unknown opcode, and like any other it is two bytes long
+        let next_is_long_load = self.memory().get(pc) == Some(&0xF0) && self.memory().get(pc + 1) == Some(&0x00);
+        self.pc = self.pc.wrapping_add(if self.quirks.xo_chip && next_is_long_load { 4 } else { 2 });
+    }
+
+    fn push_stack(&mut self, pc: u16, value: u16) -> Result<(), Chip8Error> {
+        if self.sp as usize >= self.stack.len() {
+            return Err(Chip8Error::StackOverflow { pc });
+        }
+        self.stack[self.sp as usize] = value;
+        self.sp += 1;
+        Ok(())
+    }
+
+    fn pop_stack(&mut self, pc: u16) -> Result<u16, Chip8Error> {
+        if self.sp == 0 {
+            return Err(Chip8Error::StackUnderflow { pc });
+        }
+        self.sp -= 1;
+        Ok(self.stack[self.sp as usize])
+    }
+
+    fn record_access(&mut self, address: usize, kind: AccessKind) {
+        if self.track_accesses {
+            self.accesses.push(MemoryAccess { address, kind });
+        }
+    }
+
+    // Where an access lands under the out of range policy, None when it goes nowhere
+    fn map_address(&self, pc: u16, address: usize) -> Result<Option<usize>, Chip8Error> {
+        let size = self.memory().len();
+        if address < size {
+            return Ok(Some(address));
+        }
+        match self.out_of_range {
+            OutOfRange::Wrap => Ok(Some(address % size)),
+            OutOfRange::Fault => Err(Chip8Error::MemoryOutOfBounds { pc, address }),
+            OutOfRange::Zero => Ok(None),
+        }
+    }
+
+    // Data access through the bus and the observers, pc is the address of the instruction doing the access
+    fn read_memory(&mut self, pc: u16, address: usize) -> Result<u8, Chip8Error> {
+        let mapped = self.map_address(pc, address);
+        self.record_access(mapped.ok().flatten().unwrap_or(address), AccessKind::Read);
+        let address = match mapped? {
+            Some(address) => address,
+            None => return Ok(0),
+        };
+        let mut value = self.bus.read(address);
+        for observer in self.observers.iter_mut() {
+            value = observer.read(pc, address, value);
+        }
+        Ok(value)
+    }
+
+    // Reads that belong to the instruction stream itself, straight from bytes() like the block cache
+    // decodes them, neither the bus nor the observers see these
+    fn fetch_memory(&self, pc: u16, address: usize) -> Result<u8, Chip8Error> {
+        if let Some(value) = self.memory().get(address) {
+            return Ok(*value);
+        }
+        match self.map_address(pc, address)? {
+            Some(address) => Ok(self.memory()[address]),
+            None => Ok(0),
+        }
+    }
+
+    fn write_memory(&mut self, pc: u16, address: usize, value: u8) -> Result<(), Chip8Error> {
+        let mapped = self.map_address(pc, address);
+        self.record_access(mapped.ok().flatten().unwrap_or(address), AccessKind::Write);
+        let address = match mapped? {
+            Some(address) => address,
+            None => return Ok(()),
+        };
+        let mut value = value;
+        for observer in self.observers.iter_mut() {
+            value = observer.write(pc, address, value);
+        }
+        self.bus.write(address, value);
+        self.mark_written(address, address);
+        Ok(())
+    }
+
+    fn mark_written(&mut self, first: usize, last: usize) {
+        self.written = Some(match self.written {
+            Some((low, high)) => (low.min(first), high.max(last)),
+            None => (first, last),
+        });
+    }
+
+    // Range of memory changed since the last call, anything decoded from it is stale
+    pub(crate) fn take_written(&mut self) -> Option<(usize, usize)> {
+        self.written.take()
+    }
+
+    pub(crate) fn has_written(&self) -> bool {
+        self.written.is_some()
+    }
+
+    pub fn enumlate_cycle(&mut self) -> Result<StepOutcome, Chip8Error> {
+        self.accesses.clear();
+
+        // Fx0A already moved PC along, nothing runs until the key comes back up
+        if self.is_waiting_for_key() {
+            return Ok(StepOutcome::WaitingForKey);
+        }
+
         // Fetch
-        self.opcode = (self.memory[self.pc as usize] as u16) << 8
-            | self.memory[(self.pc + 1) as usize] as u16;
-        self.pc += 2;
-
-        match self.opcode & 0xF000 {
-            0x0000 => {
-                match self.opcode & 0x00FF {
-                    // 0x00E0 - CLS - Clears the screen
-                    0x00E0 => {
-                        // Compiles to memset so fastest possible solution
-                        self.gfx.iter_mut().for_each(|entry| *entry = 0);
-                    }
-                    // 0x00EE - RET - Returns from subroutine
-                    0x00EE => {
-                        self.pc = self.stack[0];
-                    }
-                    // 0x0nnn - SYS addr - no-op this is ignored on modern compilers
-                    _ => {}
-                }
+        let pc = self.pc;
+        self.opcode = (self.fetch_memory(pc, pc as usize)? as u16) << 8
+            | self.fetch_memory(pc, pc as usize + 1)? as u16;
+
+        // Decode
+        let instruction = match Instruction::decode(self.opcode) {
+            Ok(instruction) => instruction,
+            Err(_) => return Err(Chip8Error::UnknownOpcode { pc, opcode: self.opcode }),
+        };
+
+        self.run_decoded(self.opcode, instruction)
+    }
+
+    // Runs an instruction that was already fetched and decoded from PC, the block cache enters here
+    pub(crate) fn run_decoded(&mut self, opcode: u16, instruction: Instruction) -> Result<StepOutcome, Chip8Error> {
+        self.accesses.clear();
+        if self.is_waiting_for_key() {
+            return Ok(StepOutcome::WaitingForKey);
+        }
+        let pc = self.pc;
+        self.opcode = opcode;
+        if instruction.is_xo_chip() && !self.quirks.xo_chip {
+            return Err(Chip8Error::UnknownOpcode { pc, opcode });
+        }
+        self.pc = self.pc.wrapping_add(2);
+
+        // Execute
+        if self.tracer.is_none() {
+            return self.execute(pc, instruction);
+        }
+        let before = (self.registers, self.i);
+        let outcome = self.execute(pc, instruction)?;
+        // A draw held back for the vertical blank runs again next frame, it is traced then
+        if outcome != StepOutcome::WaitingForVBlank {
+            let entry = TraceEntry::new(pc, self.opcode, instruction, before, (self.registers, self.i));
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.trace(&entry);
+            }
+        }
+        Ok(outcome)
+    }
+
+    // Counts both timers down by one, meant to be called at 60Hz independent of the instruction rate
+    pub fn tick_timers(&mut self) {
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+        self.buzzer = self.sound_timer > 0;
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
+    }
+
+    fn execute(&mut self, pc: u16, instruction: Instruction) -> Result<StepOutcome, Chip8Error> {
+        match instruction {
+            // 0x00E0 - CLS - Clears the screen
+            Instruction::Cls => {
+                self.gfx.clear();
+                self.draw = true;
+                return Ok(StepOutcome::Drew);
+            }
+            // 0x00EE - RET - Returns from subroutine
+            Instruction::Ret => {
+                self.pc = self.pop_stack(pc)?;
+            }
+            // 0x0nnn - SYS addr - no-op this is ignored on modern compilers
+            Instruction::Sys { .. } => {}
+
+            // 0x00Cn - SCD nibble - Scroll the display down n lines
+            Instruction::Scd { n } => {
+                self.gfx.scroll_down(n as usize);
+                self.draw = true;
+                return Ok(StepOutcome::Drew);
+            }
+            // 0x00Dn - SCU nibble - Scroll the display up n lines
+            Instruction::Scu { n } => {
+                self.gfx.scroll_up(n as usize);
+                self.draw = true;
+                return Ok(StepOutcome::Drew);
+            }
+            // 0x00FB - SCR - Scroll the display right 4 pixels
+            Instruction::Scr => {
+                self.gfx.scroll_right(4);
+                self.draw = true;
+                return Ok(StepOutcome::Drew);
+            }
+            // 0x00FC - SCL - Scroll the display left 4 pixels
+            Instruction::Scl => {
+                self.gfx.scroll_left(4);
+                self.draw = true;
+                return Ok(StepOutcome::Drew);
+            }
+            // 0x00FD - EXIT - Stop the interpreter, PC stays on EXIT so stepping again exits again
+            Instruction::Exit => {
+                self.pc = pc;
+                return Ok(StepOutcome::Exited);
+            }
+            // 0x00FE - LOW - Switch to 64x32
+            Instruction::Low => {
+                self.gfx.set_hires(false);
+                self.draw = true;
+                return Ok(StepOutcome::Drew);
+            }
+            // 0x00FF - HIGH - Switch to 128x64
+            Instruction::High => {
+                self.gfx.set_hires(true);
+                self.draw = true;
+                return Ok(StepOutcome::Drew);
             }
 
             // 0x1nnn - JP addr - JMP to addr nnn
-            0x1000 => {
-                self.i = get_nnn(&self.opcode);
-                self.pc = get_nnn(&self.opcode);
+            Instruction::Jp { nnn } => {
+                self.pc = nnn;
             }
 
             // 0x2nnn - CALL addr - Calls subroutine at address nnn
-            0x2000 => {
-                self.sp += 1;
-                self.stack[self.sp as usize] = self.pc;
-                self.pc = get_nnn(&self.opcode);
+            Instruction::Call { nnn } => {
+                self.push_stack(pc, self.pc)?;
+                self.pc = nnn;
             }
 
             // 0x3xkk - SE Vx, byte - Skip next if Vx == kk
-            0x3000 => {
-                if self.get_register_value(get_x(&self.opcode)) == get_kk(&self.opcode) {
-                    self.pc += 2;
+            Instruction::SeByte { x, kk } => {
+                if self.get_register_value(x) == kk {
+                    self.skip_next();
                 }
             }
 
             // 0x4xkk - SNE Vx, byte - Skip next if Vx != kk
-            0x4000 => {
-                if self.get_register_value(get_x(&self.opcode)) != get_kk(&self.opcode) {
-                    self.pc += 2;
+            Instruction::SneByte { x, kk } => {
+                if self.get_register_value(x) != kk {
+                    self.skip_next();
                 }
             }
 
             // 0x5xy0 - SE Vx, Vy - Skip next if Vx == Vy
-            0x5000 => {
-                if self.get_register_value(get_x(&self.opcode)) == self.get_register_value(get_y(&self.opcode)) {
-                    self.pc += 2;
+            Instruction::SeReg { x, y } => {
+                if self.get_register_value(x) == self.get_register_value(y) {
+                    self.skip_next();
                 }
             }
 
-            // 0x6xkk - LD Vx, byte - Load kk into Vx
-            0x6000 => {
-                let (x, kk) = (get_x(&self.opcode), get_kk(&self.opcode));
-                // println!("Op: {:X} | Vx {:X} | kk {:X}", &self.opcode, &vx, &kk);
-                self.set_register(x, kk);
-            }
-
-            // 0x7xkk - ADD Vx, byte - Add value of byte
-            0x7000 => {
-                self.set_register(
-                    get_x(&self.opcode),
-                    self.get_register_value(get_x(&self.opcode)) + get_kk(&self.opcode),
-                );
-            }
-
-            0x8000 => match self.opcode & 0x000F {
-                // LD Vx, Vy
-                0x0000 => {
-                    self.set_register(get_x(&self.opcode), self.get_register_value(get_y(&self.opcode)));
-                }
-                // OR Vx, Vy
-                0x0001 => {
-                    self.set_register(get_x(&self.opcode), get_x(&self.opcode) | get_y(&self.opcode));
-                }
-                // AND Vx, Vy
-                0x0002 => {
-                    self.set_register(get_x(&self.opcode), get_x(&self.opcode) & get_y(&self.opcode));
-                }
-                // XOR Vx, Vy
-                0x0003 => {
-                    self.set_register(get_x(&self.opcode), get_x(&self.opcode) ^ get_y(&self.opcode));
-                }
-                // ADD Vx, Vy - Add Vx to Vy
-                0x0004 => {
-                    if self.get_register_value(get_y(&self.opcode))
-                        > (0xFF - self.get_register_value(get_x(&self.opcode)))
-                    {
-                        self.registers[0xF] = 0x1;
-                    } else {
-                        self.registers[0xF] = 0x0;
-                    }
-
-                    self.set_register(
-                        get_x(&self.opcode),
-                        self.get_register_value(get_x(&self.opcode))
-                            + self.get_register_value(get_y(&self.opcode)),
-                    );
+            // 0x5xy2 - SAVE Vx, Vy - Store Vx through Vy at I, in either direction, I is left alone
+            Instruction::SaveRange { x, y } => {
+                for (offset, register) in MyChips8::register_range(x, y).enumerate() {
+                    self.write_memory(pc, self.i as usize + offset, self.registers[register])?;
                 }
-                // SUB Vx, Vy - Subtract Vx from Vy
-                0x0005 => {
-                    if self.get_register_value(get_x(&self.opcode))
-                        > self.get_register_value(get_y(&self.opcode))
-                    {
-                        self.set_register(0xF, 0x1);
-                    } else {
-                        self.set_register(0xF, 0x0);
-                    }
+            }
 
-                    self.set_register(
-                        get_x(&self.opcode),
-                        self.get_register_value(get_x(&self.opcode))
-                            - self.get_register_value(get_y(&self.opcode)),
-                    );
+            // 0x5xy3 - LOAD Vx, Vy - Load Vx through Vy from I, in either direction, I is left alone
+            Instruction::LoadRange { x, y } => {
+                for (offset, register) in MyChips8::register_range(x, y).enumerate() {
+                    self.registers[register] = self.read_memory(pc, self.i as usize + offset)?;
                 }
-                // SHR Vx - RHS 1
-                0x0006 => {
-                    // Checking LSB of Vx
-                    if (self.get_register_value(get_x(&self.opcode)) & 0x1) == 1 {
-                        self.set_register(0xF, 0x1);
-                    } else {
-                        self.set_register(0xF, 0x0);
-                    }
+            }
 
-                    self.set_register(get_x(&self.opcode), self.get_register_value(get_x(&self.opcode)) >> 1);
-                }
-                // SUBN Vx, Vy - Subtract Vy from Vx
-                0x0007 => {
-                    if self.get_register_value(get_y(&self.opcode))
-                        > self.get_register_value(get_x(&self.opcode))
-                    {
-                        self.set_register(0xF, 0x1);
-                    } else {
-                        self.set_register(0xF, 0x0);
-                    }
+            // 0x6xkk - LD Vx, byte - Load kk into Vx
+            Instruction::LdByte { x, kk } => {
+                self.set_register(x, kk);
+            }
 
-                    self.set_register(
-                        get_x(&self.opcode),
-                        self.get_register_value(get_y(&self.opcode))
-                            - self.get_register_value(get_x(&self.opcode)),
-                    );                }
-                // SHL Vx - LHS 1
-                0x000E => {
-                    if (self.get_register_value(get_x(&self.opcode)) & 0x8) == 1 {
-                        self.set_register(0xF, 0x1);
-                    } else {
-                        self.set_register(0xF, 0x0);
-                    }
+            // 0x7xkk - ADD Vx, byte - Add value of byte, no carry flag
+            Instruction::AddByte { x, kk } => {
+                self.set_register(x, self.get_register_value(x).wrapping_add(kk));
+            }
 
-                    self.set_register(get_x(&self.opcode), self.get_register_value(get_x(&self.opcode)) << 1);
-                }
-                _ => panic!("Unsupported opcode detected: {:X}", self.opcode),
-            },
+            // LD Vx, Vy
+            Instruction::LdReg { x, y } => {
+                self.set_register(x, self.get_register_value(y));
+            }
+            // OR Vx, Vy
+            Instruction::Or { x, y } => {
+                self.set_register(x, self.get_register_value(x) | self.get_register_value(y));
+                self.reset_flag_after_logic();
+            }
+            // AND Vx, Vy
+            Instruction::And { x, y } => {
+                self.set_register(x, self.get_register_value(x) & self.get_register_value(y));
+                self.reset_flag_after_logic();
+            }
+            // XOR Vx, Vy
+            Instruction::Xor { x, y } => {
+                self.set_register(x, self.get_register_value(x) ^ self.get_register_value(y));
+                self.reset_flag_after_logic();
+            }
+            // ADD Vx, Vy - Add Vy to Vx, VF = carry
+            Instruction::AddReg { x, y } => {
+                let (result, carry) = self.get_register_value(x).overflowing_add(self.get_register_value(y));
+                self.set_register_with_flag(x, result, carry);
+            }
+            // SUB Vx, Vy - Subtract Vy from Vx, VF = NOT borrow
+            Instruction::Sub { x, y } => {
+                let (result, borrow) = self.get_register_value(x).overflowing_sub(self.get_register_value(y));
+                self.set_register_with_flag(x, result, !borrow);
+            }
+            // SHR Vx - RHS 1, VF = shifted out bit
+            Instruction::Shr { x, y } => {
+                let v_x = self.get_shift_source(x, y);
+                self.set_register_with_flag(x, v_x >> 1, v_x & 0x1 == 0x1);
+            }
+            // SUBN Vx, Vy - Subtract Vx from Vy, VF = NOT borrow
+            Instruction::Subn { x, y } => {
+                let (result, borrow) = self.get_register_value(y).overflowing_sub(self.get_register_value(x));
+                self.set_register_with_flag(x, result, !borrow);
+            }
+            // SHL Vx - LHS 1, VF = shifted out bit
+            Instruction::Shl { x, y } => {
+                let v_x = self.get_shift_source(x, y);
+                self.set_register_with_flag(x, v_x << 1, v_x & 0x80 == 0x80);
+            }
 
             // 0x9xy0 - SNE Vx, Vy - Skip next instruction if Vx != Vy
-            0x9000 => {
-                if self.get_register_value(get_x(&self.opcode)) != self.get_register_value(get_y(&self.opcode))
-                {
-                    self.pc += 2;
+            Instruction::SneReg { x, y } => {
+                if self.get_register_value(x) != self.get_register_value(y) {
+                    self.skip_next();
                 }
             }
 
             // 0xAnnn - LD I, addr - Sets I to the address nnn
-            0xA000 => {
-                self.i = get_nnn(&self.opcode);
-                // println!("i: {:X}", self.i);
+            Instruction::LdI { nnn } => {
+                self.i = nnn;
             }
 
-            // Bnnn - JP V0, addr - Jump to location addr
-            0xB000 => {
-                self.pc = get_nnn(&self.opcode) + self.get_register_value(0x0);
+            // Bnnn - JP V0, addr - Jump to location addr, BXNN jumps to XNN + Vx under jump_uses_vx
+            Instruction::JpV0 { nnn } => {
+                let offset_register = if self.quirks.jump_uses_vx { (nnn >> 8) as u8 } else { 0x0 };
+                self.pc = nnn + self.get_register_value(offset_register) as u16;
             }
 
             // Cxkk - RND Vx, byte - Random number from 0 to 255, then &'d w/ byte which is stored into Vx
-            0xC000 => {
-                self.set_register(
-                    get_x(&self.opcode),
-                    self.get_rand((get_kk(&self.opcode) as u8) & get_kk(&self.opcode) as u8) as u16,
-                );
+            Instruction::Rnd { x, kk } => {
+                let random = self.rng.next_byte();
+                self.set_register(x, random & kk);
             }
 
             // 0xDxyn - DRW Vx, Vy, nibble - Draw n-byte sprite starting at mem loc I @ (vx, Vy), set VF = collision
-            0xD000 => {
-                self.registers[0xF] = 0x0;
-                for idx in 0..get_nibble(&self.opcode)
-                {
-                    let v_x = self.registers[get_x(&self.opcode) as usize];
-                    let v_y = self.registers[get_y(&self.opcode) as usize];
-
-                    let row = (v_y as usize + idx as usize) % 32;
-                    let mut sprite = self.memory[(self.i + idx) as usize];
-
-                    for bit_idx in 0..(sprite.count_ones() + sprite.count_zeros()) {
-                        let bit_value = (sprite & 0x80) >> 7;
-                        let col = ((v_x as u32 + bit_idx) % 64) as usize;
-                        let offset = row * 64 + col;
-
-                        if bit_value == 0x1 {
-                            if self.gfx[offset] != 0x0 {
-                                self.gfx[offset] = 0x0;
-                                self.registers[0xF] = 0x1;
-                            } else {
-                                self.gfx[offset] = 0x1;
+            Instruction::Drw { x, y, n } => {
+                if self.quirks.display_wait && !self.vblank {
+                    // Retry this instruction once the next frame starts
+                    self.pc = pc;
+                    return Ok(StepOutcome::WaitingForVBlank);
+                }
+                self.vblank = false;
+
+                // The starting position always wraps, the sprite itself wraps or clips
+                let (width, height) = (self.gfx.width(), self.gfx.height());
+                let v_x = self.get_register_value(x) as usize % width;
+                let v_y = self.get_register_value(y) as usize % height;
+                let mut collision = false;
+
+                // Dxy0 is a SUPER-CHIP 16x16 sprite stored as two bytes per row
+                let (rows, bytes_per_row) = if n == 0 { (16, 2) } else { (n as usize, 1) };
+
+                // Each selected XO-CHIP plane takes the next sprite worth of data from I
+                let mut sprite_address = self.i as usize;
+                let selected_planes = self.gfx.selected_planes();
+                for plane in [PLANE_1, PLANE_2].iter().filter(|&&plane| selected_planes & plane != 0) {
+                    for idx in 0..rows {
+                        if !self.quirks.sprites_wrap && v_y + idx >= height {
+                            break;
+                        }
+                        let row = (v_y + idx) % height;
+                        let mut sprite = 0_u16;
+                        for byte_idx in 0..bytes_per_row {
+                            let byte = self.read_memory(pc, sprite_address + idx * bytes_per_row + byte_idx)?;
+                            sprite |= (byte as u16) << (8 * (1 - byte_idx));
+                        }
+
+                        for bit_idx in 0..bytes_per_row * 8 {
+                            if !self.quirks.sprites_wrap && v_x + bit_idx >= width {
+                                break;
                             }
+                            let bit_value = (sprite & 0x8000) >> 15;
+                            let col = (v_x + bit_idx) % width;
+
+                            if bit_value == 0x1 && self.gfx.toggle(col, row, *plane) {
+                                collision = true;
+                            }
+                            sprite <<= 1;
                         }
-                        sprite = sprite << 1;
                     }
+                    sprite_address += rows * bytes_per_row;
                 }
+                self.set_register(0xF, collision as u8);
                 self.draw = true;
+                return Ok(StepOutcome::Drew);
             }
 
-            0xE000 => match self.opcode & 0x00FF {
-                // 0xEx9E - SKP Vx - Skip next instruction if key pressed
-                0x009E => {
-                    if self.key[get_x(&self.opcode) as usize] == 1 {
-                        self.pc += 2;
-                    }
+            // 0xEx9E - SKP Vx - Skip next instruction if key pressed
+            Instruction::Skp { x } => {
+                if self.key[(self.get_register_value(x) & 0xF) as usize] == 1 {
+                    self.skip_next();
                 }
+            }
 
-                // 0xExA1 - SKNP Vx - Skip next instruction if key not pressed
-                0x00A1 => {
-                    if self.key[get_x(&self.opcode) as usize] == 0 {
-                        self.pc += 2;
-                    }
+            // 0xExA1 - SKNP Vx - Skip next instruction if key not pressed
+            Instruction::Sknp { x } => {
+                if self.key[(self.get_register_value(x) & 0xF) as usize] == 0 {
+                    self.skip_next();
                 }
-                _ => panic!("Unsupported opcode detected: {:X}", self.opcode),
-            },
+            }
 
-            0xF000 => match self.opcode & 0x00FF {
-                // 0xFx07 - LD Vx, DT - Loading Delay Timer into Vx
-                0x0007 => {
-                    self.set_register(get_x(&self.opcode), self.delay_timer);
-                }
-                // 0xFx0A - LD Vx, K - Stop execution till key press
-                0x000A => {
-                    println!("Wait set");
-                    self.wait = true;
-                }
-                // 0xFx15 - LD DT, Vx - Load Vx into DT
-                0x0015 => {
-                    self.delay_timer = self.get_register_value(get_x(&self.opcode));
-                }
-                // 0xFx18 - LD ST, Vx - Set sound timer to Vx
-                0x0018 => {
-                    self.sound_timer = self.get_register_value(get_x(&self.opcode));
-                }
-                // 0xFx1E - ADD I, Vx - Add I and Vx then store in I
-                0x001E => {
-                    self.i = self.i + self.get_register_value(get_x(&self.opcode));
-                }
-                // 0xFx29 - LD F, Vx - Set I = location of sprite for digit Vx
-                0x0029 => {
-                    self.i = (FONT_BEGIN as u16 + self.get_register_value(get_x(&self.opcode))) as u16;
-                    println!("Font Pointer: {:X} | Opcode: {:X}", get_x(&self.opcode), self.opcode);
-                    self.wait = true;
-                }
-                // 0xFx33 - LD F, Vx - set_BCD
-                0x0033 => {
-                    self.memory[self.i as usize] =
-                        (self.get_register_value(get_x(&self.opcode)) / 100) as u8;
-                    self.memory[(self.i + 1) as usize] =
-                        (((self.get_register_value(get_x(&self.opcode)) / 100) / 10) % 10) as u8;
-                    self.memory[(self.i + 2) as usize] =
-                        (((self.get_register_value(get_x(&self.opcode)) / 100) % 100) % 10) as u8;
+            // 0xF000 nnnn - LD I, LONG nnnn - Load the following 16 bit word into I
+            Instruction::LdILong => {
+                let address = self.pc as usize;
+                self.i = (self.fetch_memory(pc, address)? as u16) << 8 | self.fetch_memory(pc, address + 1)? as u16;
+                self.pc = self.pc.wrapping_add(2);
+            }
+            // 0xFn01 - PLANE n - Select the drawing planes
+            Instruction::Plane { n } => {
+                self.gfx.select_planes(n);
+            }
+            // 0xF002 - AUDIO - Load 16 bytes of audio pattern from I
+            Instruction::Audio => {
+                for idx in 0..self.audio_pattern.len() {
+                    self.audio_pattern[idx] = self.read_memory(pc, self.i as usize + idx)?;
                 }
-                // 0xFx55 - LD [I], Vx -reg_dump
-                0x0055 => {
-                    for (i, &registry) in self.registers[0 as usize..get_x(&self.opcode) as usize]
-                        .iter()
-                        .enumerate()
-                    {
-                        // This might cause a panic :fearful:
-                        self.memory[self.i as usize + i] = registry as u8;
-                    }
+            }
+            // 0xFx3A - PITCH Vx - Set the audio playback pitch
+            Instruction::Pitch { x } => {
+                self.pitch = self.get_register_value(x);
+            }
+
+            // 0xFx07 - LD Vx, DT - Loading Delay Timer into Vx
+            Instruction::LdVxDt { x } => {
+                self.set_register(x, self.delay_timer);
+            }
+            // 0xFx0A - LD Vx, K - Stop execution till a key is pressed and released, then store it in Vx
+            Instruction::LdVxK { x } => {
+                self.key_wait = KeyWait::Press { x };
+                return Ok(StepOutcome::WaitingForKey);
+            }
+            // 0xFx15 - LD DT, Vx - Load Vx into DT
+            Instruction::LdDtVx { x } => {
+                self.delay_timer = self.get_register_value(x);
+            }
+            // 0xFx18 - LD ST, Vx - Set sound timer to Vx
+            Instruction::LdStVx { x } => {
+                self.sound_timer = self.get_register_value(x);
+            }
+            // 0xFx1E - ADD I, Vx - Add I and Vx then store in I
+            Instruction::AddIVx { x } => {
+                self.i = self.i.wrapping_add(self.get_register_value(x) as u16);
+            }
+            // 0xFx29 - LD F, Vx - Set I = location of sprite for digit Vx
+            Instruction::LdFVx { x } => {
+                self.i = (FONT_BEGIN + (self.get_register_value(x) & 0xF) as usize * FONT_HEIGHT) as u16;
+            }
+            // 0xFx30 - LD HF, Vx - Set I = location of the 8x10 sprite for digit Vx
+            Instruction::LdHfVx { x } => {
+                self.i = (BIG_FONT_BEGIN + (self.get_register_value(x) & 0xF) as usize * BIG_FONT_HEIGHT) as u16;
+            }
+            // 0xFx33 - LD B, Vx - set_BCD
+            Instruction::LdBVx { x } => {
+                let v_x = self.get_register_value(x);
+                let i = self.i as usize;
+                self.write_memory(pc, i, v_x / 100)?;
+                self.write_memory(pc, i + 1, (v_x / 10) % 10)?;
+                self.write_memory(pc, i + 2, v_x % 10)?;
+            }
+            // 0xFx55 - LD [I], Vx -reg_dump
+            Instruction::LdIVx { x } => {
+                for idx in 0..=x as usize {
+                    self.write_memory(pc, self.i as usize + idx, self.registers[idx])?;
                 }
-                // 0xFx65 - LD Vx, [I] - reg_load
-                0x0065 => {
-                    for (i, &stored_registry) in self.memory
-                        [self.i as usize..self.get_register_value(get_x(&self.opcode)) as usize]
-                        .iter()
-                        .enumerate()
-                    {
-                        // Since this is growing not shrinking it should work just fine
-                        self.registers[i as usize] = stored_registry as u16;
-                    }
+                self.increment_i_after_load_store(x);
+            }
+            // 0xFx65 - LD Vx, [I] - reg_load
+            Instruction::LdVxI { x } => {
+                for idx in 0..=x as usize {
+                    self.registers[idx] = self.read_memory(pc, self.i as usize + idx)?;
                 }
-                _ => panic!("Unsupported opcode detected: {:X}", self.opcode),
-            },
-
-            // TODO: Impl other opcodes
-            _ => panic!("Unsupported opcode detected: {:X}", self.opcode),
-        }
-
-        // Update timers
-        if self.delay_timer > 0 {
-            self.delay_timer -= 1;
-        }
-        if self.sound_timer > 0 {
-            if self.sound_timer == 1 {
-                println!("BEEP!\n");
+                self.increment_i_after_load_store(x);
+            }
+            // 0xFx75 - LD R, Vx - Save V0 through Vx to the RPL flags
+            Instruction::LdRVx { x } => {
+                self.rpl[0..=x as usize].copy_from_slice(&self.registers[0..=x as usize]);
+            }
+            // 0xFx85 - LD Vx, R - Restore V0 through Vx from the RPL flags
+            Instruction::LdVxR { x } => {
+                self.registers[0..=x as usize].copy_from_slice(&self.rpl[0..=x as usize]);
             }
-            self.sound_timer -= 1;
         }
+
+        Ok(StepOutcome::Executed)
     }
 }
 
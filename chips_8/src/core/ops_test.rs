@@ -1,4 +1,7 @@
 use super::*;
+use crate::core::error::Chip8Error;
+use crate::core::quirks::Quirks;
+use crate::core::random::{ScriptedRandom, XorShift};
 
 #[test]
 fn initialized_properly() {
@@ -15,15 +18,15 @@ fn opcode_resolution() {
     let mut my_chip_8 = MyChips8::new();
 
     // Add some fake display data
-    my_chip_8.gfx[0] = 1;
+    my_chip_8.gfx.toggle(0, 0, 0x1);
 
-    assert_eq!(1, my_chip_8.gfx[0]);
+    assert_eq!(1, my_chip_8.gfx.get(0, 0));
 
     // Since this is at init PC is at 0x200
-    my_chip_8.memory[0x200] = 0x0;  // CLS
-    my_chip_8.memory[0x201] = 0xE0;  // no-op for clear
-    my_chip_8.enumlate_cycle();
-    assert_eq!(0, my_chip_8.gfx[0]);
+    my_chip_8.poke(0x200, 0x0);  // CLS
+    my_chip_8.poke(0x201, 0xE0);  // no-op for clear
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0, my_chip_8.gfx.get(0, 0));
     assert_eq!(0x202, my_chip_8.pc);
 }
 
@@ -32,9 +35,597 @@ fn a_opcode() {
     let mut my_chip_8 = MyChips8::new();
 
     // Load program into Index Register (I)
-    my_chip_8.memory[0x200] = 0xA2;
-    my_chip_8.memory[0x201] = 0x2A;
+    my_chip_8.poke(0x200, 0xA2);
+    my_chip_8.poke(0x201, 0x2A);
 
-    my_chip_8.enumlate_cycle();
+    my_chip_8.enumlate_cycle().unwrap();
     assert_eq!(0x22A, my_chip_8.i);
-}
\ No newline at end of file
+}
+
+#[test]
+fn unknown_opcode_is_reported() {
+    let mut my_chip_8 = MyChips8::new();
+
+    my_chip_8.poke(0x200, 0x80);
+    my_chip_8.poke(0x201, 0x08);
+
+    assert_eq!(Err(Chip8Error::UnknownOpcode { pc: 0x200, opcode: 0x8008 }), my_chip_8.enumlate_cycle());
+}
+
+#[test]
+fn stack_faults_are_reported() {
+    let mut my_chip_8 = MyChips8::new();
+
+    // RET with nothing on the stack
+    my_chip_8.poke(0x200, 0x00);
+    my_chip_8.poke(0x201, 0xEE);
+    assert_eq!(Err(Chip8Error::StackUnderflow { pc: 0x200 }), my_chip_8.enumlate_cycle());
+
+    // CALL 0x300 from 0x300 recurses until the stack runs out
+    let mut my_chip_8 = MyChips8::new();
+    my_chip_8.pc = 0x300;
+    my_chip_8.poke(0x300, 0x23);
+    my_chip_8.poke(0x301, 0x00);
+    let result = (0..0x20).map(|_| my_chip_8.enumlate_cycle()).find(|result| result.is_err());
+    assert_eq!(Some(Err(Chip8Error::StackOverflow { pc: 0x300 })), result);
+}
+
+#[test]
+fn memory_faults_are_reported() {
+    let mut my_chip_8 = MyChips8::new();
+
+    // LD I, FFF then LD B, V0 writes past the end of memory
+    my_chip_8.poke(0x200, 0xAF);
+    my_chip_8.poke(0x201, 0xFF);
+    my_chip_8.poke(0x202, 0xF0);
+    my_chip_8.poke(0x203, 0x33);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(Err(Chip8Error::MemoryOutOfBounds { pc: 0x202, address: 0x1000 }), my_chip_8.enumlate_cycle());
+
+    // Fetching from the last byte of memory
+    let mut my_chip_8 = MyChips8::new();
+    my_chip_8.pc = 0xFFF;
+    assert_eq!(Err(Chip8Error::MemoryOutOfBounds { pc: 0xFFF, address: 0x1000 }), my_chip_8.enumlate_cycle());
+}
+
+#[test]
+fn alu_ops() {
+    // (opcode, Vx, Vy, expected Vx, expected VF) - VF of 0xAA means the flag is left untouched
+    let table: [(u16, u8, u8, u8, u8); 24] = [
+        (0x7120, 0x10, 0x00, 0x30, 0xAA), // ADD Vx, byte
+        (0x71FF, 0x02, 0x00, 0x01, 0xAA), // ADD Vx, byte wraps without carry
+        (0x8120, 0x10, 0x42, 0x42, 0xAA), // LD Vx, Vy
+        (0x8121, 0xF0, 0x0F, 0xFF, 0xAA), // OR
+        (0x8122, 0xF3, 0x3F, 0x33, 0xAA), // AND
+        (0x8123, 0xFF, 0x0F, 0xF0, 0xAA), // XOR
+        (0x8124, 0x10, 0x20, 0x30, 0x00), // ADD no carry
+        (0x8124, 0xFF, 0x02, 0x01, 0x01), // ADD carry
+        (0x8124, 0x80, 0x80, 0x00, 0x01), // ADD carry to zero
+        (0x8125, 0x30, 0x10, 0x20, 0x01), // SUB no borrow
+        (0x8125, 0x10, 0x10, 0x00, 0x01), // SUB equal is no borrow
+        (0x8125, 0x10, 0x30, 0xE0, 0x00), // SUB borrow
+        (0x8126, 0x05, 0x00, 0x02, 0x01), // SHR LSB set
+        (0x8126, 0x04, 0x00, 0x02, 0x00), // SHR LSB clear
+        (0x8127, 0x10, 0x30, 0x20, 0x01), // SUBN no borrow
+        (0x8127, 0x30, 0x10, 0xE0, 0x00), // SUBN borrow
+        (0x812E, 0x81, 0x00, 0x02, 0x01), // SHL MSB set
+        (0x812E, 0x41, 0x00, 0x82, 0x00), // SHL MSB clear
+        // Flag is written after the result when Vx is VF
+        (0x8F14, 0xFF, 0x01, 0x01, 0x01),
+        (0x8F15, 0x01, 0x02, 0x00, 0x00),
+        (0x8F16, 0x02, 0x00, 0x00, 0x00),
+        (0x8F17, 0x01, 0x00, 0x00, 0x00),
+        (0x8F1E, 0x80, 0x00, 0x01, 0x01),
+        (0x8F24, 0x01, 0x01, 0x00, 0x00),
+    ];
+
+    for (opcode, v_x, v_y, expected_v_x, expected_v_f) in table.iter() {
+        let mut my_chip_8 = MyChips8::new();
+        my_chip_8.registers[0xF] = 0xAA;
+        let (x, y) = (((opcode & 0x0F00) >> 8) as usize, ((opcode & 0x00F0) >> 4) as usize);
+        if opcode & 0xF000 == 0x8000 {
+            my_chip_8.registers[y] = *v_y;
+        }
+        my_chip_8.registers[x] = *v_x;
+        my_chip_8.poke(0x200, (opcode >> 8) as u8);
+        my_chip_8.poke(0x201, (opcode & 0xFF) as u8);
+        my_chip_8.enumlate_cycle().unwrap();
+
+        if x != 0xF {
+            assert_eq!(*expected_v_x, my_chip_8.registers[x], "Vx for {:04X}", opcode);
+        }
+        assert_eq!(*expected_v_f, my_chip_8.registers[0xF], "VF for {:04X}", opcode);
+    }
+}
+
+#[test]
+fn call_and_ret_use_the_stack() {
+    let mut my_chip_8 = MyChips8::new();
+
+    // 0x200 CALL 0x300, 0x300 CALL 0x400, 0x400 RET, 0x302 RET
+    my_chip_8.poke(0x200, 0x23);
+    my_chip_8.poke(0x201, 0x00);
+    my_chip_8.poke(0x300, 0x24);
+    my_chip_8.poke(0x301, 0x00);
+    my_chip_8.poke(0x302, 0x00);
+    my_chip_8.poke(0x303, 0xEE);
+    my_chip_8.poke(0x400, 0x00);
+    my_chip_8.poke(0x401, 0xEE);
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0x300, 1, 0x202), (my_chip_8.pc, my_chip_8.sp, my_chip_8.stack[0]));
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0x400, 2, 0x302), (my_chip_8.pc, my_chip_8.sp, my_chip_8.stack[1]));
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0x302, 1), (my_chip_8.pc, my_chip_8.sp));
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0x202, 0), (my_chip_8.pc, my_chip_8.sp));
+}
+
+#[test]
+fn bcd_and_register_dump() {
+    let mut my_chip_8 = MyChips8::new();
+
+    my_chip_8.registers[0x0] = 254;
+    my_chip_8.registers[0x1] = 0x11;
+    my_chip_8.registers[0x2] = 0x22;
+    my_chip_8.i = 0x300;
+    // LD B, V0 / LD [I], V2 / LD I, 300 / LD V1, [I]
+    my_chip_8.load_rom(&[0xF0, 0x33, 0xA3, 0x10, 0xF2, 0x55, 0xA3, 0x00, 0xF1, 0x65]).unwrap();
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!([2, 5, 4], my_chip_8.memory()[0x300..0x303]);
+
+    my_chip_8.enumlate_cycle().unwrap();
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!([254, 0x11, 0x22, 0x00], my_chip_8.memory()[0x310..0x314]);
+
+    my_chip_8.registers[0x2] = 0x99;
+    my_chip_8.enumlate_cycle().unwrap();
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!([2, 5, 0x99], my_chip_8.registers[0x0..0x3]);
+}
+
+#[test]
+fn font_pointer_uses_glyph_height() {
+    let mut my_chip_8 = MyChips8::new();
+
+    my_chip_8.registers[0x3] = 0xF;
+    my_chip_8.load_rom(&[0xF3, 0x29]).unwrap();
+    my_chip_8.enumlate_cycle().unwrap();
+
+    assert_eq!(0x50 + 0xF * 5, my_chip_8.i);
+    assert_eq!([0xF0, 0x80, 0xF0, 0x80, 0x80], my_chip_8.memory()[0x9B..0xA0]);
+}
+
+fn chip_8_with_quirks(quirks: Quirks, rom: &[u8]) -> MyChips8 {
+    let mut my_chip_8 = MyChips8::new_with_quirks(quirks);
+    my_chip_8.load_rom(rom).unwrap();
+    my_chip_8
+}
+
+#[test]
+fn quirk_shift_uses_vy() {
+    // SHR V1, V2 then SHL V3, V2
+    let rom = [0x81, 0x26, 0x83, 0x2E];
+    let quirks = Quirks { shift_uses_vy: true, ..Quirks::chip48() };
+
+    let mut my_chip_8 = chip_8_with_quirks(quirks, &rom);
+    my_chip_8.registers[0x1] = 0xF0;
+    my_chip_8.registers[0x2] = 0x81;
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0x40, 0x1), (my_chip_8.registers[0x1], my_chip_8.registers[0xF]));
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0x02, 0x1), (my_chip_8.registers[0x3], my_chip_8.registers[0xF]));
+
+    let mut my_chip_8 = chip_8_with_quirks(Quirks { shift_uses_vy: false, ..quirks }, &rom);
+    my_chip_8.registers[0x1] = 0xF0;
+    my_chip_8.registers[0x2] = 0x81;
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0x78, 0x0), (my_chip_8.registers[0x1], my_chip_8.registers[0xF]));
+}
+
+#[test]
+fn quirk_load_store_increments_i() {
+    // LD [I], V3 then LD V3, [I]
+    let rom = [0xF3, 0x55, 0xF3, 0x65];
+    let quirks = Quirks { load_store_increments_i: true, ..Quirks::super_chip() };
+
+    let mut my_chip_8 = chip_8_with_quirks(quirks, &rom);
+    my_chip_8.i = 0x300;
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x304, my_chip_8.i);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x308, my_chip_8.i);
+
+    let mut my_chip_8 = chip_8_with_quirks(Quirks { load_store_increments_i: false, ..quirks }, &rom);
+    my_chip_8.i = 0x300;
+    my_chip_8.enumlate_cycle().unwrap();
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x300, my_chip_8.i);
+
+    // CHIP-48 leaves I on the last register
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::chip48(), &rom);
+    my_chip_8.i = 0x300;
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x303, my_chip_8.i);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x306, my_chip_8.i);
+}
+
+#[test]
+fn quirk_jump_uses_vx() {
+    // JP V0, 310
+    let rom = [0xB3, 0x10];
+    let quirks = Quirks { jump_uses_vx: true, ..Quirks::cosmac_vip() };
+
+    let mut my_chip_8 = chip_8_with_quirks(quirks, &rom);
+    my_chip_8.registers[0x0] = 0x01;
+    my_chip_8.registers[0x3] = 0x04;
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x314, my_chip_8.pc);
+
+    let mut my_chip_8 = chip_8_with_quirks(Quirks { jump_uses_vx: false, ..quirks }, &rom);
+    my_chip_8.registers[0x0] = 0x01;
+    my_chip_8.registers[0x3] = 0x04;
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x311, my_chip_8.pc);
+}
+
+#[test]
+fn quirk_logic_resets_vf() {
+    // OR V1, V2 / AND V1, V2 / XOR V1, V2
+    for rom in [[0x81, 0x21], [0x81, 0x22], [0x81, 0x23]].iter() {
+        let quirks = Quirks { logic_resets_vf: true, ..Quirks::chip48() };
+
+        let mut my_chip_8 = chip_8_with_quirks(quirks, rom);
+        my_chip_8.registers[0xF] = 0x1;
+        my_chip_8.enumlate_cycle().unwrap();
+        assert_eq!(0x0, my_chip_8.registers[0xF]);
+
+        let mut my_chip_8 = chip_8_with_quirks(Quirks { logic_resets_vf: false, ..quirks }, rom);
+        my_chip_8.registers[0xF] = 0x1;
+        my_chip_8.enumlate_cycle().unwrap();
+        assert_eq!(0x1, my_chip_8.registers[0xF]);
+    }
+}
+
+#[test]
+fn quirk_sprites_wrap() {
+    // DRW V0, V1, 2 at (62, 31) with an 8 wide sprite
+    let rom = [0xD0, 0x12];
+    let quirks = Quirks { sprites_wrap: true, ..Quirks::chip48() };
+
+    let mut my_chip_8 = chip_8_with_quirks(quirks, &rom);
+    my_chip_8.registers[0x0] = 62;
+    my_chip_8.registers[0x1] = 31;
+    my_chip_8.i = 0x300;
+    my_chip_8.poke(0x300, 0xFF);
+    my_chip_8.poke(0x301, 0xFF);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(1, my_chip_8.gfx.get(63, 31));
+    assert_eq!(1, my_chip_8.gfx.get(0, 31));
+    assert_eq!(1, my_chip_8.gfx.get(5, 0));
+    assert_eq!(16, my_chip_8.gfx.pixels().iter().filter(|&&pixel| pixel == 1).count());
+
+    let mut my_chip_8 = chip_8_with_quirks(Quirks { sprites_wrap: false, ..quirks }, &rom);
+    my_chip_8.registers[0x0] = 62;
+    my_chip_8.registers[0x1] = 31;
+    my_chip_8.i = 0x300;
+    my_chip_8.poke(0x300, 0xFF);
+    my_chip_8.poke(0x301, 0xFF);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(1, my_chip_8.gfx.get(63, 31));
+    assert_eq!(0, my_chip_8.gfx.get(0, 31));
+    assert_eq!(2, my_chip_8.gfx.pixels().iter().filter(|&&pixel| pixel == 1).count());
+}
+
+#[test]
+fn quirk_display_wait() {
+    // DRW V0, V0, 1 twice
+    let rom = [0xD0, 0x01, 0xD0, 0x01];
+    let quirks = Quirks { display_wait: true, ..Quirks::chip48() };
+
+    let mut my_chip_8 = chip_8_with_quirks(quirks, &rom);
+    assert_eq!(StepOutcome::Drew, my_chip_8.enumlate_cycle().unwrap());
+    assert_eq!(StepOutcome::WaitingForVBlank, my_chip_8.enumlate_cycle().unwrap());
+    assert_eq!(0x202, my_chip_8.pc);
+    my_chip_8.vblank();
+    assert_eq!(StepOutcome::Drew, my_chip_8.enumlate_cycle().unwrap());
+    assert_eq!(0x204, my_chip_8.pc);
+
+    let mut my_chip_8 = chip_8_with_quirks(Quirks { display_wait: false, ..quirks }, &rom);
+    assert_eq!(StepOutcome::Drew, my_chip_8.enumlate_cycle().unwrap());
+    assert_eq!(StepOutcome::Drew, my_chip_8.enumlate_cycle().unwrap());
+}
+
+#[test]
+fn quirk_presets_by_name() {
+    assert_eq!(Some(Quirks::cosmac_vip()), Quirks::from_name("VIP"));
+    assert_eq!(Some(Quirks::chip48()), Quirks::from_name("chip-48"));
+    assert_eq!(Some(Quirks::super_chip()), Quirks::from_name("schip"));
+    assert_eq!(Some(Quirks::xo_chip()), Quirks::from_name("xo-chip"));
+    assert_eq!(Some(Quirks::original()), Quirks::from_name("original"));
+    assert_eq!(None, Quirks::from_name("chip-9"));
+    assert_ne!(Quirks::chip48(), Quirks::super_chip());
+}
+
+#[test]
+fn default_quirks_keep_the_original_behaviour() {
+    assert_eq!(Quirks::original(), Quirks::default());
+
+    // JP V0, 310 adds V0 rather than V3
+    let mut my_chip_8 = MyChips8::new();
+    my_chip_8.load_rom(&[0xB3, 0x10]).unwrap();
+    my_chip_8.registers[0x0] = 0x01;
+    my_chip_8.registers[0x3] = 0x04;
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x311, my_chip_8.pc);
+
+    // DRW V0, V1, 1 at column 3C wraps its right half round to column 0
+    let mut my_chip_8 = MyChips8::new();
+    my_chip_8.load_rom(&[0xD0, 0x11]).unwrap();
+    my_chip_8.registers[0x0] = 0x3C;
+    my_chip_8.i = 0x300;
+    my_chip_8.poke(0x300, 0xFF);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(1, my_chip_8.gfx.get(0x3C, 0));
+    assert_eq!(1, my_chip_8.gfx.get(3, 0));
+}
+
+#[test]
+fn super_chip_resolution_and_scrolling() {
+    // HIGH / DRW V0, V0, 0 / SCD 2 / SCR / SCL / LOW
+    let rom = [0x00, 0xFF, 0xD0, 0x00, 0x00, 0xC2, 0x00, 0xFB, 0x00, 0xFC, 0x00, 0xFE];
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::super_chip(), &rom);
+    my_chip_8.i = 0x300;
+    (0x300..0x320).for_each(|address| { my_chip_8.poke(address, 0xFF); });
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((128, 64), (my_chip_8.gfx.width(), my_chip_8.gfx.height()));
+
+    // 16x16 sprite
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(256, my_chip_8.gfx.pixels().iter().filter(|&&pixel| pixel == 1).count());
+    assert_eq!((1, 0), (my_chip_8.gfx.get(15, 15), my_chip_8.gfx.get(16, 15)));
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0, 1, 0), (my_chip_8.gfx.get(0, 1), my_chip_8.gfx.get(0, 2), my_chip_8.gfx.get(0, 18)));
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0, 1, 0), (my_chip_8.gfx.get(3, 2), my_chip_8.gfx.get(4, 2), my_chip_8.gfx.get(20, 2)));
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((1, 1, 0), (my_chip_8.gfx.get(0, 2), my_chip_8.gfx.get(15, 2), my_chip_8.gfx.get(16, 2)));
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((64, 32), (my_chip_8.gfx.width(), my_chip_8.gfx.height()));
+    assert!(my_chip_8.gfx.pixels().iter().all(|&pixel| pixel == 0));
+}
+
+#[test]
+fn super_chip_big_font_rpl_and_exit() {
+    // LD HF, V1 / LD R, V2 / LD V0, 00 / LD V1, 00 / LD V2, R / EXIT
+    let rom = [0xF1, 0x30, 0xF2, 0x75, 0x60, 0x00, 0x61, 0x00, 0xF2, 0x85, 0x00, 0xFD];
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::super_chip(), &rom);
+    my_chip_8.registers[0x0] = 0xAB;
+    my_chip_8.registers[0x1] = 0x8;
+    my_chip_8.registers[0x2] = 0xCD;
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0xA0 + 8 * 10, my_chip_8.i);
+    assert_eq!([0x3C, 0x7E, 0xC3, 0xC3, 0x7E], my_chip_8.memory()[0xF0..0xF5]);
+
+    (0..4).for_each(|_| { my_chip_8.enumlate_cycle().unwrap(); });
+    assert_eq!([0xAB, 0x8, 0xCD], my_chip_8.registers[0x0..0x3]);
+
+    assert_eq!(StepOutcome::Exited, my_chip_8.enumlate_cycle().unwrap());
+    assert_eq!(StepOutcome::Exited, my_chip_8.enumlate_cycle().unwrap());
+    assert_eq!(0x20A, my_chip_8.pc);
+}
+
+#[test]
+fn xo_chip_memory_and_long_load() {
+    // LD I, LONG 8000 / SE V0, 00 skips over the next four byte load / LD I, LONG 1234 / LD [I], V0
+    let rom = [0xF0, 0x00, 0x80, 0x00, 0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0xF0, 0x55];
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::xo_chip(), &rom);
+    assert_eq!(0x10000, my_chip_8.memory().len());
+    assert_eq!(0x1000, MyChips8::new().memory().len());
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0x8000, 0x204), (my_chip_8.i, my_chip_8.pc));
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x20A, my_chip_8.pc);
+
+    my_chip_8.registers[0x0] = 0x42;
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x42, my_chip_8.memory()[0x8000]);
+}
+
+#[test]
+fn xo_chip_opcodes_need_the_quirk() {
+    // SE V0, 00 / LD I, LONG 6005 is a two byte unknown opcode here, so the skip lands on LD V0, 05
+    let rom = [0x30, 0x00, 0xF0, 0x00, 0x60, 0x05];
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::default(), &rom);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x204, my_chip_8.pc);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x05, my_chip_8.registers[0x0]);
+
+    my_chip_8.pc = 0x202;
+    assert_eq!(Err(Chip8Error::UnknownOpcode { pc: 0x202, opcode: 0xF000 }), my_chip_8.enumlate_cycle());
+    assert_eq!(0x202, my_chip_8.pc);
+    for opcode in [0x5012, 0x5013, 0xF101, 0xF002, 0xF03A] {
+        my_chip_8.poke(0x202, (opcode >> 8) as u8);
+        my_chip_8.poke(0x203, opcode as u8);
+        assert_eq!(Err(Chip8Error::UnknownOpcode { pc: 0x202, opcode }), my_chip_8.enumlate_cycle());
+    }
+}
+
+#[test]
+fn pc_wraps_at_the_end_of_xo_chip_memory() {
+    // LD V0, 05 in the last word
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::xo_chip(), &[]);
+    my_chip_8.pc = 0xFFFE;
+    my_chip_8.poke(0xFFFE, 0x60);
+    my_chip_8.poke(0xFFFF, 0x05);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0x05, 0x0000), (my_chip_8.registers[0x0], my_chip_8.pc));
+
+    // SE V0, 05 skipping the last word, then LD I, LONG with its address word wrapped round to 0000
+    my_chip_8.pc = 0xFFFC;
+    my_chip_8.poke(0xFFFC, 0x30);
+    my_chip_8.poke(0xFFFD, 0x05);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x0000, my_chip_8.pc);
+    my_chip_8.pc = 0xFFFE;
+    my_chip_8.poke(0xFFFE, 0xF0);
+    my_chip_8.poke(0xFFFF, 0x00);
+    my_chip_8.poke(0x0000, 0x12);
+    my_chip_8.poke(0x0001, 0x34);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!((0x1234, 0x0002), (my_chip_8.i, my_chip_8.pc));
+}
+
+#[test]
+fn xo_chip_range_save_and_load() {
+    // SAVE V1, V3 / LOAD V6, V4
+    let rom = [0x51, 0x32, 0x56, 0x43];
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::xo_chip(), &rom);
+    my_chip_8.i = 0x300;
+    my_chip_8.registers[0x1..0x4].copy_from_slice(&[0xA, 0xB, 0xC]);
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!([0xA, 0xB, 0xC], my_chip_8.memory()[0x300..0x303]);
+    assert_eq!(0x300, my_chip_8.i);
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!([0xC, 0xB, 0xA], my_chip_8.registers[0x4..0x7]);
+    assert_eq!(0x300, my_chip_8.i);
+}
+
+#[test]
+fn xo_chip_bitplanes() {
+    // PLANE 3 / DRW V0, V0, 1 / PLANE 2 / CLS
+    let rom = [0xF3, 0x01, 0xD0, 0x01, 0xF2, 0x01, 0x00, 0xE0];
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::xo_chip(), &rom);
+    my_chip_8.i = 0x300;
+    my_chip_8.poke(0x300, 0xC0); // plane 1 row
+    my_chip_8.poke(0x301, 0x60); // plane 2 row
+
+    my_chip_8.enumlate_cycle().unwrap();
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!([0x1, 0x3, 0x2, 0x0], my_chip_8.gfx.pixels()[0..4]);
+
+    my_chip_8.enumlate_cycle().unwrap();
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!([0x1, 0x1, 0x0, 0x0], my_chip_8.gfx.pixels()[0..4]);
+}
+
+#[test]
+fn xo_chip_audio_pattern_and_pitch() {
+    // AUDIO / PITCH V1
+    let rom = [0xF0, 0x02, 0xF1, 0x3A];
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::xo_chip(), &rom);
+    my_chip_8.i = 0x300;
+    (0..16).for_each(|idx| { my_chip_8.poke(0x300 + idx, idx as u8); });
+    my_chip_8.registers[0x1] = 112;
+    assert_eq!(4000.0, my_chip_8.playback_rate());
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15], my_chip_8.audio_pattern());
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(8000.0, my_chip_8.playback_rate());
+}
+
+#[test]
+fn random_uses_the_random_source() {
+    // RND V0, 0F / RND V1, FF / RND V2, FF
+    let rom = [0xC0, 0x0F, 0xC1, 0xFF, 0xC2, 0xFF];
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::default(), &rom);
+    my_chip_8.set_random_source(Box::new(ScriptedRandom::new(vec![0xAB, 0xCD, 0xCD])));
+
+    (0..3).for_each(|_| { my_chip_8.enumlate_cycle().unwrap(); });
+    assert_eq!([0x0B, 0xCD, 0xCD], my_chip_8.registers[0x0..0x3]);
+
+    // The same seed gives the same values
+    let run_with_seed = |seed: u64| {
+        let mut my_chip_8 = chip_8_with_quirks(Quirks::default(), &rom);
+        my_chip_8.set_random_source(Box::new(XorShift::new(seed)));
+        (0..3).for_each(|_| { my_chip_8.enumlate_cycle().unwrap(); });
+        my_chip_8.registers
+    };
+    assert_eq!(run_with_seed(0x1234), run_with_seed(0x1234));
+}
+
+#[test]
+fn key_wait_blocks_until_release() {
+    // LD V3, K / LD V4, 01
+    let rom = [0xF3, 0x0A, 0x64, 0x01];
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::default(), &rom);
+
+    // A key that is already held does not count, only a fresh press
+    my_chip_8.press_key(0x2);
+    assert_eq!(Ok(StepOutcome::WaitingForKey), my_chip_8.enumlate_cycle());
+    assert!(my_chip_8.is_waiting_for_key());
+    my_chip_8.release_key(0x2);
+    assert_eq!(Ok(StepOutcome::WaitingForKey), my_chip_8.enumlate_cycle());
+
+    // Pressing alone is not enough, the key has to come back up
+    my_chip_8.press_key(0xB);
+    assert_eq!(Ok(StepOutcome::WaitingForKey), my_chip_8.enumlate_cycle());
+    assert_eq!(0x0, my_chip_8.registers[0x3]);
+
+    // Releasing a different key keeps waiting
+    my_chip_8.press_key(0x5);
+    my_chip_8.release_key(0x5);
+    assert!(my_chip_8.is_waiting_for_key());
+
+    my_chip_8.release_key(0xB);
+    assert!(!my_chip_8.is_waiting_for_key());
+    assert_eq!(0xB, my_chip_8.registers[0x3]);
+    assert_eq!(0x202, my_chip_8.pc);
+
+    assert_eq!(Ok(StepOutcome::Executed), my_chip_8.enumlate_cycle());
+    assert_eq!(0x1, my_chip_8.registers[0x4]);
+}
+
+#[test]
+fn keypad_drives_skip_instructions() {
+    // SKP V0 / SKNP V0
+    let rom = [0xE0, 0x9E, 0x00, 0x00, 0xE0, 0xA1];
+    let mut my_chip_8 = chip_8_with_quirks(Quirks::default(), &rom);
+    my_chip_8.registers[0x0] = 0x7;
+
+    my_chip_8.press_key(0x7);
+    my_chip_8.press_key(0x10);
+    assert!(my_chip_8.is_key_pressed(0x7));
+    assert!(!my_chip_8.is_key_pressed(0x10));
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x204, my_chip_8.pc);
+
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x206, my_chip_8.pc);
+
+    my_chip_8.pc = 0x204;
+    my_chip_8.release_key(0x7);
+    my_chip_8.enumlate_cycle().unwrap();
+    assert_eq!(0x208, my_chip_8.pc);
+}
+
+#[test]
+fn load_rom_rejects_programs_that_do_not_fit() {
+    let mut my_chip_8 = MyChips8::new();
+    assert_eq!(Ok(()), my_chip_8.load_rom(&[0xAB; 3584]));
+    assert_eq!(0xAB, my_chip_8.memory()[0xFFF]);
+    assert_eq!(
+        Err(Chip8Error::RomTooLarge { size: 3585, capacity: 3584 }),
+        my_chip_8.load_rom(&[0xAB; 3585])
+    );
+
+    let mut my_chip_8 = MyChips8::new_with_quirks(Quirks::xo_chip());
+    assert_eq!(Ok(()), my_chip_8.load_rom(&[0xAB; 3585]));
+    assert_eq!(Ok(()), my_chip_8.load_rom(&vec![0xAB; 0x10000 - 0x200]));
+}
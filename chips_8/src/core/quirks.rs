@@ -0,0 +1,103 @@
+// Behaviour that differs between CHIP-8 interpreters, ROMs written for one era tend to rely on its quirks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    pub shift_uses_vy: bool,           // 8xy6 / 8xyE shift Vy into Vx instead of shifting Vx in place
+    pub load_store_increments_i: bool, // Fx55 / Fx65 leave I pointing past the last register
+    pub jump_uses_vx: bool,            // Bnnn is treated as BXNN and jumps to XNN + Vx
+    pub logic_resets_vf: bool,         // 8xy1 / 8xy2 / 8xy3 clear VF
+    pub sprites_wrap: bool,            // Dxyn wraps pixels around the screen edges instead of clipping them
+    pub display_wait: bool,            // Dxyn waits for the next vertical blank before drawing
+    pub xo_chip: bool,                 // XO-CHIP extensions: 64K of memory, the XO-CHIP opcodes and skips over F000 nnnn
+    pub load_store_i_on_last: bool,    // With load_store_increments_i, I stops on the last register instead
+}
+
+impl Quirks {
+    // What this emulator did before quirks were configurable: shifts in place, I left alone, Bnnn adds V0
+    // and sprites wrap. Close to SUPER-CHIP apart from the jump and wrapping
+    pub fn original() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            sprites_wrap: true,
+            display_wait: false,
+            xo_chip: false,
+            load_store_i_on_last: false,
+        }
+    }
+
+    // Original interpreter on the RCA COSMAC VIP
+    pub fn cosmac_vip() -> Quirks {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            logic_resets_vf: true,
+            sprites_wrap: false,
+            display_wait: true,
+            xo_chip: false,
+            load_store_i_on_last: false,
+        }
+    }
+
+    // HP-48 calculator port, the root of most of the later behaviour, its Fx55 / Fx65 move I one short
+    pub fn chip48() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: true,
+            jump_uses_vx: true,
+            logic_resets_vf: false,
+            sprites_wrap: false,
+            display_wait: false,
+            xo_chip: false,
+            load_store_i_on_last: true,
+        }
+    }
+
+    // SUPER-CHIP 1.1, which stopped moving I at all
+    pub fn super_chip() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: true,
+            logic_resets_vf: false,
+            sprites_wrap: false,
+            display_wait: false,
+            xo_chip: false,
+            load_store_i_on_last: false,
+        }
+    }
+
+    // XO-CHIP as implemented by Octo
+    pub fn xo_chip() -> Quirks {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            sprites_wrap: true,
+            display_wait: false,
+            xo_chip: true,
+            load_store_i_on_last: false,
+        }
+    }
+
+    // Look up a preset by the name used on the command line
+    pub fn from_name(name: &str) -> Option<Quirks> {
+        match name.to_ascii_lowercase().as_str() {
+            "vip" | "cosmac-vip" | "chip-8" | "chip8" => Some(Quirks::cosmac_vip()),
+            "chip-48" | "chip48" => Some(Quirks::chip48()),
+            "schip" | "super-chip" | "superchip" => Some(Quirks::super_chip()),
+            "xo-chip" | "xochip" => Some(Quirks::xo_chip()),
+            "original" => Some(Quirks::original()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::original()
+    }
+}
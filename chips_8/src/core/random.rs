@@ -0,0 +1,66 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Source of bytes for Cxkk, swapped out for a fixed sequence in tests and replays
+pub trait RandomSource {
+    fn next_byte(&mut self) -> u8;
+}
+
+// xorshift64, small and fast with a seed that fully determines the sequence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        // An all zero state would only ever produce zeros
+        XorShift { state: if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed } }
+    }
+
+    // Seeded from the clock for normal play
+    pub fn from_time() -> XorShift {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos() as u64).unwrap_or(0);
+        XorShift::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+}
+
+impl RandomSource for XorShift {
+    fn next_byte(&mut self) -> u8 {
+        (self.next_u64() >> 32) as u8
+    }
+}
+
+// Plays back a fixed list of bytes, starting over once it runs out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptedRandom {
+    values: Vec<u8>,
+    position: usize,
+}
+
+impl ScriptedRandom {
+    pub fn new(values: Vec<u8>) -> ScriptedRandom {
+        ScriptedRandom { values, position: 0 }
+    }
+}
+
+impl RandomSource for ScriptedRandom {
+    fn next_byte(&mut self) -> u8 {
+        if self.values.is_empty() {
+            return 0;
+        }
+        let value = self.values[self.position % self.values.len()];
+        self.position += 1;
+        value
+    }
+}
+
+#[cfg(test)]
+#[path = "./random_test.rs"]
+mod random_test;
@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn xorshift_is_reproducible() {
+    let mut first = XorShift::new(0xC8);
+    let mut second = XorShift::new(0xC8);
+
+    let first_bytes: Vec<u8> = (0..64).map(|_| first.next_byte()).collect();
+    let second_bytes: Vec<u8> = (0..64).map(|_| second.next_byte()).collect();
+    assert_eq!(first_bytes, second_bytes);
+
+    let mut other = XorShift::new(0xC9);
+    let other_bytes: Vec<u8> = (0..64).map(|_| other.next_byte()).collect();
+    assert_ne!(first_bytes, other_bytes);
+}
+
+#[test]
+fn xorshift_covers_every_byte() {
+    let mut random = XorShift::new(0);
+    let mut seen = [false; 256];
+    (0..10_000).for_each(|_| seen[random.next_byte() as usize] = true);
+
+    assert!(seen.iter().all(|&byte_seen| byte_seen));
+}
+
+#[test]
+fn scripted_random_repeats() {
+    let mut random = ScriptedRandom::new(vec![1, 2, 3]);
+
+    assert_eq!([1, 2, 3, 1], [random.next_byte(), random.next_byte(), random.next_byte(), random.next_byte()]);
+    assert_eq!(0, ScriptedRandom::new(vec![]).next_byte());
+}
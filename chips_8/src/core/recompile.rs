@@ -0,0 +1,329 @@
+use std::fmt::Write;
+
+use super::cfg::ControlFlowGraph;
+use super::disassemble::DisassembledOp;
+use super::error::Chip8Error;
+use super::instruction::Instruction;
+use super::machine::Engine;
+use super::ops::{MyChips8, StepOutcome};
+use super::quirks::Quirks;
+
+// Longest compiled function, a block only runs compiled when it fits in what is left of the frame
+// so long blocks are split to keep them usable at low speeds
+const MAX_BLOCK_LENGTH: usize = 8;
+
+// One basic block of the ROM turned into Rust by recompile()
+pub struct CompiledBlock {
+    pub start: u16,
+    pub bytes: &'static [u8], // What the block was compiled from, it only runs while memory still holds these
+    pub length: u64,          // Instructions in the block
+    pub run: fn(&mut MyChips8) -> Result<BlockOutcome, Chip8Error>,
+}
+
+// What a compiled block did, stop is Executed unless it had to end early
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockOutcome {
+    pub executed: u64,
+    pub drew: bool,
+    pub stop: StepOutcome,
+}
+
+impl Default for BlockOutcome {
+    fn default() -> Self {
+        BlockOutcome { executed: 0, drew: false, stop: StepOutcome::Executed }
+    }
+}
+
+impl BlockOutcome {
+    // Runs one instruction on the interpreter from inside a compiled block, true when the block has to
+    // return now: the instruction stopped (key wait, vertical blank, exit) or wrote to memory, which may
+    // have been the rest of the block
+    pub fn interpret(&mut self, cpu: &mut MyChips8, pc: u16, opcode: u16, instruction: Instruction) -> Result<bool, Chip8Error> {
+        cpu.pc = pc;
+        match cpu.run_decoded(opcode, instruction)? {
+            StepOutcome::Executed => {}
+            StepOutcome::Drew => self.drew = true,
+            stop => {
+                self.stop = stop;
+                return Ok(true);
+            }
+        }
+        self.executed += 1;
+        Ok(cpu.has_written())
+    }
+}
+
+// Runs the blocks of a recompiled ROM, anything they do not cover goes to the interpreter: addresses
+// only reached through JP V0, code that was written over and CPUs set up with different quirks
+// Only interpreted instructions are traced or leave their opcode behind, PC is exact between blocks
+pub struct Recompiled {
+    quirks: Quirks,
+    table: &'static [CompiledBlock], // Sorted by start address
+    index: Vec<Option<usize>>,       // Block starting at each address
+    verified: Vec<Option<bool>>,     // Whether memory still matches each block, None until checked
+}
+
+impl Recompiled {
+    pub fn new(quirks: Quirks, table: &'static [CompiledBlock]) -> Recompiled {
+        let size = table.iter().map(|block| block.start as usize + 1).max().unwrap_or(0);
+        let mut index = vec![None; size];
+        table.iter().enumerate().for_each(|(idx, block)| index[block.start as usize] = Some(idx));
+        Recompiled { quirks, table, index, verified: vec![None; table.len()] }
+    }
+
+    fn block_at(&mut self, cpu: &MyChips8, pc: usize) -> Option<&'static CompiledBlock> {
+        let idx = (*self.index.get(pc)?)?;
+        let block = &self.table[idx];
+        let verified = *self.verified[idx].get_or_insert_with(|| cpu.memory().get(pc..pc + block.bytes.len()) == Some(block.bytes));
+        if verified { Some(block) } else { None }
+    }
+
+    // Blocks under a write are checked again before they next run
+    fn invalidate(&mut self, low: usize, high: usize) {
+        let first = self.table.partition_point(|block| block.start as usize + block.bytes.len() <= low);
+        for (idx, block) in self.table.iter().enumerate().skip(first) {
+            if block.start as usize > high {
+                break;
+            }
+            self.verified[idx] = None;
+        }
+    }
+}
+
+impl Engine for Recompiled {
+    fn step(&mut self, cpu: &mut MyChips8) -> Result<StepOutcome, Chip8Error> {
+        // Only one instruction fits, so blocks of one are all that run compiled
+        let (_, drew, stop) = self.run(cpu, 1)?;
+        Ok(if stop == StepOutcome::Executed && drew { StepOutcome::Drew } else { stop })
+    }
+
+    fn run(&mut self, cpu: &mut MyChips8, limit: u64) -> Result<(u64, bool, StepOutcome), Chip8Error> {
+        let compiled = cpu.quirks() == self.quirks;
+        let mut executed = 0;
+        let mut drew = false;
+        while executed < limit {
+            if let Some((low, high)) = cpu.take_written() {
+                self.invalidate(low, high);
+            }
+            let block = match compiled && !cpu.is_waiting_for_key() {
+                true => self.block_at(cpu, cpu.pc as usize).filter(|block| block.length <= limit - executed),
+                false => None,
+            };
+            match block {
+                Some(block) => {
+                    let outcome = (block.run)(cpu)?;
+                    executed += outcome.executed;
+                    drew |= outcome.drew;
+                    if outcome.stop != StepOutcome::Executed {
+                        return Ok((executed, drew, outcome.stop));
+                    }
+                }
+                None => {
+                    match cpu.enumlate_cycle()? {
+                        StepOutcome::Executed => {}
+                        StepOutcome::Drew => drew = true,
+                        outcome => return Ok((executed, drew, outcome)),
+                    }
+                    executed += 1;
+                }
+            }
+        }
+        Ok((executed, drew, StepOutcome::Executed))
+    }
+}
+
+// Emits a Rust module with a function per basic block of the ROM and a Recompiled table of them
+// Arithmetic, I and timer instructions become plain Rust on a copy of the registers with the quirks
+// baked in, everything else (drawing, memory, the stack, keys) calls back into the interpreter
+pub fn recompile(rom: &[u8], quirks: Quirks) -> String {
+    let graph = ControlFlowGraph::new(rom);
+    let chunks: Vec<&[DisassembledOp]> = graph.blocks().flat_map(|block| block.ops.chunks(MAX_BLOCK_LENGTH)).collect();
+
+    let mut out = String::new();
+    writeln!(out, "// Recompiled from a {} byte ROM by chip8-recompile, regenerate it rather than editing", rom.len()).unwrap();
+    writeln!(out, "// Every function is one basic block, Recompiled falls back to the interpreter for the rest").unwrap();
+    writeln!(out, "#![allow(unused_assignments, unused_imports, unused_mut, unused_variables, clippy::all)]").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use chips_8::core::error::Chip8Error;").unwrap();
+    writeln!(out, "use chips_8::core::instruction::Instruction;").unwrap();
+    writeln!(out, "use chips_8::core::ops::MyChips8;").unwrap();
+    writeln!(out, "use chips_8::core::quirks::Quirks;").unwrap();
+    writeln!(out, "use chips_8::core::recompile::{{BlockOutcome, CompiledBlock, Recompiled}};").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "pub const QUIRKS: Quirks = {:?};", quirks).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "pub static BLOCKS: [CompiledBlock; {}] = [", chunks.len()).unwrap();
+    for ops in &chunks {
+        let bytes: Vec<String> = ops.iter().flat_map(|op| op.bytes.iter()).map(|byte| format!("0x{:02X}", byte)).collect();
+        writeln!(
+            out,
+            "    CompiledBlock {{ start: 0x{:03X}, bytes: &[{}], length: {}, run: block_{:03x} }},",
+            ops[0].address,
+            bytes.join(", "),
+            ops.len(),
+            ops[0].address
+        )
+        .unwrap();
+    }
+    writeln!(out, "];").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "// Engine for Machine::set_engine, the ROM still has to be loaded into the CPU as usual").unwrap();
+    writeln!(out, "pub fn engine() -> Recompiled {{").unwrap();
+    writeln!(out, "    Recompiled::new(QUIRKS, &BLOCKS)").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    for ops in chunks {
+        writeln!(out).unwrap();
+        emit_block(&mut out, ops, quirks);
+    }
+    out
+}
+
+fn emit_block(out: &mut String, ops: &[DisassembledOp], quirks: Quirks) {
+    writeln!(out, "fn block_{:03x}(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {{", ops[0].address).unwrap();
+    writeln!(out, "    let mut out = BlockOutcome::default();").unwrap();
+    writeln!(out, "    let mut v = *cpu.registers();").unwrap();
+    writeln!(out, "    let mut i = cpu.i();").unwrap();
+
+    // loaded: v and i hold the CPU's registers, dirty: they hold changes the CPU has not seen yet
+    let (mut loaded, mut dirty) = (true, false);
+    let mut interpreted_last = false;
+    let mut counted = 0; // out.executed as the code stands, interpret() counts the instructions it runs
+    for (index, op) in ops.iter().enumerate() {
+        let instruction = op.instruction.unwrap();
+        let next = op.address + op.bytes.len();
+        let code = inline(instruction, quirks, next);
+        if code.is_some() && !loaded {
+            writeln!(out, "    v = *cpu.registers();").unwrap();
+            writeln!(out, "    i = cpu.i();").unwrap();
+            loaded = true;
+        }
+        writeln!(out, "    // {:03X}  {}", op.address, format!("{} {}", op.mnemonic, op.operand_text()).trim_end()).unwrap();
+
+        match code {
+            Some(code) => {
+                code.iter().for_each(|line| writeln!(out, "    {}", line).unwrap());
+                dirty |= !code.is_empty();
+                interpreted_last = false;
+            }
+            None => {
+                if dirty {
+                    writeln!(out, "    cpu.set_registers(v);").unwrap();
+                    writeln!(out, "    cpu.set_i(i);").unwrap();
+                    dirty = false;
+                }
+                if counted != index {
+                    writeln!(out, "    out.executed = {};", index).unwrap();
+                }
+                counted = index + 1;
+                let opcode = (op.bytes[0] as u16) << 8 | op.bytes[1] as u16;
+                writeln!(
+                    out,
+                    "    if out.interpret(cpu, 0x{:03X}, 0x{:04X}, Instruction::{:?})? {{",
+                    op.address, opcode, instruction
+                )
+                .unwrap();
+                writeln!(out, "        return Ok(out);").unwrap();
+                writeln!(out, "    }}").unwrap();
+                loaded = false;
+                interpreted_last = true;
+            }
+        }
+    }
+
+    if dirty {
+        writeln!(out, "    cpu.set_registers(v);").unwrap();
+        writeln!(out, "    cpu.set_i(i);").unwrap();
+    }
+    // Interpreted instructions leave PC where it belongs, compiled ones never touch it until here
+    if !interpreted_last {
+        let last = ops.last().unwrap();
+        let next = last.address + last.bytes.len();
+        match exit(last.instruction.unwrap(), quirks, next) {
+            Some(code) => writeln!(out, "    {}", code).unwrap(),
+            // PC wraps at 64K like the interpreter's does
+            None => writeln!(out, "    cpu.pc = 0x{:03X};", next & 0xFFFF).unwrap(),
+        }
+    }
+    if counted != ops.len() {
+        writeln!(out, "    out.executed = {};", ops.len()).unwrap();
+    }
+    writeln!(out, "    Ok(out)").unwrap();
+    writeln!(out, "}}").unwrap();
+}
+
+// Rust for instructions that only touch registers, I and the timers, None when the interpreter has to run it
+// Matches MyChips8::execute, VF is always written after the result so VF as an operand gets the flag
+fn inline(instruction: Instruction, quirks: Quirks, next: usize) -> Option<Vec<String>> {
+    let shift_source = |x: u8, y: u8| if quirks.shift_uses_vy { y } else { x };
+    let logic = |x: u8, y: u8, operator: &str| {
+        let mut code = vec![format!("v[0x{:X}] {}= v[0x{:X}];", x, operator, y)];
+        if quirks.logic_resets_vf {
+            code.push(String::from("v[0xF] = 0;"));
+        }
+        code
+    };
+    let with_flag = |x: u8, result: String, flag: String| {
+        vec![format!("let (result, flag) = ({}, {});", result, flag), format!("v[0x{:X}] = result;", x), String::from("v[0xF] = flag;")]
+    };
+
+    Some(match instruction {
+        Instruction::Sys { .. } => Vec::new(),
+        Instruction::LdByte { x, kk } => vec![format!("v[0x{:X}] = 0x{:02X};", x, kk)],
+        Instruction::AddByte { x, kk } => vec![format!("v[0x{:X}] = v[0x{:X}].wrapping_add(0x{:02X});", x, x, kk)],
+        Instruction::LdReg { x, y } => vec![format!("v[0x{:X}] = v[0x{:X}];", x, y)],
+        Instruction::Or { x, y } => logic(x, y, "|"),
+        Instruction::And { x, y } => logic(x, y, "&"),
+        Instruction::Xor { x, y } => logic(x, y, "^"),
+        Instruction::AddReg { x, y } => with_flag(
+            x,
+            format!("v[0x{:X}].wrapping_add(v[0x{:X}])", x, y),
+            format!("v[0x{:X}].checked_add(v[0x{:X}]).is_none() as u8", x, y),
+        ),
+        Instruction::Sub { x, y } => with_flag(
+            x,
+            format!("v[0x{:X}].wrapping_sub(v[0x{:X}])", x, y),
+            format!("(v[0x{:X}] >= v[0x{:X}]) as u8", x, y),
+        ),
+        Instruction::Subn { x, y } => with_flag(
+            x,
+            format!("v[0x{:X}].wrapping_sub(v[0x{:X}])", y, x),
+            format!("(v[0x{:X}] >= v[0x{:X}]) as u8", y, x),
+        ),
+        Instruction::Shr { x, y } => {
+            let source = shift_source(x, y);
+            with_flag(x, format!("v[0x{:X}] >> 1", source), format!("v[0x{:X}] & 0x1", source))
+        }
+        Instruction::Shl { x, y } => {
+            let source = shift_source(x, y);
+            with_flag(x, format!("v[0x{:X}] << 1", source), format!("v[0x{:X}] >> 7", source))
+        }
+        Instruction::LdI { nnn } => vec![format!("i = 0x{:03X};", nnn)],
+        Instruction::AddIVx { x } => vec![format!("i = i.wrapping_add(v[0x{:X}] as u16);", x)],
+        Instruction::LdVxDt { x } => vec![format!("v[0x{:X}] = cpu.delay_timer();", x)],
+        Instruction::LdDtVx { x } => vec![format!("cpu.set_delay_timer(v[0x{:X}]);", x)],
+        Instruction::LdStVx { x } => vec![format!("cpu.set_sound_timer(v[0x{:X}]);", x)],
+        // Branches only set PC, exit() writes that once the registers are back in the CPU
+        _ if exit(instruction, quirks, next).is_some() => Vec::new(),
+        _ => return None,
+    })
+}
+
+// Where PC goes after a compiled branch, None for anything else
+// Skips are left to the interpreter under XO-CHIP, how far they skip depends on what follows
+fn exit(instruction: Instruction, quirks: Quirks, next: usize) -> Option<String> {
+    let skip = |condition: String| Some(format!("cpu.pc = if {} {{ 0x{:03X} }} else {{ 0x{:03X} }};", condition, (next + 2) & 0xFFFF, next & 0xFFFF));
+    match instruction {
+        Instruction::Jp { nnn } => Some(format!("cpu.pc = 0x{:03X};", nnn)),
+        _ if quirks.xo_chip => None,
+        Instruction::SeByte { x, kk } => skip(format!("v[0x{:X}] == 0x{:02X}", x, kk)),
+        Instruction::SneByte { x, kk } => skip(format!("v[0x{:X}] != 0x{:02X}", x, kk)),
+        Instruction::SeReg { x, y } => skip(format!("v[0x{:X}] == v[0x{:X}]", x, y)),
+        Instruction::SneReg { x, y } => skip(format!("v[0x{:X}] != v[0x{:X}]", x, y)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[path ="./recompile_test.rs"]
+mod recompile_test;
@@ -0,0 +1,137 @@
+// Recompiled from a 132 byte ROM by chip8-recompile, regenerate it rather than editing
+// Every function is one basic block, Recompiled falls back to the interpreter for the rest
+#![allow(unused_assignments, unused_imports, unused_mut, unused_variables, clippy::all)]
+
+use chips_8::core::error::Chip8Error;
+use chips_8::core::instruction::Instruction;
+use chips_8::core::ops::MyChips8;
+use chips_8::core::quirks::Quirks;
+use chips_8::core::recompile::{BlockOutcome, CompiledBlock, Recompiled};
+
+pub const QUIRKS: Quirks = Quirks { shift_uses_vy: false, load_store_increments_i: true, jump_uses_vx: true, logic_resets_vf: false, sprites_wrap: false, display_wait: false, xo_chip: false, load_store_i_on_last: true };
+
+pub static BLOCKS: [CompiledBlock; 4] = [
+    CompiledBlock { start: 0x200, bytes: &[0x00, 0xE0, 0xA2, 0x2A, 0x60, 0x0C, 0x61, 0x08, 0xD0, 0x1F, 0x70, 0x09, 0xA2, 0x39, 0xD0, 0x1F], length: 8, run: block_200 },
+    CompiledBlock { start: 0x210, bytes: &[0xA2, 0x48, 0x70, 0x08, 0xD0, 0x1F, 0x70, 0x04, 0xA2, 0x57, 0xD0, 0x1F, 0x70, 0x08, 0xA2, 0x66], length: 8, run: block_210 },
+    CompiledBlock { start: 0x220, bytes: &[0xD0, 0x1F, 0x70, 0x08, 0xA2, 0x75, 0xD0, 0x1F], length: 4, run: block_220 },
+    CompiledBlock { start: 0x228, bytes: &[0x12, 0x28], length: 1, run: block_228 },
+];
+
+// Engine for Machine::set_engine, the ROM still has to be loaded into the CPU as usual
+pub fn engine() -> Recompiled {
+    Recompiled::new(QUIRKS, &BLOCKS)
+}
+
+fn block_200(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 200  CLS
+    if out.interpret(cpu, 0x200, 0x00E0, Instruction::Cls)? {
+        return Ok(out);
+    }
+    v = *cpu.registers();
+    i = cpu.i();
+    // 202  LD I, sprite_22A
+    i = 0x22A;
+    // 204  LD V0, 0C
+    v[0x0] = 0x0C;
+    // 206  LD V1, 08
+    v[0x1] = 0x08;
+    // 208  DRW V0, V1, F
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    out.executed = 4;
+    if out.interpret(cpu, 0x208, 0xD01F, Instruction::Drw { x: 0, y: 1, n: 15 })? {
+        return Ok(out);
+    }
+    v = *cpu.registers();
+    i = cpu.i();
+    // 20A  ADD V0, 09
+    v[0x0] = v[0x0].wrapping_add(0x09);
+    // 20C  LD I, sprite_239
+    i = 0x239;
+    // 20E  DRW V0, V1, F
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    out.executed = 7;
+    if out.interpret(cpu, 0x20E, 0xD01F, Instruction::Drw { x: 0, y: 1, n: 15 })? {
+        return Ok(out);
+    }
+    Ok(out)
+}
+
+fn block_210(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 210  LD I, sprite_248
+    i = 0x248;
+    // 212  ADD V0, 08
+    v[0x0] = v[0x0].wrapping_add(0x08);
+    // 214  DRW V0, V1, F
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    out.executed = 2;
+    if out.interpret(cpu, 0x214, 0xD01F, Instruction::Drw { x: 0, y: 1, n: 15 })? {
+        return Ok(out);
+    }
+    v = *cpu.registers();
+    i = cpu.i();
+    // 216  ADD V0, 04
+    v[0x0] = v[0x0].wrapping_add(0x04);
+    // 218  LD I, sprite_257
+    i = 0x257;
+    // 21A  DRW V0, V1, F
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    out.executed = 5;
+    if out.interpret(cpu, 0x21A, 0xD01F, Instruction::Drw { x: 0, y: 1, n: 15 })? {
+        return Ok(out);
+    }
+    v = *cpu.registers();
+    i = cpu.i();
+    // 21C  ADD V0, 08
+    v[0x0] = v[0x0].wrapping_add(0x08);
+    // 21E  LD I, sprite_266
+    i = 0x266;
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    cpu.pc = 0x220;
+    out.executed = 8;
+    Ok(out)
+}
+
+fn block_220(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 220  DRW V0, V1, F
+    if out.interpret(cpu, 0x220, 0xD01F, Instruction::Drw { x: 0, y: 1, n: 15 })? {
+        return Ok(out);
+    }
+    v = *cpu.registers();
+    i = cpu.i();
+    // 222  ADD V0, 08
+    v[0x0] = v[0x0].wrapping_add(0x08);
+    // 224  LD I, sprite_275
+    i = 0x275;
+    // 226  DRW V0, V1, F
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    out.executed = 3;
+    if out.interpret(cpu, 0x226, 0xD01F, Instruction::Drw { x: 0, y: 1, n: 15 })? {
+        return Ok(out);
+    }
+    Ok(out)
+}
+
+fn block_228(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 228  JMP label_228
+    cpu.pc = 0x228;
+    out.executed = 1;
+    Ok(out)
+}
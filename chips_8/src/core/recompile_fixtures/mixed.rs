@@ -0,0 +1,256 @@
+// Recompiled from a 86 byte ROM by chip8-recompile, regenerate it rather than editing
+// Every function is one basic block, Recompiled falls back to the interpreter for the rest
+#![allow(unused_assignments, unused_imports, unused_mut, unused_variables, clippy::all)]
+
+use chips_8::core::error::Chip8Error;
+use chips_8::core::instruction::Instruction;
+use chips_8::core::ops::MyChips8;
+use chips_8::core::quirks::Quirks;
+use chips_8::core::recompile::{BlockOutcome, CompiledBlock, Recompiled};
+
+pub const QUIRKS: Quirks = Quirks { shift_uses_vy: false, load_store_increments_i: true, jump_uses_vx: true, logic_resets_vf: false, sprites_wrap: false, display_wait: false, xo_chip: false, load_store_i_on_last: true };
+
+pub static BLOCKS: [CompiledBlock; 14] = [
+    CompiledBlock { start: 0x200, bytes: &[0x60, 0x02, 0x62, 0x02, 0xB2, 0x08], length: 3, run: block_200 },
+    CompiledBlock { start: 0x208, bytes: &[0x00, 0x00, 0x22, 0x30], length: 2, run: block_208 },
+    CompiledBlock { start: 0x20C, bytes: &[0xA2, 0x30, 0x60, 0x75, 0x61, 0x01, 0xF1, 0x55, 0x22, 0x30], length: 5, run: block_20c },
+    CompiledBlock { start: 0x216, bytes: &[0x45, 0x06], length: 1, run: block_216 },
+    CompiledBlock { start: 0x218, bytes: &[0x12, 0x1C], length: 1, run: block_218 },
+    CompiledBlock { start: 0x21A, bytes: &[0x00, 0xFD], length: 1, run: block_21a },
+    CompiledBlock { start: 0x21C, bytes: &[0x22, 0x34], length: 1, run: block_21c },
+    CompiledBlock { start: 0x21E, bytes: &[0x12, 0x1C], length: 1, run: block_21e },
+    CompiledBlock { start: 0x230, bytes: &[0x65, 0x05, 0x00, 0xEE], length: 2, run: block_230 },
+    CompiledBlock { start: 0x234, bytes: &[0x86, 0x04, 0x87, 0x65, 0x88, 0x76, 0x89, 0x8E, 0x8A, 0x97, 0x8B, 0xA1, 0x8C, 0xB2, 0x8D, 0xC3], length: 8, run: block_234 },
+    CompiledBlock { start: 0x244, bytes: &[0x8F, 0xD4, 0x7E, 0x03, 0xFE, 0x1E, 0x5E, 0x60], length: 4, run: block_244 },
+    CompiledBlock { start: 0x24C, bytes: &[0x9E, 0x70], length: 1, run: block_24c },
+    CompiledBlock { start: 0x24E, bytes: &[0xFE, 0x15], length: 1, run: block_24e },
+    CompiledBlock { start: 0x250, bytes: &[0xF3, 0x07, 0xC4, 0x0F, 0x00, 0xEE], length: 3, run: block_250 },
+];
+
+// Engine for Machine::set_engine, the ROM still has to be loaded into the CPU as usual
+pub fn engine() -> Recompiled {
+    Recompiled::new(QUIRKS, &BLOCKS)
+}
+
+fn block_200(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 200  LD V0, 02
+    v[0x0] = 0x02;
+    // 202  LD V2, 02
+    v[0x2] = 0x02;
+    // 204  JMP V0, label_208
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    out.executed = 2;
+    if out.interpret(cpu, 0x204, 0xB208, Instruction::JpV0 { nnn: 520 })? {
+        return Ok(out);
+    }
+    Ok(out)
+}
+
+fn block_208(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 208  SYS 000
+    // 20A  CALL sub_230
+    out.executed = 1;
+    if out.interpret(cpu, 0x20A, 0x2230, Instruction::Call { nnn: 560 })? {
+        return Ok(out);
+    }
+    Ok(out)
+}
+
+fn block_20c(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 20C  LD I, sub_230
+    i = 0x230;
+    // 20E  LD V0, 75
+    v[0x0] = 0x75;
+    // 210  LD V1, 01
+    v[0x1] = 0x01;
+    // 212  LD [I], V1
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    out.executed = 3;
+    if out.interpret(cpu, 0x212, 0xF155, Instruction::LdIVx { x: 1 })? {
+        return Ok(out);
+    }
+    // 214  CALL sub_230
+    if out.interpret(cpu, 0x214, 0x2230, Instruction::Call { nnn: 560 })? {
+        return Ok(out);
+    }
+    Ok(out)
+}
+
+fn block_216(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 216  SNE V5, 06
+    cpu.pc = if v[0x5] != 0x06 { 0x21A } else { 0x218 };
+    out.executed = 1;
+    Ok(out)
+}
+
+fn block_218(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 218  JMP label_21C
+    cpu.pc = 0x21C;
+    out.executed = 1;
+    Ok(out)
+}
+
+fn block_21a(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 21A  EXIT
+    if out.interpret(cpu, 0x21A, 0x00FD, Instruction::Exit)? {
+        return Ok(out);
+    }
+    Ok(out)
+}
+
+fn block_21c(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 21C  CALL sub_234
+    if out.interpret(cpu, 0x21C, 0x2234, Instruction::Call { nnn: 564 })? {
+        return Ok(out);
+    }
+    Ok(out)
+}
+
+fn block_21e(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 21E  JMP label_21C
+    cpu.pc = 0x21C;
+    out.executed = 1;
+    Ok(out)
+}
+
+fn block_230(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 230  LD V5, 05
+    v[0x5] = 0x05;
+    // 232  RET
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    out.executed = 1;
+    if out.interpret(cpu, 0x232, 0x00EE, Instruction::Ret)? {
+        return Ok(out);
+    }
+    Ok(out)
+}
+
+fn block_234(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 234  ADD V6, V0
+    let (result, flag) = (v[0x6].wrapping_add(v[0x0]), v[0x6].checked_add(v[0x0]).is_none() as u8);
+    v[0x6] = result;
+    v[0xF] = flag;
+    // 236  SUB V7, V6
+    let (result, flag) = (v[0x7].wrapping_sub(v[0x6]), (v[0x7] >= v[0x6]) as u8);
+    v[0x7] = result;
+    v[0xF] = flag;
+    // 238  SHR V8, V7
+    let (result, flag) = (v[0x8] >> 1, v[0x8] & 0x1);
+    v[0x8] = result;
+    v[0xF] = flag;
+    // 23A  SHL V9, V8
+    let (result, flag) = (v[0x9] << 1, v[0x9] >> 7);
+    v[0x9] = result;
+    v[0xF] = flag;
+    // 23C  SUBN VA, V9
+    let (result, flag) = (v[0x9].wrapping_sub(v[0xA]), (v[0x9] >= v[0xA]) as u8);
+    v[0xA] = result;
+    v[0xF] = flag;
+    // 23E  OR VB, VA
+    v[0xB] |= v[0xA];
+    // 240  AND VC, VB
+    v[0xC] &= v[0xB];
+    // 242  XOR VD, VC
+    v[0xD] ^= v[0xC];
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    cpu.pc = 0x244;
+    out.executed = 8;
+    Ok(out)
+}
+
+fn block_244(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 244  ADD VF, VD
+    let (result, flag) = (v[0xF].wrapping_add(v[0xD]), v[0xF].checked_add(v[0xD]).is_none() as u8);
+    v[0xF] = result;
+    v[0xF] = flag;
+    // 246  ADD VE, 03
+    v[0xE] = v[0xE].wrapping_add(0x03);
+    // 248  ADD I, VE
+    i = i.wrapping_add(v[0xE] as u16);
+    // 24A  SE VE, V6
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    cpu.pc = if v[0xE] == v[0x6] { 0x24E } else { 0x24C };
+    out.executed = 4;
+    Ok(out)
+}
+
+fn block_24c(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 24C  SNE VE, V7
+    cpu.pc = if v[0xE] != v[0x7] { 0x250 } else { 0x24E };
+    out.executed = 1;
+    Ok(out)
+}
+
+fn block_24e(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 24E  LD DT, VE
+    cpu.set_delay_timer(v[0xE]);
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    cpu.pc = 0x250;
+    out.executed = 1;
+    Ok(out)
+}
+
+fn block_250(cpu: &mut MyChips8) -> Result<BlockOutcome, Chip8Error> {
+    let mut out = BlockOutcome::default();
+    let mut v = *cpu.registers();
+    let mut i = cpu.i();
+    // 250  LD V3, DT
+    v[0x3] = cpu.delay_timer();
+    // 252  RND V4, 0F
+    cpu.set_registers(v);
+    cpu.set_i(i);
+    out.executed = 1;
+    if out.interpret(cpu, 0x252, 0xC40F, Instruction::Rnd { x: 4, kk: 15 })? {
+        return Ok(out);
+    }
+    // 254  RET
+    if out.interpret(cpu, 0x254, 0x00EE, Instruction::Ret)? {
+        return Ok(out);
+    }
+    Ok(out)
+}
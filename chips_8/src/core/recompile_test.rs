@@ -0,0 +1,98 @@
+use super::*;
+use crate::core::machine::Machine;
+use crate::core::random::XorShift;
+
+// Generated by chip8-recompile, fixtures_are_up_to_date says when they need regenerating
+#[path = "./recompile_fixtures/ibm_logo.rs"]
+mod ibm_logo;
+#[path = "./recompile_fixtures/mixed.rs"]
+mod mixed;
+
+const IBM_LOGO: &[u8] = include_bytes!("../IBM_Logo.ch8");
+
+// JP V0 into the middle of a block, CALLs to a subroutine it then writes over with Fx55, and a loop
+// through every compiled arithmetic instruction plus I, the delay timer and RND
+const MIXED: &[u8] = &[
+    0x60, 0x02, 0x62, 0x02, 0xB2, 0x08, 0x00, 0x00, 0x00, 0x00, 0x22, 0x30, 0xA2, 0x30, 0x60, 0x75,
+    0x61, 0x01, 0xF1, 0x55, 0x22, 0x30, 0x45, 0x06, 0x12, 0x1C, 0x00, 0xFD, 0x22, 0x34, 0x12, 0x1C,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x65, 0x05, 0x00, 0xEE, 0x86, 0x04, 0x87, 0x65, 0x88, 0x76, 0x89, 0x8E, 0x8A, 0x97, 0x8B, 0xA1,
+    0x8C, 0xB2, 0x8D, 0xC3, 0x8F, 0xD4, 0x7E, 0x03, 0xFE, 0x1E, 0x5E, 0x60, 0x9E, 0x70, 0xFE, 0x15,
+    0xF3, 0x07, 0xC4, 0x0F, 0x00, 0xEE,
+];
+
+fn machine(rom: &[u8], quirks: Quirks, speed: u64, engine: Option<Recompiled>) -> Machine {
+    let mut cpu = MyChips8::new_with_quirks(quirks);
+    cpu.set_random_source(Box::new(XorShift::new(9)));
+    cpu.load_rom(rom).unwrap();
+    let mut machine = Machine::new_with_speed(cpu, speed);
+    machine.set_engine(engine.map(|engine| Box::new(engine) as Box<dyn Engine>));
+    machine
+}
+
+// Compiled blocks do not keep the last opcode, everything else has to match frame for frame
+fn assert_same_as_interpreter(rom: &[u8], quirks: Quirks, speed: u64, engine: Recompiled, frames: u64) -> Machine {
+    let mut plain = machine(rom, quirks, speed, None);
+    let mut compiled = machine(rom, quirks, speed, Some(engine));
+    for frame in 0..frames {
+        assert_eq!(plain.run_frame(), compiled.run_frame(), "frame {}", frame);
+        let (mut expected, mut actual) = (plain.cpu.snapshot(), compiled.cpu.snapshot());
+        expected.opcode = 0;
+        actual.opcode = 0;
+        assert_eq!(expected, actual, "frame {}", frame);
+    }
+    compiled
+}
+
+#[test]
+fn fixtures_are_up_to_date() {
+    assert_eq!(include_str!("./recompile_fixtures/ibm_logo.rs"), recompile(IBM_LOGO, Quirks::chip48()));
+    assert_eq!(include_str!("./recompile_fixtures/mixed.rs"), recompile(MIXED, Quirks::chip48()));
+}
+
+#[test]
+fn ibm_logo_matches_the_interpreter() {
+    for speed in [60, 700, 100_000] {
+        let machine = assert_same_as_interpreter(IBM_LOGO, Quirks::chip48(), speed, ibm_logo::engine(), 20);
+        assert_eq!(0x228, machine.cpu.pc);
+    }
+}
+
+#[test]
+fn mixed_program_matches_the_interpreter() {
+    for speed in [700, 5000] {
+        let machine = assert_same_as_interpreter(MIXED, Quirks::chip48(), speed, mixed::engine(), 120);
+        // The rewritten subroutine ran as ADD V5, 01, the stale compiled LD V5, 05 would have left 05
+        assert_eq!(0x06, machine.cpu.registers()[5]);
+        assert!(machine.cpu.pc >= 0x21C);
+    }
+}
+
+#[test]
+fn other_quirks_run_on_the_interpreter() {
+    assert_same_as_interpreter(MIXED, Quirks::cosmac_vip(), 700, mixed::engine(), 60);
+}
+
+#[test]
+fn stepping_matches_the_interpreter() {
+    let mut plain = machine(MIXED, Quirks::chip48(), 700, None);
+    let mut compiled = machine(MIXED, Quirks::chip48(), 700, Some(mixed::engine()));
+    for _ in 0..300 {
+        assert_eq!(plain.step(), compiled.step());
+        assert_eq!((plain.cpu.pc, plain.cpu.registers()), (compiled.cpu.pc, compiled.cpu.registers()));
+    }
+}
+
+#[test]
+fn only_register_instructions_are_compiled() {
+    let module = recompile(MIXED, Quirks::chip48());
+    assert!(module.contains("v[0x7] = result;"));
+    assert!(module.contains("cpu.set_delay_timer(v[0xE]);"));
+    assert!(module.contains("Instruction::JpV0 { nnn: 520 }"));
+    assert!(module.contains("Instruction::LdIVx { x: 1 }"));
+    assert!(module.contains("Instruction::Rnd { x: 4, kk: 15 }"));
+
+    // Skip distance depends on what follows under XO-CHIP
+    let module = recompile(MIXED, Quirks::xo_chip());
+    assert!(module.contains("Instruction::SneByte { x: 5, kk: 6 }"));
+}
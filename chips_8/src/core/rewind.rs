@@ -0,0 +1,142 @@
+use std::collections::VecDeque;
+
+use super::machine::FRAMES_PER_SECOND;
+use super::savestate::SaveState;
+
+// Keeps the last few seconds of save states so the front end can step backward a frame at a time
+// Only the newest state is stored in full, every older one is a delta against the state after it,
+// consecutive frames barely differ so the deltas are mostly runs of zeroes
+pub struct RewindBuffer {
+    capacity: usize,              // Most states held, counting the newest
+    newest: Option<Vec<u8>>,      // SaveState::to_bytes() of the latest recorded frame
+    deltas: VecDeque<Vec<u8>>,    // Back is the delta to the frame before newest, front is the oldest
+}
+
+impl RewindBuffer {
+    pub fn new(seconds: u64) -> RewindBuffer {
+        RewindBuffer::with_capacity((seconds * FRAMES_PER_SECOND).max(1) as usize)
+    }
+
+    pub fn with_capacity(capacity: usize) -> RewindBuffer {
+        RewindBuffer { capacity: capacity.max(1), newest: None, deltas: VecDeque::new() }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // States that can still be stepped back to, including the newest
+    pub fn len(&self) -> usize {
+        self.newest.as_ref().map_or(0, |_| self.deltas.len() + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.newest.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+    }
+
+    // Meant to be called once per frame, the oldest frame drops off once the buffer is full
+    pub fn record(&mut self, state: &SaveState) {
+        let bytes = state.to_bytes();
+        if let Some(previous) = self.newest.take() {
+            self.deltas.push_back(encode_delta(&bytes, &previous));
+            if self.deltas.len() >= self.capacity {
+                self.deltas.pop_front();
+            }
+        }
+        self.newest = Some(bytes);
+    }
+
+    // Drops the newest state and hands back the one recorded before it, None once nothing older is left
+    pub fn step_back(&mut self) -> Option<SaveState> {
+        let delta = self.deltas.pop_back()?;
+        let newest = self.newest.as_ref()?;
+        let previous = apply_delta(newest, &delta);
+        let state = SaveState::from_bytes(&previous).ok();
+        self.newest = Some(previous);
+        state
+    }
+}
+
+// Delta from newer back to older: the older length followed by the XOR of both, run length encoded
+// Resolution changes alter the state size, the shorter side is treated as zero padded
+fn encode_delta(newer: &[u8], older: &[u8]) -> Vec<u8> {
+    let len = newer.len().max(older.len());
+    let xor = (0..len).map(|idx| newer.get(idx).unwrap_or(&0) ^ older.get(idx).unwrap_or(&0));
+
+    let mut delta = Vec::new();
+    put_varint(&mut delta, older.len());
+    encode_runs(&mut delta, &xor.collect::<Vec<u8>>());
+    delta
+}
+
+fn apply_delta(newer: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut offset = 0;
+    let older_len = get_varint(delta, &mut offset);
+    let xor = decode_runs(&delta[offset..]);
+
+    (0..older_len).map(|idx| newer.get(idx).unwrap_or(&0) ^ xor.get(idx).unwrap_or(&0)).collect()
+}
+
+// Alternating (zero count, literal count, literals) groups until the input runs out
+fn encode_runs(out: &mut Vec<u8>, bytes: &[u8]) {
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let zeros_start = idx;
+        while idx < bytes.len() && bytes[idx] == 0 {
+            idx += 1;
+        }
+        let literals_start = idx;
+        // A lone zero between changed bytes is cheaper kept as a literal than as a new group
+        while idx < bytes.len() && (bytes[idx] != 0 || bytes.get(idx + 1).is_some_and(|&next| next != 0)) {
+            idx += 1;
+        }
+        put_varint(out, literals_start - zeros_start);
+        put_varint(out, idx - literals_start);
+        out.extend_from_slice(&bytes[literals_start..idx]);
+    }
+}
+
+fn decode_runs(runs: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut offset = 0;
+    while offset < runs.len() {
+        let zeros = get_varint(runs, &mut offset);
+        let literals = get_varint(runs, &mut offset);
+        bytes.resize(bytes.len() + zeros, 0);
+        bytes.extend_from_slice(&runs[offset..offset + literals]);
+        offset += literals;
+    }
+    bytes
+}
+
+// LEB128, 7 bits per byte with the top bit marking that more follow
+fn put_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn get_varint(bytes: &[u8], offset: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*offset];
+        *offset += 1;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+#[path ="./rewind_test.rs"]
+mod rewind_test;
@@ -0,0 +1,99 @@
+use super::*;
+use crate::core::machine::Machine;
+use crate::core::ops::MyChips8;
+
+// ADD V0, 01 / LD F, V0 / DRW V1, V1, 5 / JP 200
+const COUNTER: [u8; 8] = [0x70, 0x01, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x00];
+
+fn counting_machine() -> Machine {
+    let mut cpu = MyChips8::new();
+    cpu.load_rom(&COUNTER).unwrap();
+    Machine::new_with_speed(cpu, 240)
+}
+
+#[test]
+fn steps_back_through_recorded_frames() {
+    let mut machine = counting_machine();
+    let mut rewind = RewindBuffer::new(1);
+    let mut states = Vec::new();
+
+    for _ in 0..10 {
+        machine.run_frame().unwrap();
+        states.push(machine.cpu.snapshot());
+        rewind.record(states.last().unwrap());
+    }
+    assert_eq!(10, rewind.len());
+
+    // The newest state is where the machine already is, stepping back starts at the one before
+    for expected in states.iter().rev().skip(1) {
+        assert_eq!(Some(expected), rewind.step_back().as_ref());
+    }
+    assert_eq!(None, rewind.step_back());
+    assert_eq!(1, rewind.len());
+}
+
+#[test]
+fn oldest_frames_drop_off() {
+    let mut machine = counting_machine();
+    let mut rewind = RewindBuffer::with_capacity(4);
+    let mut states = Vec::new();
+
+    for _ in 0..10 {
+        machine.run_frame().unwrap();
+        states.push(machine.cpu.snapshot());
+        rewind.record(states.last().unwrap());
+    }
+    assert_eq!(4, rewind.len());
+    assert_eq!(60, RewindBuffer::new(1).capacity());
+
+    (0..3).for_each(|_| { rewind.step_back().unwrap(); });
+    assert_eq!(None, rewind.step_back());
+
+    // Recording after a rewind carries on from the restored point
+    rewind.record(&states[9]);
+    assert_eq!(Some(&states[6]), rewind.step_back().as_ref());
+}
+
+#[test]
+fn deltas_survive_resolution_changes() {
+    let mut cpu = MyChips8::new();
+    let lores = cpu.snapshot();
+    cpu.gfx.set_hires(true);
+    cpu.gfx.toggle(127, 63, 0x1);
+    let hires = cpu.snapshot();
+
+    let mut rewind = RewindBuffer::with_capacity(8);
+    rewind.record(&lores);
+    rewind.record(&hires);
+    rewind.record(&lores);
+    assert_eq!(Some(hires), rewind.step_back());
+    assert_eq!(Some(lores), rewind.step_back());
+}
+
+#[test]
+fn deltas_are_compact() {
+    let mut machine = counting_machine();
+    machine.run_frame().unwrap();
+    let older = machine.cpu.snapshot().to_bytes();
+    machine.run_frame().unwrap();
+    let newer = machine.cpu.snapshot().to_bytes();
+
+    let delta = encode_delta(&newer, &older);
+    assert!(delta.len() < 100, "delta was {} bytes", delta.len());
+    assert_eq!(older, apply_delta(&newer, &delta));
+    assert_eq!(newer, apply_delta(&newer, &encode_delta(&newer, &newer)));
+}
+
+#[test]
+fn run_length_round_trip() {
+    let bytes = [0, 0, 0, 1, 2, 0, 3, 0, 0, 0, 4, 0, 0];
+    let mut runs = Vec::new();
+    encode_runs(&mut runs, &bytes);
+    assert_eq!(&bytes[..], &decode_runs(&runs)[..]);
+
+    let long_zeros = vec![0; 300];
+    let mut runs = Vec::new();
+    encode_runs(&mut runs, &long_zeros);
+    assert_eq!(vec![0xAC, 0x02, 0x00], runs);
+    assert_eq!(long_zeros, decode_runs(&runs));
+}
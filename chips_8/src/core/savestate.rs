@@ -0,0 +1,246 @@
+use std::fmt::Display;
+
+use super::framebuffer::FrameBuffer;
+use super::ops::{KeyWait, PROGRAM_START};
+use super::quirks::Quirks;
+
+// Every state file starts with the magic followed by a big endian format version
+const MAGIC: &[u8; 4] = b"CH8S";
+pub const SAVE_STATE_VERSION: u16 = 2;
+
+// Everything needed to put a MyChips8 back exactly where it was, the random source is not included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveState {
+    pub(crate) quirks: Quirks,
+    pub(crate) opcode: u16,
+    pub(crate) pc: u16,
+    pub(crate) i: u16,
+    pub(crate) sp: u16,
+    pub(crate) registers: [u8; 0x10],
+    pub(crate) stack: [u16; 0x10],
+    pub(crate) delay_timer: u8,
+    pub(crate) sound_timer: u8,
+    pub(crate) buzzer: bool,
+    pub(crate) key: [u8; 0x10],
+    pub(crate) rpl: [u8; 0x10],
+    pub(crate) audio_pattern: [u8; 0x10],
+    pub(crate) pitch: u8,
+    pub(crate) vblank: bool,
+    pub(crate) key_wait: KeyWait,
+    pub(crate) gfx: FrameBuffer,
+    pub(crate) memory: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveStateError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    Truncated,
+    Corrupt(&'static str),
+}
+
+impl Display for SaveStateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveStateError::BadMagic => write!(f, "Not a save state"),
+            SaveStateError::UnsupportedVersion(version) => write!(f, "Unsupported save state version {}", version),
+            SaveStateError::Truncated => write!(f, "Save state is truncated"),
+            SaveStateError::Corrupt(reason) => write!(f, "Save state is corrupt: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SaveStateError {}
+
+impl SaveState {
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    // Version 2 layout, all multi byte values are big endian. Version 1 is the same without the buzzer:
+    //   magic, version, quirk bits, opcode, pc, i, sp, V0-VF, stack, DT, ST, buzzer, keys, RPL flags,
+    //   audio pattern, pitch, vblank, key wait (tag, x, key), hires, planes, pixel count, pixels,
+    //   memory size, memory
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.memory.len() + self.gfx.pixels().len() + 0x100);
+        bytes.extend_from_slice(MAGIC);
+        put_u16(&mut bytes, SAVE_STATE_VERSION);
+        bytes.push(quirks_to_bits(self.quirks));
+        [self.opcode, self.pc, self.i, self.sp].iter().for_each(|value| put_u16(&mut bytes, *value));
+        bytes.extend_from_slice(&self.registers);
+        self.stack.iter().for_each(|value| put_u16(&mut bytes, *value));
+        bytes.push(self.delay_timer);
+        bytes.push(self.sound_timer);
+        bytes.push(self.buzzer as u8);
+        bytes.extend_from_slice(&self.key);
+        bytes.extend_from_slice(&self.rpl);
+        bytes.extend_from_slice(&self.audio_pattern);
+        bytes.push(self.pitch);
+        bytes.push(self.vblank as u8);
+        bytes.extend_from_slice(&match self.key_wait {
+            KeyWait::Idle => [0x0, 0x0, 0x0],
+            KeyWait::Press { x } => [0x1, x, 0x0],
+            KeyWait::Release { x, key } => [0x2, x, key],
+        });
+        bytes.push(self.gfx.is_hires() as u8);
+        bytes.push(self.gfx.selected_planes());
+        put_u32(&mut bytes, self.gfx.pixels().len() as u32);
+        bytes.extend_from_slice(self.gfx.pixels());
+        put_u32(&mut bytes, self.memory.len() as u32);
+        bytes.extend_from_slice(&self.memory);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<SaveState, SaveStateError> {
+        let mut reader = Reader { bytes, offset: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(SaveStateError::BadMagic);
+        }
+        let version = reader.u16()?;
+        if !(1..=SAVE_STATE_VERSION).contains(&version) {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+
+        let quirks = quirks_from_bits(reader.u8()?);
+        let opcode = reader.u16()?;
+        let pc = reader.u16()?;
+        let i = reader.u16()?;
+        let sp = reader.u16()?;
+        let registers = reader.array()?;
+        let mut stack = [0x0; 0x10];
+        for entry in stack.iter_mut() {
+            *entry = reader.u16()?;
+        }
+        let delay_timer = reader.u8()?;
+        let sound_timer = reader.u8()?;
+        // Version 1 states were saved before the buzzer was, it comes back off like it did then
+        let buzzer = version >= 2 && reader.u8()? != 0;
+        let key = reader.array()?;
+        let rpl = reader.array()?;
+        let audio_pattern = reader.array()?;
+        let pitch = reader.u8()?;
+        let vblank = reader.u8()? != 0;
+        let key_wait = match (reader.u8()?, reader.u8()?, reader.u8()?) {
+            (0x0, _, _) => KeyWait::Idle,
+            (0x1, x, _) => KeyWait::Press { x },
+            (0x2, x, key) => KeyWait::Release { x, key },
+            _ => return Err(SaveStateError::Corrupt("unknown key wait state")),
+        };
+        let hires = reader.u8()? != 0;
+        let selected_planes = reader.u8()?;
+        let pixel_count = reader.u32()? as usize;
+        let pixels = reader.take(pixel_count)?.to_vec();
+        let gfx = FrameBuffer::from_pixels(hires, selected_planes, pixels)
+            .ok_or(SaveStateError::Corrupt("display size does not match its resolution"))?;
+        let memory_size = reader.u32()? as usize;
+        let memory = reader.take(memory_size)?.to_vec();
+
+        if reader.offset != bytes.len() {
+            return Err(SaveStateError::Corrupt("trailing bytes"));
+        }
+        if sp as usize > stack.len() {
+            return Err(SaveStateError::Corrupt("stack pointer out of range"));
+        }
+        // Front ends can put memory of any size behind the bus, as long as programs still fit
+        if memory.len() <= PROGRAM_START {
+            return Err(SaveStateError::Corrupt("memory ends before the program start"));
+        }
+        if key_wait_out_of_range(key_wait) {
+            return Err(SaveStateError::Corrupt("key wait register out of range"));
+        }
+
+        Ok(SaveState {
+            quirks, opcode, pc, i, sp, registers, stack, delay_timer, sound_timer, buzzer, key, rpl,
+            audio_pattern, pitch, vblank, key_wait, gfx, memory,
+        })
+    }
+}
+
+fn key_wait_out_of_range(key_wait: KeyWait) -> bool {
+    match key_wait {
+        KeyWait::Idle => false,
+        KeyWait::Press { x } => x > 0xF,
+        KeyWait::Release { x, key } => x > 0xF || key > 0xF,
+    }
+}
+
+// One bit per quirk in declaration order, new quirks are only ever appended
+pub(crate) fn quirks_to_bits(quirks: Quirks) -> u8 {
+    [
+        quirks.shift_uses_vy,
+        quirks.load_store_increments_i,
+        quirks.jump_uses_vx,
+        quirks.logic_resets_vf,
+        quirks.sprites_wrap,
+        quirks.display_wait,
+        quirks.xo_chip,
+        quirks.load_store_i_on_last,
+    ]
+    .iter()
+    .enumerate()
+    .fold(0, |bits, (idx, &set)| bits | (set as u8) << idx)
+}
+
+pub(crate) fn quirks_from_bits(bits: u8) -> Quirks {
+    let bit = |idx: u8| bits & (1 << idx) != 0;
+    Quirks {
+        shift_uses_vy: bit(0),
+        load_store_increments_i: bit(1),
+        jump_uses_vx: bit(2),
+        logic_resets_vf: bit(3),
+        sprites_wrap: bit(4),
+        display_wait: bit(5),
+        xo_chip: bit(6),
+        load_store_i_on_last: bit(7),
+    }
+}
+
+fn put_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_be_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SaveStateError> {
+        let end = self.offset.checked_add(len).ok_or(SaveStateError::Truncated)?;
+        let slice = self.bytes.get(self.offset..end).ok_or(SaveStateError::Truncated)?;
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, SaveStateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SaveStateError> {
+        let slice = self.take(2)?;
+        Ok(u16::from_be_bytes([slice[0], slice[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, SaveStateError> {
+        let slice = self.take(4)?;
+        Ok(u32::from_be_bytes([slice[0], slice[1], slice[2], slice[3]]))
+    }
+
+    fn array(&mut self) -> Result<[u8; 0x10], SaveStateError> {
+        let mut array = [0x0; 0x10];
+        array.copy_from_slice(self.take(0x10)?);
+        Ok(array)
+    }
+}
+
+#[cfg(test)]
+#[path ="./savestate_test.rs"]
+mod savestate_test;
@@ -0,0 +1,98 @@
+use super::*;
+use crate::core::ops::MyChips8;
+
+// LD V0, 05 / LD DT, V0 / CALL 20A / LD V1, K / JP 208 / HIGH / DRW V0, V0, 0 / RET
+const ROM: [u8; 16] = [0x60, 0x05, 0xF0, 0x15, 0x22, 0x0A, 0xF1, 0x0A, 0x12, 0x08, 0x00, 0xFF, 0xD0, 0x00, 0x00, 0xEE];
+
+fn running_chip_8(quirks: Quirks) -> MyChips8 {
+    let mut my_chip_8 = MyChips8::new_with_quirks(quirks);
+    my_chip_8.load_rom(&ROM).unwrap();
+    (0..5).for_each(|_| { my_chip_8.enumlate_cycle().unwrap(); });
+    my_chip_8.press_key(0x3);
+    my_chip_8
+}
+
+#[test]
+fn snapshot_restores_the_machine() {
+    let mut my_chip_8 = running_chip_8(Quirks::default());
+    let state = my_chip_8.snapshot();
+
+    // Run on past the snapshot, then go back
+    (0..3).for_each(|_| { my_chip_8.enumlate_cycle().unwrap(); });
+    my_chip_8.release_key(0x3);
+    my_chip_8.tick_timers();
+    my_chip_8.gfx.set_hires(false);
+    my_chip_8.restore(&state);
+
+    assert_eq!(state, my_chip_8.snapshot());
+    assert_eq!(0x5, my_chip_8.delay_timer());
+    assert!(my_chip_8.is_key_pressed(0x3));
+    assert!(my_chip_8.gfx.is_hires());
+}
+
+#[test]
+fn round_trips_through_bytes() {
+    let state = running_chip_8(Quirks::xo_chip()).snapshot();
+    let bytes = state.to_bytes();
+
+    assert_eq!(b"CH8S", &bytes[0..4]);
+    assert_eq!(SAVE_STATE_VERSION.to_be_bytes(), bytes[4..6]);
+    assert_eq!(Ok(state.clone()), SaveState::from_bytes(&bytes));
+    assert_eq!(Quirks::xo_chip(), state.quirks());
+}
+
+#[test]
+fn rejects_bad_input() {
+    let bytes = running_chip_8(Quirks::default()).snapshot().to_bytes();
+
+    assert_eq!(Err(SaveStateError::BadMagic), SaveState::from_bytes(b"CH8X\x00\x01"));
+    assert_eq!(Err(SaveStateError::Truncated), SaveState::from_bytes(&bytes[0..2]));
+    assert_eq!(Err(SaveStateError::Truncated), SaveState::from_bytes(&bytes[0..bytes.len() - 1]));
+
+    let mut future = bytes.clone();
+    future[5] = 0x3;
+    assert_eq!(Err(SaveStateError::UnsupportedVersion(3)), SaveState::from_bytes(&future));
+    future[5] = 0x0;
+    assert_eq!(Err(SaveStateError::UnsupportedVersion(0)), SaveState::from_bytes(&future));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0x0);
+    assert!(matches!(SaveState::from_bytes(&trailing), Err(SaveStateError::Corrupt(_))));
+
+    // Not even room for a program
+    let mut state = SaveState::from_bytes(&bytes).unwrap();
+    state.memory.truncate(0x200);
+    assert!(matches!(SaveState::from_bytes(&state.to_bytes()), Err(SaveStateError::Corrupt(_))));
+}
+
+#[test]
+fn restores_the_buzzer() {
+    let mut my_chip_8 = running_chip_8(Quirks::default());
+    my_chip_8.set_sound_timer(1);
+    my_chip_8.tick_timers();
+    assert!(my_chip_8.is_buzzer_on());
+    let state = SaveState::from_bytes(&my_chip_8.snapshot().to_bytes()).unwrap();
+
+    // Sound timer ran out, then back to the frame the buzzer sounded for
+    my_chip_8.tick_timers();
+    assert!(!my_chip_8.is_buzzer_on());
+    my_chip_8.restore(&state);
+    assert!(my_chip_8.is_buzzer_on());
+}
+
+#[test]
+fn loads_version_1_states() {
+    let mut my_chip_8 = running_chip_8(Quirks::default());
+    my_chip_8.set_sound_timer(1);
+    my_chip_8.tick_timers();
+    let state = my_chip_8.snapshot();
+
+    // Version 1 had no buzzer byte, it went straight from ST to the keys
+    let mut bytes = state.to_bytes();
+    bytes[5] = 0x1;
+    assert_eq!(0x01, bytes.remove(65));
+    let old = SaveState::from_bytes(&bytes).unwrap();
+
+    assert!(!old.buzzer);
+    assert_eq!(SaveState { buzzer: false, ..state }, old);
+}
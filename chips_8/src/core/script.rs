@@ -0,0 +1,101 @@
+use std::fmt::Display;
+
+use super::ops::MyChips8;
+
+// Keypad input fed to a run frame by frame, for headless runs and anything else that cannot take a keyboard
+// One event per line, `<frame> down|up <key>` with the key in hex, '#' starts a comment:
+//   # press 5 on frame 120 and let go ten frames later
+//   120 down 5
+//   130 up 5
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub frame: u64,
+    pub key: u8,
+    pub pressed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KeyScript {
+    events: Vec<KeyEvent>, // Sorted by frame, events on the same frame keep their file order
+    next: usize,           // First event not applied yet
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+impl KeyScript {
+    pub fn new(mut events: Vec<KeyEvent>) -> KeyScript {
+        events.sort_by_key(|event| event.frame);
+        KeyScript { events, next: 0 }
+    }
+
+    pub fn parse(source: &str) -> Result<KeyScript, ScriptError> {
+        let mut events = Vec::new();
+        for (idx, line) in source.lines().enumerate() {
+            let line_number = idx + 1;
+            let error = |message: String| ScriptError { line: line_number, message };
+            let words: Vec<&str> = line.split('#').next().unwrap_or("").split_whitespace().collect();
+            if words.is_empty() {
+                continue;
+            }
+            events.push(parse_event(&words).map_err(error)?);
+        }
+        Ok(KeyScript::new(events))
+    }
+
+    pub fn events(&self) -> &[KeyEvent] {
+        &self.events
+    }
+
+    // Presses and releases everything scheduled up to and including this frame
+    pub fn apply(&mut self, frame: u64, cpu: &mut MyChips8) {
+        while let Some(event) = self.events.get(self.next).copied().filter(|event| event.frame <= frame) {
+            if event.pressed {
+                cpu.press_key(event.key);
+            } else {
+                cpu.release_key(event.key);
+            }
+            self.next += 1;
+        }
+    }
+}
+
+// `<frame> down|up <key>` split into words, shared with movie files
+pub(crate) fn parse_event(words: &[&str]) -> Result<KeyEvent, String> {
+    if words.len() != 3 {
+        return Err(format!("expected `<frame> down|up <key>`, found `{}`", words.join(" ")));
+    }
+
+    let frame = words[0].parse::<u64>().map_err(|_| format!("bad frame number `{}`", words[0]))?;
+    let pressed = match words[1] {
+        "down" => true,
+        "up" => false,
+        other => return Err(format!("expected down or up, found `{}`", other)),
+    };
+    let key = match u8::from_str_radix(words[2].trim_start_matches("0x"), 16) {
+        Ok(key) if key <= 0xF => key,
+        _ => return Err(format!("bad key `{}`, keys go from 0 to F", words[2])),
+    };
+    Ok(KeyEvent { frame, key, pressed })
+}
+
+impl Display for KeyEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {:X}", self.frame, if self.pressed { "down" } else { "up" }, self.key)
+    }
+}
+
+#[cfg(test)]
+#[path ="./script_test.rs"]
+mod script_test;
@@ -0,0 +1,39 @@
+use super::*;
+
+#[test]
+fn parses_events_in_frame_order() {
+    let script = KeyScript::parse("# start\n30 up 0xA\n\n10 down A  # hold A\n10 down f\n").unwrap();
+
+    assert_eq!(
+        &[
+            KeyEvent { frame: 10, key: 0xA, pressed: true },
+            KeyEvent { frame: 10, key: 0xF, pressed: true },
+            KeyEvent { frame: 30, key: 0xA, pressed: false },
+        ],
+        script.events()
+    );
+}
+
+#[test]
+fn reports_the_bad_line() {
+    assert_eq!(2, KeyScript::parse("1 down 1\n2 press 1").unwrap_err().line);
+    assert_eq!(1, KeyScript::parse("x down 1").unwrap_err().line);
+    assert_eq!(3, KeyScript::parse("\n\n1 down 10").unwrap_err().line);
+    assert_eq!(1, KeyScript::parse("1 down").unwrap_err().line);
+}
+
+#[test]
+fn applies_events_as_frames_pass() {
+    let mut script = KeyScript::parse("2 down 5\n4 up 5").unwrap();
+    let mut cpu = MyChips8::new();
+
+    script.apply(1, &mut cpu);
+    assert!(!cpu.is_key_pressed(0x5));
+    script.apply(2, &mut cpu);
+    assert!(cpu.is_key_pressed(0x5));
+    script.apply(3, &mut cpu);
+    assert!(cpu.is_key_pressed(0x5));
+    // Skipping frames still catches up on everything that was due
+    script.apply(10, &mut cpu);
+    assert!(!cpu.is_key_pressed(0x5));
+}
@@ -0,0 +1,120 @@
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use super::debugger::Register;
+use super::instruction::Instruction;
+
+// A register an instruction changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterChange {
+    pub register: Register,
+    pub before: u16,
+    pub after: u16,
+}
+
+// One executed instruction, registers and I are the values after it ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: u16,
+    pub opcode: u16,
+    pub instruction: Instruction,
+    pub registers: [u8; 0x10],
+    pub i: u16,
+    pub changes: Vec<RegisterChange>,
+}
+
+impl TraceEntry {
+    pub(crate) fn new(pc: u16, opcode: u16, instruction: Instruction, before: ([u8; 0x10], u16), after: ([u8; 0x10], u16)) -> TraceEntry {
+        let mut changes: Vec<RegisterChange> = (0..0x10)
+            .filter(|x| before.0[*x] != after.0[*x])
+            .map(|x| RegisterChange { register: Register::V(x as u8), before: before.0[x] as u16, after: after.0[x] as u16 })
+            .collect();
+        if before.1 != after.1 {
+            changes.push(RegisterChange { register: Register::I, before: before.1, after: after.1 });
+        }
+        TraceEntry { pc, opcode, instruction, registers: after.0, i: after.1, changes }
+    }
+}
+
+// One line per instruction, fixed width up to the mnemonic so traces line up under diff:
+//   0204 600C I:022A V:0C 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 LD V0, 0C | V0 00>0C
+// PC, opcode, I and V0-VF after the instruction, then the instruction and what it changed
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let registers: Vec<String> = self.registers.iter().map(|value| format!("{:02X}", value)).collect();
+        write!(f, "{:04X} {:04X} I:{:04X} V:{} {}", self.pc, self.opcode, self.i, registers.join(" "), self.instruction)?;
+        if !self.changes.is_empty() {
+            let changes: Vec<String> = self
+                .changes
+                .iter()
+                .map(|change| match change.register {
+                    Register::V(_) => format!("{} {:02X}>{:02X}", change.register, change.before, change.after),
+                    _ => format!("{} {:04X}>{:04X}", change.register, change.before, change.after),
+                })
+                .collect();
+            write!(f, " | {}", changes.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// Called by the CPU after every instruction it executes, nothing is recorded while none is set
+pub trait Tracer {
+    fn trace(&mut self, entry: &TraceEntry);
+
+    // Reports anything that went wrong while tracing, called once the run is over
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<F: FnMut(&TraceEntry)> Tracer for F {
+    fn trace(&mut self, entry: &TraceEntry) {
+        self(entry)
+    }
+}
+
+// Writes each entry as a line, the first write error stops the trace and is kept for flush()
+pub struct TraceWriter<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl TraceWriter<BufWriter<File>> {
+    pub fn create(path: &Path) -> io::Result<TraceWriter<BufWriter<File>>> {
+        Ok(TraceWriter::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub fn new(writer: W) -> TraceWriter<W> {
+        TraceWriter { writer, error: None }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Tracer for TraceWriter<W> {
+    fn trace(&mut self, entry: &TraceEntry) {
+        if self.error.is_none() {
+            if let Err(err) = writeln!(self.writer, "{}", entry) {
+                self.error = Some(err);
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.writer.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path ="./trace_test.rs"]
+mod trace_test;
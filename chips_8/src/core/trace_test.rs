@@ -0,0 +1,52 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::*;
+use crate::core::ops::MyChips8;
+
+fn traced(rom: &[u8], steps: usize) -> Vec<TraceEntry> {
+    let entries = Rc::new(RefCell::new(Vec::new()));
+    let sink = Rc::clone(&entries);
+    let mut cpu = MyChips8::new();
+    cpu.load_rom(rom).unwrap();
+    cpu.set_tracer(Box::new(move |entry: &TraceEntry| sink.borrow_mut().push(entry.clone())));
+    for _ in 0..steps {
+        cpu.enumlate_cycle().unwrap();
+    }
+    let entries = entries.borrow().clone();
+    entries
+}
+
+#[test]
+fn records_every_executed_instruction() {
+    // LD V0, 0C / LD I, 22A / ADD V0, V0
+    let entries = traced(&[0x60, 0x0C, 0xA2, 0x2A, 0x80, 0x04], 3);
+
+    assert_eq!(vec![0x200, 0x202, 0x204], entries.iter().map(|entry| entry.pc).collect::<Vec<u16>>());
+    assert_eq!(Instruction::LdI { nnn: 0x22A }, entries[1].instruction);
+    assert_eq!(vec![RegisterChange { register: Register::V(0), before: 0x00, after: 0x0C }], entries[0].changes);
+    assert_eq!(vec![RegisterChange { register: Register::I, before: 0x000, after: 0x22A }], entries[1].changes);
+    assert_eq!((0x18, 0x22A), (entries[2].registers[0], entries[2].i));
+}
+
+#[test]
+fn skips_instructions_that_did_not_run() {
+    // LD V0, K waits, the wait itself is not an instruction
+    let entries = traced(&[0xF0, 0x0A], 5);
+
+    assert_eq!(1, entries.len());
+}
+
+#[test]
+fn writes_stable_lines() {
+    let entries = traced(&[0x60, 0x0C, 0x00, 0xE0], 2);
+    let mut writer = TraceWriter::new(Vec::new());
+    entries.iter().for_each(|entry| writer.trace(entry));
+    writer.flush().unwrap();
+
+    assert_eq!(
+        "0200 600C I:0000 V:0C 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 LD V0, 0C | V0 00>0C\n\
+         0202 00E0 I:0000 V:0C 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 CLS\n",
+        String::from_utf8(writer.into_inner()).unwrap()
+    );
+}
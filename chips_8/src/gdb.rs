@@ -0,0 +1,335 @@
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+
+use crate::core::debugger::{Breakpoint, Debugger, Stop};
+use crate::core::error::Chip8Error;
+
+// GDB remote serial protocol stub, lets gdb (or anything else speaking RSP) attach over TCP
+//
+// There is no CHIP-8 architecture in gdb, so the register file is laid out as raw bytes:
+//   0-15  V0-VF  1 byte each
+//   16    I      2 bytes, little endian
+//   17    PC     2 bytes, little endian
+//   18    DT     1 byte
+//   19    ST     1 byte
+// `monitor key down K` / `monitor key up K` drive the keypad while the target is stopped
+
+const REGISTER_COUNT: usize = 20;
+const SIGINT: u8 = 0x02;
+const SIGILL: u8 = 0x04;
+const SIGTRAP: u8 = 0x05;
+const SIGSEGV: u8 = 0x0B;
+
+// Instructions run between checks for a ctrl-c from the client while continuing
+const CONTINUE_CHUNK: u64 = 10_000;
+
+pub struct GdbStub {
+    pub debugger: Debugger,
+    last_signal: u8,
+}
+
+impl GdbStub {
+    pub fn new(debugger: Debugger) -> GdbStub {
+        GdbStub { debugger, last_signal: SIGTRAP }
+    }
+
+    // Answers one packet payload, None means the session is over (detach or kill)
+    // interrupted is polled while continuing and returns true once the client asks to stop
+    pub fn handle_packet(&mut self, packet: &str, interrupted: &mut dyn FnMut() -> bool) -> Option<String> {
+        // Payloads come through from_utf8_lossy, the first character need not be a single byte
+        let command = packet.chars().next().unwrap_or('\0');
+        let args = &packet[command.len_utf8().min(packet.len())..];
+        let reply = match command {
+            '?' => format!("S{:02x}", self.last_signal),
+            'g' => self.read_registers(),
+            'G' => ok_or_error(self.write_registers(args)),
+            'p' => usize::from_str_radix(args, 16).ok().and_then(|register| self.read_register(register)).unwrap_or_else(|| "E01".to_string()),
+            'P' => ok_or_error(self.write_register(args)),
+            'm' => self.read_memory(args).unwrap_or_else(|| "E01".to_string()),
+            'M' => ok_or_error(self.write_memory(args)),
+            's' => {
+                let result = self.debugger.step().map(|_| Stop::StepLimit);
+                self.stop_reply(result)
+            }
+            'c' => self.resume(interrupted),
+            'Z' => ok_or_error(self.breakpoint(args, true)),
+            'z' => ok_or_error(self.breakpoint(args, false)),
+            'H' => "OK".to_string(),
+            'D' | 'k' => return None,
+            'q' => self.query(args),
+            _ => String::new(),
+        };
+        Some(reply)
+    }
+
+    fn query(&mut self, args: &str) -> String {
+        match args.split(':').next().unwrap_or("") {
+            "Supported" => "PacketSize=4000".to_string(),
+            "Attached" => "1".to_string(),
+            "C" => "QC1".to_string(),
+            "fThreadInfo" => "m1".to_string(),
+            "sThreadInfo" => "l".to_string(),
+            _ if args.starts_with("Rcmd,") => self.monitor(&args["Rcmd,".len()..]),
+            _ => String::new(),
+        }
+    }
+
+    // `monitor` commands arrive hex encoded
+    fn monitor(&mut self, hex: &str) -> String {
+        let command = match decode_hex(hex).and_then(|bytes| String::from_utf8(bytes).ok()) {
+            Some(command) => command,
+            None => return "E01".to_string(),
+        };
+        let words: Vec<&str> = command.split_whitespace().collect();
+        match words.as_slice() {
+            ["key", state, key] => {
+                let key = match u8::from_str_radix(key.trim_start_matches("0x"), 16) {
+                    Ok(key) if key <= 0xF => key,
+                    _ => return "E01".to_string(),
+                };
+                match *state {
+                    "down" => self.debugger.machine.cpu.press_key(key),
+                    "up" => self.debugger.machine.cpu.release_key(key),
+                    _ => return "E01".to_string(),
+                }
+                "OK".to_string()
+            }
+            _ => encode_hex("Unknown monitor command, try `key down K` or `key up K`\n".as_bytes()),
+        }
+    }
+
+    fn resume(&mut self, interrupted: &mut dyn FnMut() -> bool) -> String {
+        loop {
+            match self.debugger.run(CONTINUE_CHUNK) {
+                Ok(Stop::StepLimit) if interrupted() => {
+                    self.last_signal = SIGINT;
+                    return format!("S{:02x}", SIGINT);
+                }
+                Ok(Stop::StepLimit) => {}
+                result => return self.stop_reply(result),
+            }
+        }
+    }
+
+    fn stop_reply(&mut self, result: Result<Stop, Chip8Error>) -> String {
+        self.last_signal = match result {
+            Ok(Stop::Exited) => return "W00".to_string(),
+            Ok(_) => SIGTRAP,
+            Err(Chip8Error::UnknownOpcode { .. }) => SIGILL,
+            Err(_) => SIGSEGV,
+        };
+        format!("S{:02x}", self.last_signal)
+    }
+
+    fn register_bytes(&self, register: usize) -> Option<Vec<u8>> {
+        let cpu = &self.debugger.machine.cpu;
+        match register {
+            0..=15 => Some(vec![cpu.registers()[register]]),
+            16 => Some(cpu.i().to_le_bytes().to_vec()),
+            17 => Some(cpu.pc.to_le_bytes().to_vec()),
+            18 => Some(vec![cpu.delay_timer()]),
+            19 => Some(vec![cpu.sound_timer()]),
+            _ => None,
+        }
+    }
+
+    fn read_register(&self, register: usize) -> Option<String> {
+        self.register_bytes(register).map(|bytes| encode_hex(&bytes))
+    }
+
+    fn read_registers(&self) -> String {
+        (0..REGISTER_COUNT).filter_map(|register| self.read_register(register)).collect()
+    }
+
+    fn set_register(&mut self, register: usize, bytes: &[u8]) -> Option<()> {
+        let cpu = &mut self.debugger.machine.cpu;
+        match (register, bytes) {
+            (0..=15, [value]) => cpu.set_v(register as u8, *value),
+            (16, [low, high]) => cpu.set_i(u16::from_le_bytes([*low, *high])),
+            (17, [low, high]) => cpu.pc = u16::from_le_bytes([*low, *high]),
+            (18, [value]) => cpu.set_delay_timer(*value),
+            (19, [value]) => cpu.set_sound_timer(*value),
+            _ => return None,
+        }
+        Some(())
+    }
+
+    // P<register>=<value>
+    fn write_register(&mut self, args: &str) -> Option<()> {
+        let (register, value) = args.split_once('=')?;
+        self.set_register(usize::from_str_radix(register, 16).ok()?, &decode_hex(value)?)
+    }
+
+    // G<every register>, same layout as g
+    fn write_registers(&mut self, args: &str) -> Option<()> {
+        let bytes = decode_hex(args)?;
+        let mut offset = 0;
+        for register in 0..REGISTER_COUNT {
+            let len = self.register_bytes(register)?.len();
+            self.set_register(register, bytes.get(offset..offset + len)?)?;
+            offset += len;
+        }
+        Some(())
+    }
+
+    // m<address>,<length>
+    fn read_memory(&self, args: &str) -> Option<String> {
+        let (address, len) = parse_address_and_length(args)?;
+        let memory = self.debugger.machine.cpu.memory();
+        memory.get(address..address.checked_add(len)?).map(encode_hex)
+    }
+
+    // M<address>,<length>:<bytes>
+    fn write_memory(&mut self, args: &str) -> Option<()> {
+        let (range, data) = args.split_once(':')?;
+        let (address, len) = parse_address_and_length(range)?;
+        let bytes = decode_hex(data)?;
+        if bytes.len() != len || address.checked_add(len)? > self.debugger.machine.cpu.memory().len() {
+            return None;
+        }
+        bytes.iter().enumerate().for_each(|(offset, byte)| {
+            self.debugger.machine.cpu.poke(address + offset, *byte);
+        });
+        Some(())
+    }
+
+    // Z<type>,<address>,<kind> inserts, z removes: 0/1 break on PC, 2 write, 3 read and 4 any access watchpoints
+    fn breakpoint(&mut self, args: &str, insert: bool) -> Option<()> {
+        let mut fields = args.split(',');
+        let kind = fields.next()?;
+        let address = usize::from_str_radix(fields.next()?, 16).ok()?;
+        let len = fields.next().and_then(|len| usize::from_str_radix(len, 16).ok()).unwrap_or(1).max(1);
+        let end = address.checked_add(len - 1)?;
+        let breakpoint = match kind {
+            "0" | "1" => Breakpoint::Pc(address as u16),
+            "2" => Breakpoint::Watch { start: address, end, read: false, write: true },
+            "3" => Breakpoint::Watch { start: address, end, read: true, write: false },
+            "4" => Breakpoint::Watch { start: address, end, read: true, write: true },
+            _ => return None,
+        };
+
+        if insert {
+            self.debugger.add_breakpoint(breakpoint);
+            return Some(());
+        }
+        let id = self.debugger.breakpoints().iter().find(|(_, existing)| *existing == breakpoint).map(|(id, _)| *id)?;
+        self.debugger.remove_breakpoint(id);
+        Some(())
+    }
+
+    // Serves one client until it detaches or disconnects
+    pub fn serve(&mut self, stream: &mut TcpStream) -> io::Result<()> {
+        loop {
+            let packet = match read_packet(stream)? {
+                Some(packet) => packet,
+                None => return Ok(()),
+            };
+            let packet = match packet {
+                Incoming::Packet(packet) => {
+                    stream.write_all(b"+")?;
+                    packet
+                }
+                // A ctrl-c while already stopped just reports the stop again
+                Incoming::Interrupt => "?".to_string(),
+            };
+
+            let mut interrupted = || poll_interrupt(stream).unwrap_or(false);
+            let reply = self.handle_packet(&packet, &mut interrupted);
+            match reply {
+                Some(reply) => stream.write_all(encode_packet(&reply).as_bytes())?,
+                None => {
+                    stream.write_all(encode_packet("OK").as_bytes())?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+enum Incoming {
+    Packet(String),
+    Interrupt,
+}
+
+// Reads up to the next complete packet, acks are skipped and a bad checksum asks for a resend
+fn read_packet(stream: &mut TcpStream) -> io::Result<Option<Incoming>> {
+    let mut byte = [0_u8];
+    loop {
+        if stream.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        match byte[0] {
+            0x03 => return Ok(Some(Incoming::Interrupt)),
+            b'$' => {}
+            _ => continue,
+        }
+
+        let mut payload = Vec::new();
+        loop {
+            if stream.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            if byte[0] == b'#' {
+                break;
+            }
+            payload.push(byte[0]);
+        }
+        let mut checksum = [0_u8; 2];
+        stream.read_exact(&mut checksum)?;
+
+        let expected = std::str::from_utf8(&checksum).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if expected == Some(checksum_of(&payload)) {
+            return Ok(Some(Incoming::Packet(String::from_utf8_lossy(&payload).into_owned())));
+        }
+        stream.write_all(b"-")?;
+    }
+}
+
+// Non blocking check for a ctrl-c byte from the client
+fn poll_interrupt(stream: &mut TcpStream) -> io::Result<bool> {
+    stream.set_nonblocking(true)?;
+    let mut byte = [0_u8];
+    let result = stream.read(&mut byte);
+    stream.set_nonblocking(false)?;
+    match result {
+        Ok(1) => Ok(byte[0] == 0x03),
+        Ok(_) => Ok(false),
+        Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+fn ok_or_error(result: Option<()>) -> String {
+    match result {
+        Some(()) => "OK".to_string(),
+        None => "E01".to_string(),
+    }
+}
+
+fn parse_address_and_length(args: &str) -> Option<(usize, usize)> {
+    let (address, len) = args.split_once(',')?;
+    Some((usize::from_str_radix(address, 16).ok()?, usize::from_str_radix(len, 16).ok()?))
+}
+
+fn checksum_of(payload: &[u8]) -> u8 {
+    payload.iter().fold(0_u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+pub fn encode_packet(payload: &str) -> String {
+    format!("${}#{:02x}", payload, checksum_of(payload.as_bytes()))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|idx| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok()).collect()
+}
+
+#[cfg(test)]
+#[path ="./gdb_test.rs"]
+mod gdb_test;
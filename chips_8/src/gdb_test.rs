@@ -0,0 +1,148 @@
+use std::net::TcpListener;
+use std::thread;
+
+use super::*;
+use crate::core::machine::Machine;
+use crate::core::ops::MyChips8;
+
+// 200 LD V0, 05 / 202 LD I, 300 / 204 LD [I], V0 / 206 ADD V0, 01 / 208 JP 204
+const ROM: [u8; 10] = [0x60, 0x05, 0xA3, 0x00, 0xF0, 0x55, 0x70, 0x01, 0x12, 0x04];
+
+fn stub() -> GdbStub {
+    let mut cpu = MyChips8::new();
+    cpu.load_rom(&ROM).unwrap();
+    GdbStub::new(Debugger::new(Machine::new(cpu)))
+}
+
+fn send(stub: &mut GdbStub, packet: &str) -> String {
+    stub.handle_packet(packet, &mut || false).unwrap()
+}
+
+#[test]
+fn reads_and_writes_registers() {
+    let mut stub = stub();
+    assert_eq!("S05", send(&mut stub, "?"));
+
+    // V0-VF, I, PC (0x200 little endian), DT, ST
+    assert_eq!(format!("{}{}{}{}", "00".repeat(16), "0000", "0002", "0000"), send(&mut stub, "g"));
+
+    assert_eq!("OK", send(&mut stub, "P3=ab"));
+    assert_eq!("OK", send(&mut stub, "P10=3412"));
+    assert_eq!("ab", send(&mut stub, "p3"));
+    assert_eq!("3412", send(&mut stub, "p10"));
+    assert_eq!(0x1234, stub.debugger.machine.cpu.i());
+    assert_eq!("E01", send(&mut stub, "p14"));
+    assert_eq!("E01", send(&mut stub, "P11=02"));
+
+    let registers = format!("{}{}{}{}", "11".repeat(16), "2203", "0602", "0708");
+    assert_eq!("OK", send(&mut stub, &format!("G{}", registers)));
+    assert_eq!(registers, send(&mut stub, "g"));
+    assert_eq!(0x206, stub.debugger.machine.cpu.pc);
+}
+
+#[test]
+fn reads_and_writes_memory() {
+    let mut stub = stub();
+
+    assert_eq!("6005a300", send(&mut stub, "m200,4"));
+    assert_eq!("OK", send(&mut stub, "M300,2:beef"));
+    assert_eq!("beef", send(&mut stub, "m300,2"));
+    assert_eq!("E01", send(&mut stub, "mfff,2"));
+    assert_eq!("E01", send(&mut stub, "M300,2:be"));
+    assert_eq!("E01", send(&mut stub, "Mffffffffffffffff,1:00"));
+}
+
+#[test]
+fn steps_and_continues_to_breakpoints() {
+    let mut stub = stub();
+
+    assert_eq!("S05", send(&mut stub, "s"));
+    assert_eq!("0202", send(&mut stub, "p11"));
+
+    assert_eq!("OK", send(&mut stub, "Z0,206,2"));
+    assert_eq!("S05", send(&mut stub, "c"));
+    assert_eq!("0602", send(&mut stub, "p11"));
+
+    // Write watchpoint on the store
+    assert_eq!("OK", send(&mut stub, "z0,206,2"));
+    assert_eq!("OK", send(&mut stub, "Z2,300,1"));
+    assert_eq!("S05", send(&mut stub, "c"));
+    assert_eq!("06", send(&mut stub, "m300,1"));
+    assert_eq!("OK", send(&mut stub, "z2,300,1"));
+    assert_eq!("E01", send(&mut stub, "z2,300,1"));
+    assert_eq!("E01", send(&mut stub, "Z2,ffffffffffffffff,2"));
+
+    // Nothing left to stop on, ctrl-c it
+    assert_eq!("S02", stub.handle_packet("c", &mut || true).unwrap());
+    assert_eq!("S02", send(&mut stub, "?"));
+}
+
+#[test]
+fn breakpoints_on_a_chunk_boundary_still_stop() {
+    // 200 ADD V1, 01 / 202 SE V1, 00 / 204 JP 200 / 206 ADD V2, 01 / 208 SE V2, 0D / 20A JP 200 / 20C LD V3, AA / 20E JP 20E
+    let rom = [0x71, 0x01, 0x31, 0x00, 0x12, 0x00, 0x72, 0x01, 0x32, 0x0D, 0x12, 0x00, 0x63, 0xAA, 0x12, 0x0E];
+    let mut cpu = MyChips8::new();
+    cpu.load_rom(&rom).unwrap();
+    let mut stub = GdbStub::new(Debugger::new(Machine::new(cpu)));
+
+    // Nine steps in, 20C is exactly CONTINUE_CHUNK instructions away
+    assert_eq!("OK", send(&mut stub, "Z0,20c,2"));
+    (0..9).for_each(|_| { send(&mut stub, "s"); });
+    // Stepping over it would spin on 20E until the client gives up
+    let mut polls = 0;
+    assert_eq!("S05", stub.handle_packet("c", &mut || { polls += 1; polls > 3 }).unwrap());
+    assert_eq!("0c02", send(&mut stub, "p11"));
+    assert_eq!("00", send(&mut stub, "p3"));
+}
+
+#[test]
+fn reports_faults_and_exit() {
+    let mut cpu = MyChips8::new();
+    cpu.load_rom(&[0x00, 0xFD]).unwrap();
+    let mut stub = GdbStub::new(Debugger::new(Machine::new(cpu)));
+    assert_eq!("W00", send(&mut stub, "c"));
+
+    let mut cpu = MyChips8::new();
+    cpu.load_rom(&[0xFF, 0xFF]).unwrap();
+    let mut stub = GdbStub::new(Debugger::new(Machine::new(cpu)));
+    assert_eq!("S04", send(&mut stub, "s"));
+}
+
+#[test]
+fn monitor_drives_the_keypad() {
+    let mut stub = stub();
+    // "key down a"
+    assert_eq!("OK", send(&mut stub, "qRcmd,6b657920646f776e2061"));
+    assert!(stub.debugger.machine.cpu.is_key_pressed(0xA));
+    assert_eq!("", send(&mut stub, "vMustReplyEmpty"));
+    assert_eq!("", send(&mut stub, "\u{e9}300,2"));
+    assert_eq!("", send(&mut stub, "\u{fffd}"));
+    assert_eq!("", send(&mut stub, ""));
+    assert_eq!(None, stub.handle_packet("D", &mut || false));
+}
+
+#[test]
+fn serves_packets_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        stub().serve(&mut stream).unwrap();
+    });
+
+    let mut client = TcpStream::connect(address).unwrap();
+    let mut exchange = |packet: &str| {
+        client.write_all(encode_packet(packet).as_bytes()).unwrap();
+        let mut reply = String::new();
+        let mut byte = [0_u8];
+        while !reply.contains('#') || reply.len() < reply.find('#').unwrap() + 3 {
+            client.read_exact(&mut byte).unwrap();
+            reply.push(byte[0] as char);
+        }
+        reply
+    };
+
+    assert_eq!("+$6005#cb", exchange("m200,2"));
+    assert_eq!(encode_packet("OK").as_str(), &exchange("D")[1..]);
+    server.join().unwrap();
+}
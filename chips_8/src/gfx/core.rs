@@ -4,6 +4,8 @@ use std::rc::Rc;
 use std::ops::Deref;
 use std::ffi::{ CStr, CString };
 
+use crate::core::framebuffer::{FrameBuffer, HIRES_WIDTH};
+
 pub mod bindings {
     include!(concat!(env!("OUT_DIR"), "/gl_bindings.rs"));
 }
@@ -277,7 +279,7 @@ pub fn get_cstr_from_bytes(file_bytes: &[u8]) -> &CStr {
     }
 }
 
-type PixelValue = (u8, u8, u8, u8);
+pub type PixelValue = (u8, u8, u8, u8);
 type TextureAttribute = (bindings::types::GLenum, bindings::types::GLenum);
 
 // Inefficient yes, but we're going to try!
@@ -492,13 +494,54 @@ pub fn edit_texture(texture: &mut Texture, x_t: (usize, usize), y_t: (usize, usi
     })
 }
 
-pub fn chip_8_texture_to_opengl(texture: &mut Texture, pixel: &[u8]) {
-    (0..32).for_each(|y| {
-        (0..64).for_each(|x| {
-            let is_on = pixel[(y * 64 + x) as usize] == 1;
-            texture.edit_texture_data(x, y, match is_on {
-                true => (255, 255, 255, 255),
-                false => (0, 0, 0, 255)
+// Texture is sized for the 128x64 SUPER-CHIP screen, low resolution pixels are drawn as 2x2 blocks
+pub const CHIP_8_TEXTURE_SIZE: usize = HIRES_WIDTH;
+
+// Colour for each combination of XO-CHIP plane bits: off, plane 1, plane 2, both planes
+pub type Palette = [PixelValue; 4];
+pub const DEFAULT_PALETTE: Palette = [
+    (0, 0, 0, 255),
+    (255, 255, 255, 255),
+    (170, 170, 170, 255),
+    (85, 85, 85, 255),
+];
+
+// Either a preset name or two to four RRGGBB colours separated by commas, missing entries come from the default
+pub fn parse_palette(spec: &str) -> Option<Palette> {
+    match spec.to_ascii_lowercase().as_str() {
+        "default" | "mono" => return Some(DEFAULT_PALETTE),
+        "amber" => return Some([(40, 20, 0, 255), (255, 176, 0, 255), (170, 110, 0, 255), (255, 220, 120, 255)]),
+        "green" => return Some([(15, 56, 15, 255), (155, 188, 15, 255), (48, 98, 48, 255), (139, 172, 15, 255)]),
+        _ => {}
+    }
+
+    let colours: Vec<&str> = spec.split(',').map(|colour| colour.trim().trim_start_matches('#')).collect();
+    if colours.len() < 2 || colours.len() > 4 {
+        return None;
+    }
+    let mut palette = DEFAULT_PALETTE;
+    for (idx, colour) in colours.iter().enumerate() {
+        if colour.len() != 6 {
+            return None;
+        }
+        let rgb = u32::from_str_radix(colour, 16).ok()?;
+        palette[idx] = ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255);
+    }
+    Some(palette)
+}
+
+pub fn chip_8_texture_to_opengl(texture: &mut Texture, gfx: &FrameBuffer, palette: &Palette) {
+    let scale = CHIP_8_TEXTURE_SIZE / gfx.width();
+    (0..gfx.height()).for_each(|y| {
+        (0..gfx.width()).for_each(|x| {
+            let pixel = match palette.get(gfx.get(x, y) as usize) {
+                Some(colour) => *colour,
+                None => (255, 0, 0, 255)  // Marker for pixels at incorrect value
+            };
+            (0..scale).for_each(|offset_y| {
+                (0..scale).for_each(|offset_x| {
+                    texture.edit_texture_data(x * scale + offset_x, y * scale + offset_y, pixel)
+                })
             })
         })
     })
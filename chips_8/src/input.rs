@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use glutin::event::VirtualKeyCode;
+
+// The usual left hand layout, written in keypad order from 0x0 to 0xF
+//   1 2 3 C      1 2 3 4
+//   4 5 6 D  ->  Q W E R
+//   7 8 9 E      A S D F
+//   A 0 B F      Z X C V
+pub const DEFAULT_LAYOUT: &str = "X123QWEASDZC4RFV";
+
+// Maps host keyboard keys onto the 16 key hex keypad
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap {
+    keys: HashMap<VirtualKeyCode, u8>,
+}
+
+impl KeyMap {
+    pub fn new() -> KeyMap {
+        KeyMap { keys: HashMap::new() }
+    }
+
+    // Sixteen different letters or digits, the first one is bound to 0x0 and the last to 0xF
+    pub fn from_layout(layout: &str) -> Option<KeyMap> {
+        let mut key_map = KeyMap::new();
+        for (hex, c) in layout.chars().enumerate() {
+            if hex > 0xF {
+                return None;
+            }
+            // A key used twice would leave one of the keypad keys unreachable
+            if key_map.keys.insert(key_code_for(c)?, hex as u8).is_some() {
+                return None;
+            }
+        }
+
+        if key_map.keys.len() == 0x10 { Some(key_map) } else { None }
+    }
+
+    pub fn bind(&mut self, key_code: VirtualKeyCode, hex: u8) {
+        self.keys.insert(key_code, hex & 0xF);
+    }
+
+    pub fn get(&self, key_code: VirtualKeyCode) -> Option<u8> {
+        self.keys.get(&key_code).copied()
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        KeyMap::from_layout(DEFAULT_LAYOUT).unwrap()
+    }
+}
+
+fn key_code_for(c: char) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+
+    let key_code = match c.to_ascii_uppercase() {
+        '0' => Key0, '1' => Key1, '2' => Key2, '3' => Key3, '4' => Key4,
+        '5' => Key5, '6' => Key6, '7' => Key7, '8' => Key8, '9' => Key9,
+        'A' => A, 'B' => B, 'C' => C, 'D' => D, 'E' => E, 'F' => F, 'G' => G,
+        'H' => H, 'I' => I, 'J' => J, 'K' => K, 'L' => L, 'M' => M, 'N' => N,
+        'O' => O, 'P' => P, 'Q' => Q, 'R' => R, 'S' => S, 'T' => T, 'U' => U,
+        'V' => V, 'W' => W, 'X' => X, 'Y' => Y, 'Z' => Z,
+        _ => return None,
+    };
+    Some(key_code)
+}
+
+#[cfg(test)]
+#[path ="./input_test.rs"]
+mod input_test;
@@ -0,0 +1,31 @@
+use super::*;
+
+#[test]
+fn default_layout_matches_the_keypad() {
+    let key_map = KeyMap::default();
+
+    assert_eq!(Some(0x1), key_map.get(VirtualKeyCode::Key1));
+    assert_eq!(Some(0xC), key_map.get(VirtualKeyCode::Key4));
+    assert_eq!(Some(0x0), key_map.get(VirtualKeyCode::X));
+    assert_eq!(Some(0xF), key_map.get(VirtualKeyCode::V));
+    assert_eq!(None, key_map.get(VirtualKeyCode::Key5));
+}
+
+#[test]
+fn custom_layouts() {
+    let key_map = KeyMap::from_layout("0123456789abcdef").unwrap();
+    assert_eq!(Some(0x9), key_map.get(VirtualKeyCode::Key9));
+    assert_eq!(Some(0xA), key_map.get(VirtualKeyCode::A));
+
+    assert_eq!(None, KeyMap::from_layout("0123"));
+    assert_eq!(None, KeyMap::from_layout("0123456789abcdefg"));
+    assert_eq!(None, KeyMap::from_layout("0123456789abcde!"));
+}
+
+#[test]
+fn layouts_with_repeated_keys_are_rejected() {
+    assert_eq!(None, KeyMap::from_layout("XXXXXXXXXXXXXXXX"));
+    assert_eq!(None, KeyMap::from_layout("0123456789abcdea"));
+    // Letters are matched without case, so these are the same key
+    assert_eq!(None, KeyMap::from_layout("0123456789abcdeA"));
+}
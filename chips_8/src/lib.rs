@@ -1,3 +1,9 @@
 pub mod gfx;
 pub mod scenes;
-pub mod core;
\ No newline at end of file
+pub mod core;
+pub mod gdb;
+pub mod input;
+
+// Lets code generated by core::recompile, which names the crate, be compiled into the tests
+#[cfg(test)]
+extern crate self as chips_8;
@@ -1,20 +1,170 @@
-use glutin::event::{Event, WindowEvent};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::time::{Duration, Instant};
+
+use glutin::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
 use glutin::event_loop::{ControlFlow, EventLoopBuilder};
 use glutin::window::WindowBuilder;
 use glutin::ContextBuilder;
 
-use chips_8::gfx::core::{load_gl, chip_8_texture_to_opengl};
+use chips_8::gfx::core::{load_gl, chip_8_texture_to_opengl, parse_palette, Palette, DEFAULT_PALETTE};
 use chips_8::scenes::textured::create_scene_with_chips_8_text;
+use chips_8::core::audio::{Audio, AudioSettings, AudioSink, BellSink, WavSink, DEFAULT_SAMPLE_RATE};
+use chips_8::core::machine::{Machine, DEFAULT_INSTRUCTIONS_PER_SECOND, FRAMES_PER_SECOND};
+use chips_8::core::movie::Movie;
 use chips_8::core::ops::MyChips8;
+use chips_8::core::quirks::Quirks;
+use chips_8::core::random::XorShift;
+use chips_8::core::rewind::RewindBuffer;
+use chips_8::core::savestate::SaveState;
+use chips_8::input::{KeyMap, DEFAULT_LAYOUT};
+
+// F5 writes the quick save, F9 loads it back
+const QUICK_SAVE_PATH: &str = "chips_8.state";
+
+const USAGE: &str = "Usage: chips_8 <rom> [options]
+
+Options:
+  --quirks NAME      vip, chip48, schip, xo-chip or original (default original)
+  --speed N          Instructions per second (default 700)
+  --palette COLOURS  default, amber, green or 2-4 RRGGBB colours, e.g. 000000,FFB000
+  --scale N          Window pixels per CHIP-8 pixel (default 4)
+  --keys LAYOUT      Host keys for hex keys 0-F in order (default X123QWEASDZC4RFV)
+  --rewind SECONDS   How far backspace can rewind (default 10)
+  --record FILE      Record a movie of the session, written when the window closes, chip8-headless --movie replays it
+  --wav FILE         Write the sound to a WAV file when the window closes, the terminal bell rings otherwise
+  --volume N         Tone volume in percent (default 25)
+  --tone HZ          Square wave frequency in Hz (default 440)
+  --mute             Start with the sound off
+
+Keys: F5 quick-save, F9 quick-load, hold backspace to rewind, F8 toggles sound";
+
+struct Options {
+    rom: PathBuf,
+    quirks: Quirks,
+    speed: u64,
+    palette: Palette,
+    scale: u32,
+    key_map: KeyMap,
+    rewind_seconds: u64,
+    record: Option<PathBuf>,
+    wav: Option<PathBuf>,
+    audio: AudioSettings,
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<u64, String> {
+    let value = value.ok_or(format!("{} needs a value", flag))?;
+    value.parse().map_err(|_| format!("{} expects a number, found `{}`", flag, value))
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        rom: PathBuf::new(),
+        quirks: Quirks::default(),
+        speed: DEFAULT_INSTRUCTIONS_PER_SECOND,
+        palette: DEFAULT_PALETTE,
+        scale: 4,
+        key_map: KeyMap::default(),
+        rewind_seconds: 10,
+        record: None,
+        wav: None,
+        audio: AudioSettings::default(),
+    };
+    let mut rom = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--speed" => options.speed = parse_number(&arg, args.next())?.max(1),
+            "--scale" => options.scale = parse_number(&arg, args.next())?.clamp(1, 16) as u32,
+            "--rewind" => options.rewind_seconds = parse_number(&arg, args.next())?,
+            "--record" => options.record = Some(PathBuf::from(args.next().ok_or("--record needs a file")?)),
+            "--wav" => options.wav = Some(PathBuf::from(args.next().ok_or("--wav needs a file")?)),
+            "--volume" => options.audio.volume = parse_number(&arg, args.next())?.min(100) as f32 / 100.0,
+            "--tone" => options.audio.frequency = parse_number(&arg, args.next())? as f32,
+            "--mute" => options.audio.muted = true,
+            "--quirks" => {
+                let name = args.next().ok_or("--quirks needs a value")?;
+                options.quirks = Quirks::from_name(&name).ok_or(format!("Unknown quirk profile `{}`", name))?;
+            }
+            "--palette" => {
+                let spec = args.next().ok_or("--palette needs a value")?;
+                options.palette = parse_palette(&spec).ok_or(format!("Bad palette `{}`", spec))?;
+            }
+            "--keys" => {
+                let layout = args.next().ok_or("--keys needs a value")?;
+                options.key_map = KeyMap::from_layout(&layout)
+                    .ok_or(format!("--keys expects 16 different letters or digits like {}, found `{}`", DEFAULT_LAYOUT, layout))?;
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option `{}`", arg)),
+            _ if rom.is_none() => rom = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument `{}`", arg)),
+        }
+    }
+
+    options.rom = rom.ok_or(USAGE)?;
+    Ok(options)
+}
+
+fn quick_save(cpu: &MyChips8) -> Result<(), Box<dyn Error>> {
+    fs::write(QUICK_SAVE_PATH, cpu.snapshot().to_bytes())?;
+    Ok(())
+}
+
+fn quick_load(cpu: &mut MyChips8) -> Result<(), Box<dyn Error>> {
+    let state = SaveState::from_bytes(&fs::read(QUICK_SAVE_PATH)?)?;
+    cpu.restore(&state);
+    Ok(())
+}
 
 fn main() {
-    let mut chips_8_state = MyChips8::new();
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    };
+    let rom = match fs::read(&options.rom) {
+        Ok(rom) => rom,
+        Err(err) => {
+            eprintln!("Could not read {}: {}", options.rom.display(), err);
+            process::exit(1);
+        }
+    };
+
+    let mut chips_8_state = MyChips8::new_with_quirks(options.quirks);
+    if let Err(err) = chips_8_state.load_rom(&rom) {
+        eprintln!("{}: {}", options.rom.display(), err);
+        process::exit(1);
+    }
+    // A movie needs to know the seed, so recordings pick one up front instead of leaving it to the CPU
+    let mut movie = options.record.as_ref().map(|_| {
+        let seed = XorShift::from_time().next_u64();
+        chips_8_state.set_random_source(Box::new(XorShift::new(seed)));
+        Movie::new(&rom, seed, options.quirks, options.speed)
+    });
+    let record_path = options.record;
+    // No sound device is opened, the tone goes to a WAV file or rings the terminal bell
+    let sink: Box<dyn AudioSink> = match &options.wav {
+        Some(path) => Box::new(WavSink::to_file(path, DEFAULT_SAMPLE_RATE)),
+        None => Box::new(BellSink::default()),
+    };
+    let mut audio = Audio::new(sink);
+    audio.settings = options.audio;
+    let wav_path = options.wav;
+    let key_map = options.key_map;
+    let palette = options.palette;
+    let frame_duration = Duration::from_nanos(1_000_000_000 / FRAMES_PER_SECOND);
 
     let el = EventLoopBuilder::new().build();
     let wb = WindowBuilder::new()
                                             .with_title("Chips 8")
                                             .with_inner_size(
-                                                glutin::dpi::LogicalSize::new(512, 512));
+                                                // The display quad covers half of the window and the top half of its texture
+                                                glutin::dpi::LogicalSize::new(128 * options.scale, 128 * options.scale));
 
     let windowed_context = ContextBuilder::new().build_windowed(wb, &el).unwrap();
     let windowed_context = unsafe { windowed_context.make_current().unwrap() };
@@ -22,44 +172,136 @@ fn main() {
     println!("Pixel format of the window's GL context: {:?}", windowed_context.get_pixel_format());
     let gl = load_gl(&windowed_context.context());
 
-    let mut scene = create_scene_with_chips_8_text(&gl, &chips_8_state.gfx);
-    let rom = include_bytes!("IBM_Logo.ch8");
-    chips_8_state.load_rom(rom);
+    let mut scene = create_scene_with_chips_8_text(&gl, &chips_8_state.gfx, &palette);
 
-    let mut wait_next_loop = false;
+    let mut machine = Machine::new_with_speed(chips_8_state, options.speed);
+    let mut next_frame = Instant::now();
+    let mut rewind = RewindBuffer::new(options.rewind_seconds);
+    let mut rewinding = false;
+    let mut halted = false;
 
     el.run(move |event, _, control_flow| {
-        if wait_next_loop {
-            *control_flow = ControlFlow::Wait;
-        }
-
         match event {
-            Event::LoopDestroyed => return,
+            Event::LoopDestroyed => {
+                if let (Some(movie), Some(path)) = (&mut movie, &record_path) {
+                    movie.frames = machine.frame();
+                    match fs::write(path, movie.to_string()) {
+                        Ok(()) => println!("Movie written to {}", path.display()),
+                        Err(err) => println!("Could not write {}: {}", path.display(), err),
+                    }
+                }
+                match (audio.flush(), &wav_path) {
+                    (Ok(()), Some(path)) => println!("Sound written to {}", path.display()),
+                    (Err(err), Some(path)) => println!("Could not write {}: {}", path.display(), err),
+                    _ => {}
+                }
+            }
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::Resized(physical_size) => windowed_context.resize(physical_size),
                 WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+                WindowEvent::KeyboardInput {
+                    input: KeyboardInput { virtual_keycode: Some(key_code), state, .. },
+                    ..
+                } => {
+                    if state == ElementState::Pressed && key_code == VirtualKeyCode::F5 {
+                        match quick_save(&machine.cpu) {
+                            Ok(()) => println!("Saved state to {}", QUICK_SAVE_PATH),
+                            Err(err) => println!("Quick save failed: {}", err),
+                        }
+                    } else if state == ElementState::Pressed && key_code == VirtualKeyCode::F8 {
+                        audio.settings.muted = !audio.settings.muted;
+                        println!("Sound {}", if audio.settings.muted { "off" } else { "on" });
+                    } else if movie.is_some() && (key_code == VirtualKeyCode::F9 || key_code == VirtualKeyCode::Back) {
+                        // Jumping around in time would leave the movie describing a session that never happened
+                        if state == ElementState::Pressed {
+                            println!("Quick load and rewind are off while recording");
+                        }
+                    } else if state == ElementState::Pressed && key_code == VirtualKeyCode::F9 {
+                        match quick_load(&mut machine.cpu) {
+                            Ok(()) => {
+                                println!("Loaded state from {}", QUICK_SAVE_PATH);
+                                // A restored state may come from a running program, pick it back up
+                                halted = false;
+                                windowed_context.window().set_title("Chips 8");
+                                if let Some(texture) = &mut scene.objects[0].texture {
+                                    chip_8_texture_to_opengl(texture, &machine.cpu.gfx, &palette);
+                                }
+                                scene.render_scene_objects(&gl);
+                                windowed_context.window().request_redraw();
+                            }
+                            Err(err) => println!("Quick load failed: {}", err),
+                        }
+                    } else if key_code == VirtualKeyCode::Back {
+                        rewinding = state == ElementState::Pressed;
+                    } else if let Some(hex) = key_map.get(key_code) {
+                        let pressed = state == ElementState::Pressed;
+                        // Held keys repeat, only actual changes go into the movie
+                        if let Some(movie) = movie.as_mut().filter(|_| machine.cpu.is_key_pressed(hex) != pressed) {
+                            movie.record(machine.frame(), hex, pressed);
+                        }
+                        if pressed {
+                            machine.cpu.press_key(hex);
+                        } else {
+                            machine.cpu.release_key(hex);
+                        }
+                    }
+                },
                 _ => (),
             },
             Event::RedrawRequested(_) => {
                 gl.draw_frame([0.2, 0.3, 0.3, 1.0], &scene);
                 windowed_context.swap_buffers().unwrap();
             },
-            Event::Resumed => {
-                println!("Resumed");
-                wait_next_loop = false;
-                chips_8_state.wait = false;
-            },
             Event::MainEventsCleared => {
-                chips_8_state.enumlate_cycle();
+                // A faulted or exited program stays halted, the window is kept so the last frame can be inspected
+                if halted && !rewinding {
+                    *control_flow = ControlFlow::Wait;
+                    return;
+                }
 
-                if chips_8_state.wait {
-                    wait_next_loop = true;
+                // Frames run on wall clock time, the emulated speed is handled by the machine
+                let now = Instant::now();
+                if now < next_frame {
+                    *control_flow = ControlFlow::WaitUntil(next_frame);
+                    return;
                 }
+                // Fell too far behind (window dragged, debugger attached), pick up from now instead of catching up
+                next_frame = if now - next_frame > frame_duration * 4 { now } else { next_frame } + frame_duration;
+                *control_flow = ControlFlow::WaitUntil(next_frame);
+
+                let drew = if rewinding {
+                    match rewind.step_back() {
+                        Some(state) => {
+                            machine.cpu.restore(&state);
+                            halted = false;
+                            windowed_context.window().set_title("Chips 8");
+                            true
+                        }
+                        None => false,
+                    }
+                } else {
+                    let outcome = match machine.run_frame() {
+                        Ok(outcome) => outcome,
+                        Err(err) => {
+                            println!("Halted: {} | {:?}", err, machine.cpu);
+                            windowed_context.window().set_title(&format!("Chips 8 - {}", err));
+                            halted = true;
+                            return;
+                        }
+                    };
+                    if outcome.exited {
+                        windowed_context.window().set_title("Chips 8 - Exited");
+                        halted = true;
+                    }
+                    audio.run_frame(&machine.cpu);
+                    rewind.record(&machine.cpu.snapshot());
+                    outcome.drew
+                };
 
-                if chips_8_state.draw {
-                    chips_8_state.draw = false;
+                if drew {
+                    machine.cpu.draw = false;
                     if let Some(texture) = &mut scene.objects[0].texture {
-                        chip_8_texture_to_opengl(texture, &chips_8_state.gfx);
+                        chip_8_texture_to_opengl(texture, &machine.cpu.gfx, &palette);
                     }
                     scene.render_scene_objects(&gl);
                     windowed_context.window().request_redraw();
@@ -1,6 +1,7 @@
 use std::ffi::CString;
 
-use crate::gfx::core::{Object, Scene, edit_texture, render_object};
+use crate::core::framebuffer::FrameBuffer;
+use crate::gfx::core::{Object, Scene, edit_texture, render_object, chip_8_texture_to_opengl, CHIP_8_TEXTURE_SIZE, Palette};
 use crate::gfx::core::bindings as bindings;
 
 fn get_quad_verts() -> [f32; 32] {
@@ -79,7 +80,7 @@ pub fn create_textured_scene(gl: &bindings::Gl) -> Scene {
 
 // Little too specific for me but oh well
 // Yes this violates DRY to a degree but I don't feel like fighting the borrow checker right now
-pub fn create_scene_with_chips_8_text(gl: &bindings::Gl, pixels: &[u8; 64 * 32]) -> Scene {
+pub fn create_scene_with_chips_8_text(gl: &bindings::Gl, gfx: &FrameBuffer, palette: &Palette) -> Scene {
     let mut objects = Vec::with_capacity(1); // I know there will only be one so no need to waste here
 
     let rectangle_verts = [
@@ -106,8 +107,8 @@ pub fn create_scene_with_chips_8_text(gl: &bindings::Gl, pixels: &[u8; 64 * 32])
         Box::new(attributes), 
         frag_src, 
         vert_src,
-        64,
-        64, // The real height is 32, but OpenGL needs them to be the same
+        CHIP_8_TEXTURE_SIZE,
+        CHIP_8_TEXTURE_SIZE, // The real height is half of this, but OpenGL needs them to be the same
         bindings::TEXTURE_2D,
         vec![
                         (bindings::TEXTURE_WRAP_S, bindings::CLAMP_TO_EDGE),
@@ -118,18 +119,7 @@ pub fn create_scene_with_chips_8_text(gl: &bindings::Gl, pixels: &[u8; 64 * 32])
     );
 
     if let Some(texture) = &mut rectangle.texture {
-        (0..64)
-            .for_each(|x| {
-                (0..32).for_each(|y| {
-                    let value_at_pixel = pixels[(63 - x) * (31 -y) as usize];
-                    
-                    texture.edit_texture_data(x, y, match value_at_pixel {
-                        1 => (255, 255, 255, 255),
-                        0 => (0, 0, 0, 255),
-                        _ => (255, 0, 0, 255)  // Marker for pixels at incorrect value
-                    })
-                })
-            });
+        chip_8_texture_to_opengl(texture, gfx, palette);
     }
 
     match render_object(gl, &mut rectangle) {